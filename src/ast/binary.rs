@@ -0,0 +1,654 @@
+/*
+ * Dermis is an interpreter for a pure, statically typed, imperitive language designed to be edited with a custom IDE.
+ * Copyright (C) 2018 Amelorate
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! A compact binary encoding of dermis values, and so of the AST.
+//!
+//! The encoding starts with the magic bytes `DRMB`, followed by a table of every symbol used in
+//! the value and then the value itself, which refers to symbols by their index in the table.
+//! Each symbol is only stored once, no matter how often it is used.
+//!
+//! All integers are unsigned LEB128, and strings are their length in bytes followed by their
+//! UTF-8 bytes.
+//!
+//! The symbol table is the number of symbols followed by each symbol, which is the index of it's
+//! namespace plus one, or zero for a global symbol, and then it's name. A namespace always comes
+//! before the symbols in it. An anonymous symbol has no namespace and an empty name, followed by
+//! it's id.
+//!
+//! A value is a tag byte followed by it's contents:
+//!
+//! | Tag | Value    | Contents                                                             |
+//! |-----|----------|----------------------------------------------------------------------|
+//! | 0   | Number   | 8 bytes of a little endian `f64`                                     |
+//! | 1   | String   | a string                                                             |
+//! | 2   | Symbol   | an index into the symbol table                                       |
+//! | 3   | Array    | the number of elements, followed by each element                     |
+//! | 4   | Object   | the number of entries, followed by each key and value, sorted by key |
+//! | 5   | Function | the parameters, the body encoded as a value, and the captures        |
+//!
+//! Values can be nested at most [`MAX_DEPTH`](MAX_DEPTH) deep.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::io::{Read, Write};
+use std::sync::Arc;
+
+use ast::decode::DecodeError;
+use ast::expression::Expression;
+use value::owned::symbol::AnonymousOwnedSymbol;
+use value::symbol::{self, SymbolError};
+use value::{OwnedArray, OwnedFunction, OwnedObject, OwnedSymbol, OwnedValue};
+
+const MAGIC: &[u8; 4] = b"DRMB";
+
+const NUMBER: u8 = 0;
+const STRING: u8 = 1;
+const SYMBOL: u8 = 2;
+const ARRAY: u8 = 3;
+const OBJECT: u8 = 4;
+const FUNCTION: u8 = 5;
+
+/// How deeply values can be nested, so that reading a value can not overflow the stack.
+///
+/// Values nested deeper than this are not written either, so anything written can be read back.
+pub const MAX_DEPTH: usize = 128;
+
+/// An error that occured while reading or writing a binary encoded value.
+#[derive(Debug)]
+pub enum BinaryError {
+    Io(io::Error),
+    /// The data did not start with the magic bytes `DRMB`.
+    NotBinary,
+    /// A value had a tag that does not name a type of value.
+    UnknownTag(u8),
+    /// A symbol index was past the end of the symbol table, or a symbol's namespace came after
+    /// it in the table.
+    UnknownSymbol(u64),
+    /// A symbol's name was not valid, see [`check_name`](::value::symbol::check_name).
+    InvalidSymbolName(String, SymbolError),
+    /// A string was not valid UTF-8.
+    InvalidString,
+    /// A number was NaN.
+    NotANumber,
+    /// Values were nested more than [`MAX_DEPTH`](MAX_DEPTH) deep.
+    TooDeep,
+    /// The body of a function was not a valid AST.
+    Decode(DecodeError),
+}
+
+impl Display for BinaryError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            BinaryError::Io(err) => write!(f, "{}", err),
+            BinaryError::NotBinary => write!(f, "not a binary dermis value"),
+            BinaryError::UnknownTag(tag) => write!(f, "unknown value tag {}", tag),
+            BinaryError::UnknownSymbol(i) => write!(f, "unknown symbol index {}", i),
+            BinaryError::InvalidSymbolName(name, err) => {
+                write!(f, "invalid symbol name \"{}\": {}", name, err)
+            }
+            BinaryError::InvalidString => write!(f, "string is not valid UTF-8"),
+            BinaryError::NotANumber => write!(f, "number is NaN"),
+            BinaryError::TooDeep => write!(f, "values are nested more than {} deep", MAX_DEPTH),
+            BinaryError::Decode(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl Error for BinaryError {}
+
+impl From<io::Error> for BinaryError {
+    fn from(err: io::Error) -> BinaryError {
+        BinaryError::Io(err)
+    }
+}
+
+impl From<DecodeError> for BinaryError {
+    fn from(err: DecodeError) -> BinaryError {
+        BinaryError::Decode(err)
+    }
+}
+
+/// Writes the binary encoding of `val` to `writer`.
+///
+/// # Example
+/// ```
+/// #[macro_use]
+/// extern crate dermis;
+/// use dermis::ast::binary;
+/// use dermis::value::{OwnedArray, OwnedValue};
+///
+/// # fn main() {
+/// let val: OwnedValue = OwnedArray::from(vec![
+///     symbol_o!(foo;bar).into(),
+///     symbol_o!(foo;bar).into(),
+///     "baz".into(),
+/// ])
+/// .into();
+///
+/// let mut bytes = vec![];
+/// binary::write(&mut bytes, &val).unwrap();
+///
+/// assert_eq!(binary::read(&bytes[..]).unwrap(), val);
+/// # }
+/// ```
+///
+/// # Errors
+/// [`BinaryError::TooDeep`](BinaryError::TooDeep) if `val` is nested more than
+/// [`MAX_DEPTH`](MAX_DEPTH) deep, after the part of `val` before that has been written.
+pub fn write<W: Write>(mut writer: W, val: &OwnedValue) -> Result<(), BinaryError> {
+    let mut table = SymbolTable::default();
+    table.add_value(val);
+
+    writer.write_all(MAGIC)?;
+    write_uint(&mut writer, table.symbols.len() as u64)?;
+    for sym in &table.symbols {
+        let namespace = match sym.get_namespace() {
+            Some(ns) => table.indices[ns] + 1,
+            None => 0,
+        };
+        write_uint(&mut writer, namespace)?;
+        write_str(&mut writer, sym.get_name())?;
+        if let OwnedSymbol::Anonymous(AnonymousOwnedSymbol { id }) = sym {
+            write_uint(&mut writer, *id)?;
+        }
+    }
+    write_value(&mut writer, &table, val, 1)
+}
+
+/// Reads a value from it's binary encoding.
+pub fn read<R: Read>(mut reader: R) -> Result<OwnedValue, BinaryError> {
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+    if magic != *MAGIC {
+        return Err(BinaryError::NotBinary);
+    }
+
+    let mut symbols: Vec<OwnedSymbol> = vec![];
+    for _ in 0..read_uint(&mut reader)? {
+        let namespace = read_uint(&mut reader)?;
+        let name = read_string(&mut reader)?;
+        if namespace == 0 && name.is_empty() {
+            let id = read_uint(&mut reader)?;
+            symbols.push(OwnedSymbol::Anonymous(AnonymousOwnedSymbol { id }));
+            continue;
+        }
+        if let Err(err) = symbol::check_name(&name) {
+            return Err(BinaryError::InvalidSymbolName(name, err));
+        }
+        let sym = match namespace {
+            0 => OwnedSymbol::new_global(name),
+            i => OwnedSymbol::new_local(name, symbol_at(&symbols, i - 1)?.clone()),
+        };
+        symbols.push(sym);
+    }
+    read_value(&mut reader, &symbols, 1)
+}
+
+/// Writes the binary encoding of an expression, see [`write`](write).
+pub fn write_expression<W: Write>(writer: W, expr: &Expression) -> Result<(), BinaryError> {
+    write(writer, &expr.clone().into())
+}
+
+/// Reads an expression from it's binary encoding.
+pub fn read_expression<R: Read>(reader: R) -> Result<Expression, BinaryError> {
+    Ok(Expression::try_from(read(reader)?)?)
+}
+
+/// The symbols used in a value, each with it's index.
+#[derive(Default)]
+struct SymbolTable {
+    symbols: Vec<OwnedSymbol>,
+    indices: HashMap<OwnedSymbol, u64>,
+}
+
+impl SymbolTable {
+    fn add(&mut self, sym: &OwnedSymbol) {
+        if self.indices.contains_key(sym) {
+            return;
+        }
+        if let Some(ns) = sym.get_namespace() {
+            self.add(ns);
+        }
+        self.indices.insert(sym.clone(), self.symbols.len() as u64);
+        self.symbols.push(sym.clone());
+    }
+
+    fn add_value(&mut self, val: &OwnedValue) {
+        match val {
+            OwnedValue::Number(_) | OwnedValue::String(_) => (),
+            OwnedValue::Symbol(sym) => self.add(sym),
+            OwnedValue::Array(arr) => {
+                for v in arr.iter() {
+                    self.add_value(&v)
+                }
+            }
+            OwnedValue::Object(obj) => {
+                for (k, v) in obj.iter() {
+                    self.add_value(&k);
+                    self.add_value(&v);
+                }
+            }
+            OwnedValue::Function(fun) => {
+                for p in &fun.params {
+                    self.add(p);
+                }
+                self.add_value(&(*fun.body).clone().into());
+                for (name, v) in &fun.captures {
+                    self.add(name);
+                    self.add_value(v);
+                }
+            }
+        }
+    }
+}
+
+/// Writes a value that is nested `depth` deep, counting the value itself.
+fn write_value<W: Write>(
+    w: &mut W,
+    table: &SymbolTable,
+    val: &OwnedValue,
+    depth: usize,
+) -> Result<(), BinaryError> {
+    if depth > MAX_DEPTH {
+        return Err(BinaryError::TooDeep);
+    }
+    match val {
+        OwnedValue::Number(n) => {
+            w.write_all(&[NUMBER])?;
+            w.write_all(&n.into_inner().to_bits().to_le_bytes())?;
+        }
+        OwnedValue::String(s) => {
+            w.write_all(&[STRING])?;
+            write_str(w, s)?;
+        }
+        OwnedValue::Symbol(sym) => {
+            w.write_all(&[SYMBOL])?;
+            write_uint(w, table.indices[sym])?;
+        }
+        OwnedValue::Array(arr) => {
+            w.write_all(&[ARRAY])?;
+            write_uint(w, arr.len() as u64)?;
+            for v in arr.iter() {
+                write_value(w, table, &v, depth + 1)?;
+            }
+        }
+        OwnedValue::Object(obj) => {
+            w.write_all(&[OBJECT])?;
+            write_uint(w, obj.len() as u64)?;
+            let mut entries: Vec<(Arc<OwnedValue>, Arc<OwnedValue>)> = obj.iter().collect();
+            entries.sort();
+            for (k, v) in entries {
+                write_value(w, table, &k, depth + 1)?;
+                write_value(w, table, &v, depth + 1)?;
+            }
+        }
+        OwnedValue::Function(fun) => {
+            w.write_all(&[FUNCTION])?;
+            write_uint(w, fun.params.len() as u64)?;
+            for p in &fun.params {
+                write_uint(w, table.indices[p])?;
+            }
+            write_value(w, table, &(*fun.body).clone().into(), depth + 1)?;
+            write_uint(w, fun.captures.len() as u64)?;
+            for (name, v) in &fun.captures {
+                write_uint(w, table.indices[name])?;
+                write_value(w, table, v, depth + 1)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads a value that is nested `depth` deep, counting the value itself.
+fn read_value<R: Read>(
+    r: &mut R,
+    symbols: &[OwnedSymbol],
+    depth: usize,
+) -> Result<OwnedValue, BinaryError> {
+    if depth > MAX_DEPTH {
+        return Err(BinaryError::TooDeep);
+    }
+    let mut tag = [0];
+    r.read_exact(&mut tag)?;
+    match tag[0] {
+        NUMBER => {
+            let mut bytes = [0; 8];
+            r.read_exact(&mut bytes)?;
+            let n = f64::from_bits(u64::from_le_bytes(bytes));
+            if n.is_nan() {
+                return Err(BinaryError::NotANumber);
+            }
+            Ok(n.into())
+        }
+        STRING => Ok(OwnedValue::String(read_string(r)?)),
+        SYMBOL => Ok(read_symbol(r, symbols)?.into()),
+        ARRAY => {
+            let mut arr = vec![];
+            for _ in 0..read_uint(r)? {
+                arr.push(read_value(r, symbols, depth + 1)?);
+            }
+            Ok(OwnedArray::from(arr).into())
+        }
+        OBJECT => {
+            let mut obj = OwnedObject::empty();
+            for _ in 0..read_uint(r)? {
+                let k = read_value(r, symbols, depth + 1)?;
+                let v = read_value(r, symbols, depth + 1)?;
+                obj.set_mut(k, v);
+            }
+            Ok(obj.into())
+        }
+        FUNCTION => {
+            let mut params = vec![];
+            for _ in 0..read_uint(r)? {
+                params.push(read_symbol(r, symbols)?);
+            }
+            let body = Expression::try_from(read_value(r, symbols, depth + 1)?)?;
+            let mut captures = vec![];
+            for _ in 0..read_uint(r)? {
+                let name = read_symbol(r, symbols)?;
+                captures.push((name, read_value(r, symbols, depth + 1)?));
+            }
+            Ok(OwnedFunction {
+                params,
+                body: Box::new(body),
+                captures,
+            }
+            .into())
+        }
+        tag => Err(BinaryError::UnknownTag(tag)),
+    }
+}
+
+fn symbol_at(symbols: &[OwnedSymbol], i: u64) -> Result<&OwnedSymbol, BinaryError> {
+    symbols.get(i as usize).ok_or(BinaryError::UnknownSymbol(i))
+}
+
+fn read_symbol<R: Read>(r: &mut R, symbols: &[OwnedSymbol]) -> Result<OwnedSymbol, BinaryError> {
+    let i = read_uint(r)?;
+    Ok(symbol_at(symbols, i)?.clone())
+}
+
+fn write_uint<W: Write>(w: &mut W, mut n: u64) -> io::Result<()> {
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            return w.write_all(&[byte]);
+        }
+        w.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn read_uint<R: Read>(r: &mut R) -> Result<u64, BinaryError> {
+    let mut n = 0;
+    let mut shift = 0;
+    loop {
+        let mut byte = [0];
+        r.read_exact(&mut byte)?;
+        if shift >= 64 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "integer is too large").into());
+        }
+        n |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(n);
+        }
+        shift += 7;
+    }
+}
+
+fn write_str<W: Write>(w: &mut W, s: &str) -> io::Result<()> {
+    write_uint(w, s.len() as u64)?;
+    w.write_all(s.as_bytes())
+}
+
+fn read_string<R: Read>(r: &mut R) -> Result<String, BinaryError> {
+    let len = read_uint(r)?;
+    let mut bytes = vec![];
+    r.take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 != len {
+        return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+    }
+    String::from_utf8(bytes).map_err(|_| BinaryError::InvalidString)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ast::expression::CondDisplay;
+    use eval::Env;
+    use serde_json;
+    use value::{Function, Value};
+
+    fn round_trip(val: OwnedValue) -> Vec<u8> {
+        let mut bytes = vec![];
+        write(&mut bytes, &val).unwrap();
+
+        assert_eq!(read(&bytes[..]).unwrap(), val);
+        bytes
+    }
+
+    fn var(name: OwnedSymbol) -> Box<Expression> {
+        Box::new(Expression::Var(name))
+    }
+
+    /// A program that uses a few long symbols many times, like most programs.
+    fn program() -> Expression {
+        let body = (0..20).fold(Expression::Nop, |acc, i| Expression::Cond {
+            cond: Box::new(Expression::LessThan(
+                var(symbol_o!(app;geometry;width)),
+                Box::new(Expression::Literal(f64::from(i).into())),
+            )),
+            if_true: Box::new(Expression::Add(
+                var(symbol_o!(app;geometry;width)),
+                var(symbol_o!(app;geometry;height)),
+            )),
+            if_false: Box::new(acc),
+            display: CondDisplay::If,
+        });
+        Expression::Let {
+            name: symbol_o!(app;geometry;width),
+            value: Box::new(Expression::Literal(2.0.into())),
+            body: Box::new(Expression::Let {
+                name: symbol_o!(app;geometry;height),
+                value: Box::new(Expression::Literal(3.0.into())),
+                body: Box::new(body),
+            }),
+        }
+    }
+
+    #[test]
+    fn round_trip_values() {
+        round_trip(12.5.into());
+        round_trip((-0.0).into());
+        round_trip(f64::INFINITY.into());
+        round_trip("".into());
+        round_trip("snowman ☃".into());
+        round_trip(symbol_o!(global).into());
+        round_trip(symbol_o!(a;b;c).into());
+        round_trip(OwnedArray::empty().into());
+        round_trip(OwnedObject::empty().into());
+
+        let mut obj = OwnedObject::empty();
+        obj.set_mut(
+            symbol_o!(a;b).into(),
+            OwnedArray::from(vec![1.0.into()]).into(),
+        );
+        obj.set_mut(OwnedObject::empty().into(), symbol_o!(a).into());
+        round_trip(obj.into());
+    }
+
+    #[test]
+    fn round_trip_anonymous() {
+        let a = OwnedSymbol::Anonymous(AnonymousOwnedSymbol { id: 0 });
+        let b = OwnedSymbol::Anonymous(AnonymousOwnedSymbol { id: 300 });
+        let local = OwnedSymbol::new_local("x".to_string(), b.clone());
+
+        round_trip(OwnedArray::from(vec![a.into(), b.into(), local.into()]).into());
+    }
+
+    #[test]
+    fn round_trip_function() {
+        let env = Env::new().bind(symbol_o!(n), Value::from(10.0));
+        let fun = Function::new(
+            vec![symbol_o!(x)],
+            Arc::new(Expression::Multiply(var(symbol_o!(x)), var(symbol_o!(n)))),
+            env,
+        );
+
+        round_trip(OwnedValue::from(fun));
+    }
+
+    #[test]
+    fn round_trip_expression() {
+        let expr = program();
+        let mut bytes = vec![];
+        write_expression(&mut bytes, &expr).unwrap();
+
+        assert_eq!(read_expression(&bytes[..]).unwrap(), expr);
+    }
+
+    #[test]
+    fn symbols_are_stored_once() {
+        let sym: OwnedValue = symbol_o!(very_long_namespace;very_long_name).into();
+        let one = round_trip(OwnedArray::from(vec![sym.clone()]).into());
+        let two = round_trip(OwnedArray::from(vec![sym.clone(), sym]).into());
+
+        // The second use only adds a tag byte and a one byte index.
+        assert_eq!(two.len(), one.len() + 2);
+    }
+
+    #[test]
+    fn smaller_than_json() {
+        let val: OwnedValue = program().into();
+        let binary = round_trip(val.clone());
+        let json = serde_json::to_vec(&val).unwrap();
+
+        assert!(
+            binary.len() * 4 < json.len(),
+            "binary is {} bytes but json is {} bytes",
+            binary.len(),
+            json.len()
+        );
+    }
+
+    #[test]
+    fn uint_round_trip() {
+        for &n in &[0, 1, 127, 128, 300, u64::from(u32::MAX), u64::MAX] {
+            let mut bytes = vec![];
+            write_uint(&mut bytes, n).unwrap();
+
+            assert_eq!(read_uint(&mut &bytes[..]).unwrap(), n);
+        }
+    }
+
+    #[test]
+    fn read_not_binary() {
+        match read(&b"{\"Number\":1}"[..]) {
+            Err(BinaryError::NotBinary) => (),
+            res => panic!("expected not binary, found {:?}", res),
+        }
+    }
+
+    #[test]
+    fn read_unknown_symbol() {
+        // An empty symbol table, followed by the symbol at index 3.
+        let bytes = [b'D', b'R', b'M', b'B', 0, SYMBOL, 3];
+
+        match read(&bytes[..]) {
+            Err(BinaryError::UnknownSymbol(3)) => (),
+            res => panic!("expected an unknown symbol, found {:?}", res),
+        }
+    }
+
+    #[test]
+    fn read_symbol_with_space() {
+        let bytes = [b'D', b'R', b'M', b'B', 1, 0, 3, b'a', b' ', b'b', SYMBOL, 0];
+
+        match read(&bytes[..]) {
+            Err(BinaryError::InvalidSymbolName(name, SymbolError::InvalidChar(' '))) => {
+                assert_eq!(name, "a b")
+            }
+            res => panic!("expected an invalid symbol, found {:?}", res),
+        }
+    }
+
+    #[test]
+    fn read_truncated() {
+        let mut bytes = vec![];
+        write(&mut bytes, &"hello".into()).unwrap();
+        bytes.pop();
+
+        match read(&bytes[..]) {
+            Err(BinaryError::Io(err)) => assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof),
+            res => panic!("expected an io error, found {:?}", res),
+        }
+    }
+
+    /// The encoding of `depth` arrays, each inside the one before it.
+    fn nested_arrays(depth: usize) -> Vec<u8> {
+        let mut bytes = vec![b'D', b'R', b'M', b'B', 0];
+        for _ in 1..depth {
+            bytes.extend_from_slice(&[ARRAY, 1]);
+        }
+        bytes.extend_from_slice(&[ARRAY, 0]);
+        bytes
+    }
+
+    #[test]
+    fn read_too_deep() {
+        assert!(read(&nested_arrays(MAX_DEPTH)[..]).is_ok());
+
+        match read(&nested_arrays(MAX_DEPTH + 1)[..]) {
+            Err(BinaryError::TooDeep) => (),
+            res => panic!("expected too deep, found {:?}", res),
+        }
+    }
+
+    #[test]
+    fn write_too_deep() {
+        let nested = |depth| {
+            (1..depth).fold(OwnedValue::from(OwnedArray::empty()), |val, _| {
+                OwnedArray::from(vec![val]).into()
+            })
+        };
+
+        let mut bytes = vec![];
+        write(&mut bytes, &nested(MAX_DEPTH)).unwrap();
+        assert_eq!(read(&bytes[..]).unwrap(), nested(MAX_DEPTH));
+
+        match write(&mut vec![], &nested(MAX_DEPTH + 1)) {
+            Err(BinaryError::TooDeep) => (),
+            res => panic!("expected too deep, found {:?}", res),
+        }
+    }
+
+    #[test]
+    fn read_unknown_tag() {
+        let bytes = [b'D', b'R', b'M', b'B', 0, 9];
+
+        match read(&bytes[..]) {
+            Err(BinaryError::UnknownTag(9)) => (),
+            res => panic!("expected an unknown tag, found {:?}", res),
+        }
+    }
+}
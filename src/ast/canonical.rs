@@ -0,0 +1,249 @@
+/*
+ * Dermis is an interpreter for a pure, statically typed, imperitive language designed to be edited with a custom IDE.
+ * Copyright (C) 2018 Amelorate
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Canonical JSON serialization, which writes equal values as identical bytes.
+//!
+//! The output is indented by two spaces and ends with a newline, so that a small change to a
+//! program is a small change to it's file. Objects are written with their keys sorted by the
+//! `Ord` of [`OwnedValue`](::value::OwnedValue), which
+//! [`OwnedObject`](::value::OwnedObject) does when it is serialized, and `-0` is written as `0`
+//! since the two are equal.
+//!
+//! Arrays and objects can not be nested more than [`MAX_DEPTH`](MAX_DEPTH) deep. This is as deep
+//! as serde_json reads, and since every line is indented by it's depth it also bounds how large
+//! the output grows with depth. Each node of an AST is about three levels deep, or six once it
+//! has an [id](::ast::ids).
+
+use std::io;
+use std::io::Write;
+
+use serde::Serialize;
+use serde_json;
+use serde_json::ser::{Formatter, PrettyFormatter, Serializer};
+
+/// The deepest arrays and objects can be nested in canonical JSON, which is the deepest serde_json
+/// reads them.
+pub const MAX_DEPTH: usize = 127;
+
+/// Writes the canonical JSON serialization of `val` to `writer`.
+///
+/// # Errors
+/// An IO error if `val` nests arrays and objects more than [`MAX_DEPTH`](MAX_DEPTH) deep, after
+/// the part of `val` before that has been written.
+pub fn to_writer<W: Write, T: Serialize + ?Sized>(
+    mut writer: W,
+    val: &T,
+) -> serde_json::Result<()> {
+    {
+        let formatter = Canonical {
+            pretty: PrettyFormatter::with_indent(b"  "),
+            depth: 0,
+        };
+        let mut ser = Serializer::with_formatter(&mut writer, formatter);
+        val.serialize(&mut ser)?;
+    }
+    writer.write_all(b"\n").map_err(serde_json::Error::io)
+}
+
+/// Serializes `val` as canonical JSON.
+///
+/// # Example
+/// ```
+/// #[macro_use]
+/// extern crate dermis;
+/// use dermis::ast::canonical;
+/// use dermis::value::OwnedObject;
+///
+/// # fn main() {
+/// let mut a = OwnedObject::empty();
+/// a.set_mut("x".into(), 1.0.into());
+/// a.set_mut(symbol_o!(y).into(), 2.0.into());
+///
+/// let mut b = OwnedObject::empty();
+/// b.set_mut(symbol_o!(y).into(), 2.0.into());
+/// b.set_mut("x".into(), 1.0.into());
+///
+/// assert_eq!(canonical::to_string(&a).unwrap(), canonical::to_string(&b).unwrap());
+/// # }
+/// ```
+pub fn to_string<T: Serialize + ?Sized>(val: &T) -> serde_json::Result<String> {
+    let mut bytes = vec![];
+    to_writer(&mut bytes, val)?;
+    // serde_json only writes valid UTF-8.
+    Ok(String::from_utf8(bytes).expect("JSON was not valid UTF-8"))
+}
+
+/// Formats like `PrettyFormatter`, but writes `-0` as `0` and stops past
+/// [`MAX_DEPTH`](MAX_DEPTH).
+struct Canonical<'a> {
+    pretty: PrettyFormatter<'a>,
+    /// How many arrays and objects the value being written is in.
+    depth: usize,
+}
+
+impl<'a> Canonical<'a> {
+    fn enter(&mut self) -> io::Result<()> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("values are nested more than {} deep", MAX_DEPTH),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Formatter for Canonical<'a> {
+    fn write_f64<W: ?Sized + Write>(&mut self, writer: &mut W, value: f64) -> io::Result<()> {
+        let value = if value == 0.0 { 0.0 } else { value };
+        self.pretty.write_f64(writer, value)
+    }
+
+    fn begin_array<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.enter()?;
+        self.pretty.begin_array(writer)
+    }
+
+    fn end_array<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.depth -= 1;
+        self.pretty.end_array(writer)
+    }
+
+    fn begin_array_value<W: ?Sized + Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.pretty.begin_array_value(writer, first)
+    }
+
+    fn end_array_value<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.pretty.end_array_value(writer)
+    }
+
+    fn begin_object<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.enter()?;
+        self.pretty.begin_object(writer)
+    }
+
+    fn end_object<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.depth -= 1;
+        self.pretty.end_object(writer)
+    }
+
+    fn begin_object_key<W: ?Sized + Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.pretty.begin_object_key(writer, first)
+    }
+
+    fn begin_object_value<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.pretty.begin_object_value(writer)
+    }
+
+    fn end_object_value<W: ?Sized + Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.pretty.end_object_value(writer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ast::expression::Expression;
+    use value::{OwnedArray, OwnedObject, OwnedSymbol, OwnedValue};
+
+    #[test]
+    fn indentation() {
+        let val: OwnedValue = OwnedArray::from(vec!["a".into(), OwnedArray::empty().into()]).into();
+
+        assert_eq!(
+            to_string(&val).unwrap(),
+            "{\n  \"Array\": [\n    {\n      \"String\": \"a\"\n    },\n    {\n      \"Array\": []\n    }\n  ]\n}\n"
+        );
+    }
+
+    #[test]
+    fn key_order() {
+        let keys: Vec<OwnedValue> = (0..50)
+            .map(|i| match i % 3 {
+                0 => f64::from(i).into(),
+                1 => i.to_string().as_str().into(),
+                _ => OwnedValue::from(OwnedSymbol::new_global(format!("s{}", i))),
+            })
+            .collect();
+        let mut forward = OwnedObject::empty();
+        for k in &keys {
+            forward.set_mut(k.clone(), Expression::Nop.into());
+        }
+        let mut backward = OwnedObject::empty();
+        for k in keys.iter().rev() {
+            backward.set_mut(k.clone(), Expression::Nop.into());
+        }
+
+        assert_eq!(forward, backward);
+        assert_eq!(to_string(&forward).unwrap(), to_string(&backward).unwrap());
+    }
+
+    #[test]
+    fn negative_zero() {
+        let zero: OwnedValue = 0.0.into();
+        let negative: OwnedValue = (-0.0).into();
+
+        assert_eq!(zero, negative);
+        assert_eq!(to_string(&zero).unwrap(), to_string(&negative).unwrap());
+        assert!(to_string(&(-1.5)).unwrap().starts_with('-'));
+    }
+
+    /// `depth` arrays, each inside the last.
+    fn nested(depth: usize) -> serde_json::Value {
+        (1..depth).fold(serde_json::Value::Array(vec![]), |val, _| {
+            serde_json::Value::Array(vec![val])
+        })
+    }
+
+    #[test]
+    fn max_depth() {
+        let deepest = to_string(&nested(MAX_DEPTH)).unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&deepest).unwrap(),
+            nested(MAX_DEPTH)
+        );
+
+        let err = to_string(&nested(MAX_DEPTH + 1)).unwrap_err();
+        assert_eq!(err.to_string(), "values are nested more than 127 deep");
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut obj = OwnedObject::empty();
+        obj.set_mut(
+            symbol_o!(a;b).into(),
+            OwnedArray::from(vec![1.0.into()]).into(),
+        );
+        obj.set_mut("c".into(), OwnedObject::empty().into());
+        let val: OwnedValue = obj.into();
+
+        assert_eq!(
+            serde_json::from_str::<OwnedValue>(&to_string(&val).unwrap()).unwrap(),
+            val
+        );
+    }
+}
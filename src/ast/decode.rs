@@ -0,0 +1,278 @@
+/*
+ * Dermis is an interpreter for a pure, statically typed, imperitive language designed to be edited with a custom IDE.
+ * Copyright (C) 2018 Amelorate
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Utilities for decoding the AST from it's dermis value encoding.
+//!
+//! A node of the AST is encoded as an object where every key is a symbol in the namespace
+//! `'Ast::Node`, for example `'Ast::Cond` or `'Ast::Cond::IfTrue`. `'Ast::Node` is the node's
+//! tag, and decides which node the object is decoded into.
+
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use value::symbol::SymbolError;
+use value::{Number, OwnedArray, OwnedObject, OwnedSymbol, OwnedValue};
+
+/// An error that occured while decoding the AST from a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    /// A key required by the node was not present.
+    MissingKey(OwnedSymbol),
+    /// A key was present that the node does not use.
+    UnexpectedKey(OwnedValue),
+    /// A symbol was found that does not name a node or option.
+    UnexpectedSymbol(OwnedSymbol),
+    /// A value of the wrong type was found.
+    UnexpectedValue {
+        expected: &'static str,
+        found: OwnedValue,
+    },
+    /// A name was not a valid symbol name, see [`check_name`](::value::symbol::check_name).
+    InvalidSymbolName(String, SymbolError),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        use self::DecodeError::*;
+        match self {
+            MissingKey(key) => write!(f, "missing key {}", key),
+            UnexpectedKey(key) => write!(f, "unexpected key {}", key),
+            UnexpectedSymbol(sym) => write!(f, "unexpected symbol {}", sym),
+            UnexpectedValue { expected, found } => {
+                write!(f, "expected {} but found {}", expected, found)
+            }
+            InvalidSymbolName(name, err) => write!(f, "invalid symbol name \"{}\": {}", name, err),
+        }
+    }
+}
+
+impl Error for DecodeError {}
+
+/// The fields of an encoded node, which are removed as they are decoded.
+#[derive(Debug)]
+pub(crate) struct Fields {
+    tag: OwnedSymbol,
+    fields: OwnedObject,
+}
+
+impl Fields {
+    /// Finds the tag of an encoded node.
+    ///
+    /// If keys from more than one node are present, the tag of the smallest key is used and the
+    /// others will be reported by [`Fields::finish`](Fields::finish).
+    pub(crate) fn new(fields: OwnedObject) -> Result<Fields, DecodeError> {
+        Fields::with_root(fields, symbol_o!(Ast))
+    }
+
+    /// Finds the tag of an encoded node whose keys are in the namespace `root` instead of
+    /// `'Ast`.
+    pub(crate) fn with_root(fields: OwnedObject, root: OwnedSymbol) -> Result<Fields, DecodeError> {
+        let key = match fields.keys().min() {
+            Some(key) => key,
+            None => {
+                return Err(DecodeError::UnexpectedValue {
+                    expected: "an AST node",
+                    found: fields.into(),
+                })
+            }
+        };
+
+        let mut tag = match *key {
+            OwnedValue::Symbol(ref sym) => sym,
+            ref key => return Err(DecodeError::UnexpectedKey(key.clone())),
+        };
+        loop {
+            match tag.get_namespace() {
+                Some(ns) if *ns == root => break,
+                Some(ns) => tag = ns,
+                None => return Err(DecodeError::UnexpectedKey((*key).clone())),
+            }
+        }
+
+        Ok(Fields {
+            tag: tag.clone(),
+            fields,
+        })
+    }
+
+    /// The tag of the node, such as `'Ast::Cond`.
+    pub(crate) fn tag(&self) -> &OwnedSymbol {
+        &self.tag
+    }
+
+    /// Removes the value stored under the node's tag.
+    pub(crate) fn take(&mut self) -> Result<OwnedValue, DecodeError> {
+        let key = self.tag.clone();
+        self.take_key(key)
+    }
+
+    /// Removes the value stored under `'Ast::Node::name`.
+    pub(crate) fn take_field(&mut self, name: &str) -> Result<OwnedValue, DecodeError> {
+        let key = OwnedSymbol::new_local(name.to_string(), self.tag.clone());
+        self.take_key(key)
+    }
+
+    fn take_key(&mut self, key: OwnedSymbol) -> Result<OwnedValue, DecodeError> {
+        match self.fields.pop_mut(&key.clone().into()) {
+            Some(val) => Ok((*val).clone()),
+            None => Err(DecodeError::MissingKey(key)),
+        }
+    }
+
+    /// Checks that every field of the node has been decoded.
+    pub(crate) fn finish(self) -> Result<(), DecodeError> {
+        match self.fields.keys().min() {
+            Some(key) => Err(DecodeError::UnexpectedKey((*key).clone())),
+            None => Ok(()),
+        }
+    }
+}
+
+pub(crate) fn number(val: OwnedValue) -> Result<Number, DecodeError> {
+    match val {
+        OwnedValue::Number(n) => Ok(n),
+        found => Err(DecodeError::UnexpectedValue {
+            expected: "a number",
+            found,
+        }),
+    }
+}
+
+pub(crate) fn symbol(val: OwnedValue) -> Result<OwnedSymbol, DecodeError> {
+    match val {
+        OwnedValue::Symbol(s) => Ok(s),
+        found => Err(DecodeError::UnexpectedValue {
+            expected: "a symbol",
+            found,
+        }),
+    }
+}
+
+pub(crate) fn string(val: OwnedValue) -> Result<String, DecodeError> {
+    match val {
+        OwnedValue::String(s) => Ok(s),
+        found => Err(DecodeError::UnexpectedValue {
+            expected: "a string",
+            found,
+        }),
+    }
+}
+
+/// Decodes the symbol `'true` or `'false`.
+pub(crate) fn boolean(val: OwnedValue) -> Result<bool, DecodeError> {
+    if val == true.into() {
+        Ok(true)
+    } else if val == false.into() {
+        Ok(false)
+    } else {
+        Err(DecodeError::UnexpectedValue {
+            expected: "'true or 'false",
+            found: val,
+        })
+    }
+}
+
+pub(crate) fn array(val: OwnedValue) -> Result<OwnedArray, DecodeError> {
+    match val {
+        OwnedValue::Array(a) => Ok(a),
+        found => Err(DecodeError::UnexpectedValue {
+            expected: "an array",
+            found,
+        }),
+    }
+}
+
+pub(crate) fn object(val: OwnedValue) -> Result<OwnedObject, DecodeError> {
+    match val {
+        OwnedValue::Object(o) => Ok(o),
+        found => Err(DecodeError::UnexpectedValue {
+            expected: "an object",
+            found,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fields_tag() {
+        let mut o = OwnedObject::empty();
+        o.set_mut(symbol_o!(Ast;Foo;Bar).into(), 1.0.into());
+        o.set_mut(symbol_o!(Ast;Foo).into(), 2.0.into());
+
+        let f = Fields::new(o).unwrap();
+
+        assert_eq!(f.tag(), &symbol_o!(Ast;Foo));
+    }
+
+    #[test]
+    fn fields_take() {
+        let mut o = OwnedObject::empty();
+        o.set_mut(symbol_o!(Ast;Foo;Bar).into(), 1.0.into());
+        o.set_mut(symbol_o!(Ast;Foo).into(), 2.0.into());
+
+        let mut f = Fields::new(o).unwrap();
+
+        assert_eq!(f.take(), Ok(2.0.into()));
+        assert_eq!(f.take_field("Bar"), Ok(1.0.into()));
+        assert_eq!(
+            f.take_field("Baz"),
+            Err(DecodeError::MissingKey(symbol_o!(Ast;Foo;Baz)))
+        );
+        assert_eq!(f.finish(), Ok(()));
+    }
+
+    #[test]
+    fn fields_unexpected_key() {
+        let mut o = OwnedObject::empty();
+        o.set_mut(symbol_o!(Ast;Foo).into(), 2.0.into());
+        o.set_mut(symbol_o!(Ast;Foo;Bar).into(), 1.0.into());
+
+        let mut f = Fields::new(o).unwrap();
+        f.take().unwrap();
+
+        assert_eq!(
+            f.finish(),
+            Err(DecodeError::UnexpectedKey(symbol_o!(Ast;Foo;Bar).into()))
+        );
+    }
+
+    #[test]
+    fn fields_not_ast() {
+        let o = OwnedObject::singleton(symbol_o!(foo;bar).into(), 1.0.into());
+
+        assert_eq!(
+            Fields::new(o).unwrap_err(),
+            DecodeError::UnexpectedKey(symbol_o!(foo;bar).into())
+        );
+    }
+
+    #[test]
+    fn fields_empty() {
+        assert_eq!(
+            Fields::new(OwnedObject::empty()).unwrap_err(),
+            DecodeError::UnexpectedValue {
+                expected: "an AST node",
+                found: OwnedObject::empty().into(),
+            }
+        );
+    }
+}
@@ -16,58 +16,172 @@
  * along with this program.  If not, see <https://www.gnu.org/licenses/>.
  */
 
-use value::{OwnedValue, OwnedObject};
+use std::convert::TryFrom;
+
+use ast::decode::{self, DecodeError, Fields};
+use ast::path::Step;
+use ast::pattern::Pattern;
+use ast::types::Type;
+use value::{Number, OwnedArray, OwnedObject, OwnedSymbol, OwnedValue};
 
 type E = Box<Expression>;
 
-#[derive(Eq, PartialEq, Hash, Ord, PartialOrd, Debug, Serialize, Deserialize)]
+#[derive(Eq, PartialEq, Hash, Ord, PartialOrd, Debug, Clone, Serialize, Deserialize)]
 pub enum Expression {
     Nop,
+    /// A constant value.
+    ///
+    /// When ran, this will return the value it holds.
+    Literal(OwnedValue),
     /// Used for setting IDE-specific options on an expression.
     ///
+    /// The options known to dermis are read with [`IdeOptions`](::ast::options::IdeOptions).
+    ///
     /// When ran, this will return the value of id.
     IdeOption { id: E, options: OwnedObject },
+    /// Declares the type of an expression, which is checked by the type checker.
+    ///
+    /// When ran, this will return the value of expr.
+    Typed { expr: E, ty: Type },
 
     // Logical Operators:
+    //
+    // Truthiness is decided by `Value::is_truthy`: null and `'false` are false, anything else is
+    // true. Operators that return a boolean return either `'true` or `'false`.
     Cond { cond: E, if_true: E, if_false: E, display: CondDisplay},
-//    LAnd(E, E),
-//    LOr(E, E),
-//    LXor(E, E),
-//    LNot(E),
-//    NotNull(E),
-//    IsNull(E),
-//
-//    // Comparison Operators:
-//    /// Rounds and then compares two floats.
-//    ///
-//    /// `abs(lhs - rhs) < max(lhs, rhs) * rounding_factor` is how this is calculated.
-//    ///
-//    /// This calculaton was taken from the J programming language, see 
-//    /// http://code.jsoftware.com/wiki/Essays/Tolerant_Comparison
-//    ///
-//    /// If the value of `rounding_factor` is negative 2^-44 will be used.
-//    FloatingEqual { lhs: E, rhs: E, rounding_factor: f64 },
-//    FloatingNE(E, E),
-//    Equal(E, E),
-//    NotEqual(E, E),
-//    LessThan(E, E),
-//    GreaterThan(E, E),
-//    LesserOrEqual(E, E),
-//    GreaterOrEqual(E, E),
-//
-//    // Math Operators:
-//    StrConcat(E, E),
-//    Add(E, E),
-//    Subtract(E, E),
-//    Multiply(E, E),
-//    Divide(E, E),
-//    IntDivide(E, E),
-//    Exponent(E, E),
-//    Sqrt(E),
-//    Log(E),
+    /// Logical and. The right hand side is not evaluated if the left hand side is false.
+    LAnd(E, E),
+    /// Logical or. The right hand side is not evaluated if the left hand side is true.
+    LOr(E, E),
+    LXor(E, E),
+    LNot(E),
+    /// Returns true if the value is not null, that is not an empty object.
+    NotNull(E),
+    /// Returns true if the value is null, that is an empty object.
+    IsNull(E),
+
+    // Comparison Operators:
+    //
+    // Values of different types are ordered by type, in the order
+    // Number < String < Symbol < Array < Object, matching the `Ord` implementation on `Value`.
+
+    /// Rounds and then compares two numbers.
+    ///
+    /// `abs(lhs - rhs) <= max(abs(lhs), abs(rhs)) * rounding_factor` is how this is calculated.
+    ///
+    /// This calculaton was taken from the J programming language, see
+    /// http://code.jsoftware.com/wiki/Essays/Tolerant_Comparison
+    ///
+    /// If the value of `rounding_factor` is negative 2^-44 will be used.
+    ///
+    /// An infinity is only equal to an infinity of the same sign.
+    FloatingEqual { lhs: E, rhs: E, rounding_factor: Number },
+    /// The inverse of `FloatingEqual`.
+    FloatingNE { lhs: E, rhs: E, rounding_factor: Number },
+    Equal(E, E),
+    NotEqual(E, E),
+    LessThan(E, E),
+    GreaterThan(E, E),
+    LesserOrEqual(E, E),
+    GreaterOrEqual(E, E),
+
+    // Math Operators:
+    //
+    // Operations that would result in a value that is not a number, such as `0 / 0` or
+    // `sqrt(-1)`, result in an error. Infinite results are allowed.
+
+    /// Concatenates two strings.
+    StrConcat(E, E),
+    Add(E, E),
+    Subtract(E, E),
+    Multiply(E, E),
+    Divide(E, E),
+    /// Divides and then rounds towards negative infinity.
+    IntDivide(E, E),
+    /// Raises `lhs` to the power of `rhs`.
+    Exponent(E, E),
+    Sqrt(E),
+    /// The natural logarithm.
+    Log(E),
+
+    // Variables:
+
+    /// Evaluates `value` and binds it to `name` while evaluating `body`.
+    ///
+    /// The binding is lexically scoped, and shadows any enclosing binding of the same name.
+    Let { name: OwnedSymbol, value: E, body: E },
+    /// Returns the value bound to a variable by an enclosing `Let`.
+    Var(OwnedSymbol),
+
+    // Statements:
+    //
+    // Assignment only changes the variable being assigned to. Arrays and objects are persistent,
+    // so other variables holding the same array or object do not see the change, and a function
+    // can not change the variables of its caller.
+
+    /// Evaluates each expression in order, returning the value of the last one.
+    ///
+    /// An empty block returns null.
+    Block(Vec<Expression>),
+    /// Changes the value of a variable bound by an enclosing `Let`, returning null.
+    ///
+    /// The change lasts until the end of the `Let` that bound the variable.
+    Assign { name: OwnedSymbol, value: E },
+    /// Sets the element at `key` in the array or object held by a variable, returning null.
+    ///
+    /// An array can be extended by setting the index one past it's last element.
+    AssignAt { name: OwnedSymbol, key: E, value: E },
+    /// Returns the element at `key` in an array or object, or null if there is none.
+    Get { target: E, key: E },
+
+    // Loops:
+    //
+    // A loop carries an accumulator, `acc`, from one iteration to the next. It starts as the
+    // value of `init`, and the value of `body` becomes the accumulator for the next iteration.
+    // The loop returns the final value of the accumulator.
+    //
+    // Each iteration is evaluated in it's own scope, so assignments in the body do not change
+    // anything outside of the loop.
+
+    /// Evaluates `body` for as long as `cond` is true.
+    ///
+    /// If `cond` is still true after `limit` iterations, evaluation fails instead of looping
+    /// forever.
+    While { acc: OwnedSymbol, init: E, cond: E, body: E, limit: Number },
+    /// Evaluates `body` for each element of an array or entry of an object, with `item` bound to
+    /// the element and `key` bound to it's index or key.
+    ///
+    /// The entries of an object are visited in order of their keys.
+    ForEach { item: OwnedSymbol, key: OwnedSymbol, over: E, acc: OwnedSymbol, init: E, body: E },
+    /// Ends the innermost loop, which returns the given value.
+    ///
+    /// A function body is not part of the loop the function is called from, and neither are the
+    /// `init`, `cond` and `over` of a loop part of that loop or any loop around it.
+    Break(E),
+    /// Ends the current iteration of the innermost loop, with the given value as the
+    /// accumulator.
+    Continue(E),
+
+    // Functions:
+
+    /// Creates a function, which captures the variables currently in scope.
+    ///
+    /// When called, `body` is evaluated with the captured variables and with each of `params`
+    /// bound to the matching argument.
+    Lambda { params: Vec<OwnedSymbol>, body: E },
+    /// Calls a function with the given arguments, which are evaluated in order before the call.
+    Call { function: E, args: Vec<Expression> },
+
+    // Pattern Matching:
+
+    /// Evaluates the body of the first arm whose pattern matches the value of `scrutinee`, with
+    /// the variables bound by the pattern in scope.
+    ///
+    /// If no arm matches, evaluation fails.
+    Match { scrutinee: E, arms: Vec<(Pattern, Expression)> },
 } // That's a lot of E's
 
-#[derive(Eq, PartialEq, Hash, Ord, PartialOrd, Debug, Serialize, Deserialize)]
+#[derive(Eq, PartialEq, Hash, Ord, PartialOrd, Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum CondDisplay {
     /// Display like an if/else expression.
     If,
@@ -75,6 +189,114 @@ pub enum CondDisplay {
     Ternary,
 }
 
+/// Generates the body of [`Expression::children`](Expression::children) and
+/// [`Expression::children_mut`](Expression::children_mut), which differ only in whether the
+/// children are borrowed mutably.
+macro_rules! children {
+    ($expr:expr, $iter:ident, $($mut:tt)*) => {{
+        use self::Expression::*;
+        match $expr {
+            Nop | Literal(_) | Var(_) => vec![],
+            IdeOption { id, .. } => vec![(Step::Field("id"), id)],
+            Typed { expr, .. } => vec![(Step::Field("expr"), expr)],
+            Cond {
+                cond,
+                if_true,
+                if_false,
+                ..
+            } => vec![
+                (Step::Field("cond"), cond),
+                (Step::Field("if_true"), if_true),
+                (Step::Field("if_false"), if_false),
+            ],
+            LNot(e) | NotNull(e) | IsNull(e) | Sqrt(e) | Log(e) | Break(e) | Continue(e) => {
+                vec![(Step::Field("operand"), e)]
+            }
+            LAnd(lhs, rhs)
+            | LOr(lhs, rhs)
+            | LXor(lhs, rhs)
+            | FloatingEqual { lhs, rhs, .. }
+            | FloatingNE { lhs, rhs, .. }
+            | Equal(lhs, rhs)
+            | NotEqual(lhs, rhs)
+            | LessThan(lhs, rhs)
+            | GreaterThan(lhs, rhs)
+            | LesserOrEqual(lhs, rhs)
+            | GreaterOrEqual(lhs, rhs)
+            | StrConcat(lhs, rhs)
+            | Add(lhs, rhs)
+            | Subtract(lhs, rhs)
+            | Multiply(lhs, rhs)
+            | Divide(lhs, rhs)
+            | IntDivide(lhs, rhs)
+            | Exponent(lhs, rhs) => vec![(Step::Field("lhs"), lhs), (Step::Field("rhs"), rhs)],
+            Let { value, body, .. } => {
+                vec![(Step::Field("value"), value), (Step::Field("body"), body)]
+            }
+            Lambda { body, .. } => vec![(Step::Field("body"), body)],
+            Call { function, args } => {
+                let mut children = vec![(Step::Field("function"), &$($mut)* **function)];
+                children.extend(
+                    args.$iter()
+                        .enumerate()
+                        .map(|(i, a)| (Step::Index("args", i), a)),
+                );
+                children
+            }
+            Block(exprs) => exprs
+                .$iter()
+                .enumerate()
+                .map(|(i, e)| (Step::Index("exprs", i), e))
+                .collect(),
+            Assign { value, .. } => vec![(Step::Field("value"), value)],
+            AssignAt { key, value, .. } => {
+                vec![(Step::Field("key"), key), (Step::Field("value"), value)]
+            }
+            Get { target, key } => {
+                vec![(Step::Field("target"), target), (Step::Field("key"), key)]
+            }
+            While {
+                init, cond, body, ..
+            } => vec![
+                (Step::Field("init"), init),
+                (Step::Field("cond"), cond),
+                (Step::Field("body"), body),
+            ],
+            ForEach {
+                over, init, body, ..
+            } => vec![
+                (Step::Field("over"), over),
+                (Step::Field("init"), init),
+                (Step::Field("body"), body),
+            ],
+            Match { scrutinee, arms } => {
+                let mut children = vec![(Step::Field("scrutinee"), &$($mut)* **scrutinee)];
+                children.extend(
+                    arms.$iter()
+                        .enumerate()
+                        .map(|(i, (_, body))| (Step::Index("arms", i), body)),
+                );
+                children
+            }
+        }
+    }};
+}
+
+impl Expression {
+    /// The direct children of this node, along with the step from this node to each of them.
+    ///
+    /// Operators name their operands `lhs` and `rhs`, or `operand` if they only have one.
+    pub fn children(&self) -> Vec<(Step, &Expression)> {
+        children!(self, iter,)
+    }
+
+    /// The direct children of this node, which can be modified in place. See
+    /// [`Expression::children`](Expression::children).
+    pub fn children_mut(&mut self) -> Vec<(Step, &mut Expression)> {
+        children!(self, iter_mut, mut)
+    }
+}
+
 impl From<CondDisplay> for OwnedValue {
     fn from(d: CondDisplay) -> OwnedValue {
         use self::CondDisplay::*;
@@ -90,12 +312,18 @@ impl From<Expression> for OwnedValue {
         use self::Expression::*;
         match expr {
             Nop => symbol_o!(ast;nop).into(),
+            Literal(val) => OwnedObject::singleton(symbol_o!(Ast;Literal).into(), val).into(),
             IdeOption { id, options } => {
                 let mut o = OwnedObject::empty();
                 o.set_mut(symbol_o!(Ast;IdeOption;Id).into(), (*id).into());
                 o.set_mut(symbol_o!(Ast;IdeOption;Options).into(), options.into());
                 o.into()
             }
+            Typed { expr, ty } => {
+                let mut o = OwnedObject::singleton(symbol_o!(Ast;Typed).into(), (*expr).into());
+                o.set_mut(symbol_o!(Ast;Typed;Type).into(), ty.into());
+                o.into()
+            }
             Cond { cond, if_true, if_false, display } => {
                 let mut o = OwnedObject::empty();
                 o.set_mut(symbol_o!(Ast;Cond).into(), (*cond).into());
@@ -104,6 +332,606 @@ impl From<Expression> for OwnedValue {
                 o.set_mut(symbol_o!(Ast;Cond;Display).into(), display.into());
                 o.into()
             }
+            LAnd(lhs, rhs) => binary(symbol_o!(Ast;LAnd), *lhs, *rhs),
+            LOr(lhs, rhs) => binary(symbol_o!(Ast;LOr), *lhs, *rhs),
+            LXor(lhs, rhs) => binary(symbol_o!(Ast;LXor), *lhs, *rhs),
+            LNot(e) => unary(symbol_o!(Ast;LNot), *e),
+            NotNull(e) => unary(symbol_o!(Ast;NotNull), *e),
+            IsNull(e) => unary(symbol_o!(Ast;IsNull), *e),
+            FloatingEqual { lhs, rhs, rounding_factor } => {
+                floating(symbol_o!(Ast;FloatingEqual), *lhs, *rhs, rounding_factor)
+            }
+            FloatingNE { lhs, rhs, rounding_factor } => {
+                floating(symbol_o!(Ast;FloatingNE), *lhs, *rhs, rounding_factor)
+            }
+            Equal(lhs, rhs) => binary(symbol_o!(Ast;Equal), *lhs, *rhs),
+            NotEqual(lhs, rhs) => binary(symbol_o!(Ast;NotEqual), *lhs, *rhs),
+            LessThan(lhs, rhs) => binary(symbol_o!(Ast;LessThan), *lhs, *rhs),
+            GreaterThan(lhs, rhs) => binary(symbol_o!(Ast;GreaterThan), *lhs, *rhs),
+            LesserOrEqual(lhs, rhs) => binary(symbol_o!(Ast;LesserOrEqual), *lhs, *rhs),
+            GreaterOrEqual(lhs, rhs) => binary(symbol_o!(Ast;GreaterOrEqual), *lhs, *rhs),
+            StrConcat(lhs, rhs) => binary(symbol_o!(Ast;StrConcat), *lhs, *rhs),
+            Add(lhs, rhs) => binary(symbol_o!(Ast;Add), *lhs, *rhs),
+            Subtract(lhs, rhs) => binary(symbol_o!(Ast;Subtract), *lhs, *rhs),
+            Multiply(lhs, rhs) => binary(symbol_o!(Ast;Multiply), *lhs, *rhs),
+            Divide(lhs, rhs) => binary(symbol_o!(Ast;Divide), *lhs, *rhs),
+            IntDivide(lhs, rhs) => binary(symbol_o!(Ast;IntDivide), *lhs, *rhs),
+            Exponent(lhs, rhs) => binary(symbol_o!(Ast;Exponent), *lhs, *rhs),
+            Sqrt(e) => unary(symbol_o!(Ast;Sqrt), *e),
+            Log(e) => unary(symbol_o!(Ast;Log), *e),
+            Let { name, value, body } => {
+                let mut o = OwnedObject::singleton(symbol_o!(Ast;Let).into(), name.into());
+                o.set_mut(symbol_o!(Ast;Let;Value).into(), (*value).into());
+                o.set_mut(symbol_o!(Ast;Let;Body).into(), (*body).into());
+                o.into()
+            }
+            Var(name) => OwnedObject::singleton(symbol_o!(Ast;Var).into(), name.into()).into(),
+            Lambda { params, body } => {
+                let params: Vec<OwnedValue> = params.into_iter().map(|p| p.into()).collect();
+                let mut o = OwnedObject::singleton(
+                    symbol_o!(Ast;Lambda).into(),
+                    OwnedArray::from(params).into(),
+                );
+                o.set_mut(symbol_o!(Ast;Lambda;Body).into(), (*body).into());
+                o.into()
+            }
+            Call { function, args } => {
+                let mut o = OwnedObject::singleton(symbol_o!(Ast;Call).into(), (*function).into());
+                o.set_mut(symbol_o!(Ast;Call;Args).into(), list(args));
+                o.into()
+            }
+            Block(exprs) => OwnedObject::singleton(symbol_o!(Ast;Block).into(), list(exprs)).into(),
+            Assign { name, value } => {
+                let mut o = OwnedObject::singleton(symbol_o!(Ast;Assign).into(), name.into());
+                o.set_mut(symbol_o!(Ast;Assign;Value).into(), (*value).into());
+                o.into()
+            }
+            AssignAt { name, key, value } => {
+                let mut o = OwnedObject::singleton(symbol_o!(Ast;AssignAt).into(), name.into());
+                o.set_mut(symbol_o!(Ast;AssignAt;Key).into(), (*key).into());
+                o.set_mut(symbol_o!(Ast;AssignAt;Value).into(), (*value).into());
+                o.into()
+            }
+            Get { target, key } => {
+                let mut o = OwnedObject::singleton(symbol_o!(Ast;Get).into(), (*target).into());
+                o.set_mut(symbol_o!(Ast;Get;Key).into(), (*key).into());
+                o.into()
+            }
+            While { acc, init, cond, body, limit } => {
+                let mut o = OwnedObject::singleton(symbol_o!(Ast;While).into(), (*cond).into());
+                o.set_mut(symbol_o!(Ast;While;Acc).into(), acc.into());
+                o.set_mut(symbol_o!(Ast;While;Init).into(), (*init).into());
+                o.set_mut(symbol_o!(Ast;While;Body).into(), (*body).into());
+                o.set_mut(symbol_o!(Ast;While;Limit).into(), limit.into());
+                o.into()
+            }
+            ForEach { item, key, over, acc, init, body } => {
+                let mut o = OwnedObject::singleton(symbol_o!(Ast;ForEach).into(), (*over).into());
+                o.set_mut(symbol_o!(Ast;ForEach;Item).into(), item.into());
+                o.set_mut(symbol_o!(Ast;ForEach;Key).into(), key.into());
+                o.set_mut(symbol_o!(Ast;ForEach;Acc).into(), acc.into());
+                o.set_mut(symbol_o!(Ast;ForEach;Init).into(), (*init).into());
+                o.set_mut(symbol_o!(Ast;ForEach;Body).into(), (*body).into());
+                o.into()
+            }
+            Break(e) => unary(symbol_o!(Ast;Break), *e),
+            Continue(e) => unary(symbol_o!(Ast;Continue), *e),
+            Match { scrutinee, arms } => {
+                let arms: Vec<OwnedValue> = arms
+                    .into_iter()
+                    .map(|(pattern, body)| OwnedArray::from(vec![pattern.into(), body.into()]).into())
+                    .collect();
+                let mut o =
+                    OwnedObject::singleton(symbol_o!(Ast;Match).into(), (*scrutinee).into());
+                o.set_mut(symbol_o!(Ast;Match;Arms).into(), OwnedArray::from(arms).into());
+                o.into()
+            }
+        }
+    }
+}
+
+impl TryFrom<OwnedValue> for CondDisplay {
+    type Error = DecodeError;
+
+    fn try_from(val: OwnedValue) -> Result<CondDisplay, DecodeError> {
+        let sym = decode::symbol(val)?;
+        if sym == symbol_o!(Ast;CondDisplay;If) {
+            Ok(CondDisplay::If)
+        } else if sym == symbol_o!(Ast;CondDisplay;Ternary) {
+            Ok(CondDisplay::Ternary)
+        } else {
+            Err(DecodeError::UnexpectedSymbol(sym))
+        }
+    }
+}
+
+/// Decodes an expression from the encoding produced by `From<Expression> for OwnedValue`.
+impl TryFrom<OwnedValue> for Expression {
+    type Error = DecodeError;
+
+    fn try_from(val: OwnedValue) -> Result<Expression, DecodeError> {
+        use self::Expression::*;
+        let mut f = match val {
+            OwnedValue::Symbol(sym) => {
+                return if sym == symbol_o!(ast;nop) {
+                    Ok(Nop)
+                } else {
+                    Err(DecodeError::UnexpectedSymbol(sym))
+                }
+            }
+            OwnedValue::Object(o) => Fields::new(o)?,
+            found => {
+                return Err(DecodeError::UnexpectedValue {
+                    expected: "an AST node",
+                    found,
+                })
+            }
+        };
+
+        let tag = f.tag().get_name().clone();
+        let expr = match &tag[..] {
+            "Literal" => Literal(f.take()?),
+            "IdeOption" => IdeOption {
+                id: field(&mut f, "Id")?,
+                options: decode::object(f.take_field("Options")?)?,
+            },
+            "Typed" => Typed {
+                expr: Box::new(Expression::try_from(f.take()?)?),
+                ty: Type::try_from(f.take_field("Type")?)?,
+            },
+            "Cond" => Cond {
+                cond: Box::new(Expression::try_from(f.take()?)?),
+                if_true: field(&mut f, "IfTrue")?,
+                if_false: field(&mut f, "IfFalse")?,
+                display: CondDisplay::try_from(f.take_field("Display")?)?,
+            },
+            "LAnd" => LAnd(field(&mut f, "Lhs")?, field(&mut f, "Rhs")?),
+            "LOr" => LOr(field(&mut f, "Lhs")?, field(&mut f, "Rhs")?),
+            "LXor" => LXor(field(&mut f, "Lhs")?, field(&mut f, "Rhs")?),
+            "LNot" => LNot(Box::new(Expression::try_from(f.take()?)?)),
+            "NotNull" => NotNull(Box::new(Expression::try_from(f.take()?)?)),
+            "IsNull" => IsNull(Box::new(Expression::try_from(f.take()?)?)),
+            "FloatingEqual" => FloatingEqual {
+                lhs: field(&mut f, "Lhs")?,
+                rhs: field(&mut f, "Rhs")?,
+                rounding_factor: decode::number(f.take_field("RoundingFactor")?)?,
+            },
+            "FloatingNE" => FloatingNE {
+                lhs: field(&mut f, "Lhs")?,
+                rhs: field(&mut f, "Rhs")?,
+                rounding_factor: decode::number(f.take_field("RoundingFactor")?)?,
+            },
+            "Equal" => Equal(field(&mut f, "Lhs")?, field(&mut f, "Rhs")?),
+            "NotEqual" => NotEqual(field(&mut f, "Lhs")?, field(&mut f, "Rhs")?),
+            "LessThan" => LessThan(field(&mut f, "Lhs")?, field(&mut f, "Rhs")?),
+            "GreaterThan" => GreaterThan(field(&mut f, "Lhs")?, field(&mut f, "Rhs")?),
+            "LesserOrEqual" => LesserOrEqual(field(&mut f, "Lhs")?, field(&mut f, "Rhs")?),
+            "GreaterOrEqual" => GreaterOrEqual(field(&mut f, "Lhs")?, field(&mut f, "Rhs")?),
+            "StrConcat" => StrConcat(field(&mut f, "Lhs")?, field(&mut f, "Rhs")?),
+            "Add" => Add(field(&mut f, "Lhs")?, field(&mut f, "Rhs")?),
+            "Subtract" => Subtract(field(&mut f, "Lhs")?, field(&mut f, "Rhs")?),
+            "Multiply" => Multiply(field(&mut f, "Lhs")?, field(&mut f, "Rhs")?),
+            "Divide" => Divide(field(&mut f, "Lhs")?, field(&mut f, "Rhs")?),
+            "IntDivide" => IntDivide(field(&mut f, "Lhs")?, field(&mut f, "Rhs")?),
+            "Exponent" => Exponent(field(&mut f, "Lhs")?, field(&mut f, "Rhs")?),
+            "Sqrt" => Sqrt(Box::new(Expression::try_from(f.take()?)?)),
+            "Log" => Log(Box::new(Expression::try_from(f.take()?)?)),
+            "Let" => Let {
+                name: decode::symbol(f.take()?)?,
+                value: field(&mut f, "Value")?,
+                body: field(&mut f, "Body")?,
+            },
+            "Var" => Var(decode::symbol(f.take()?)?),
+            "Lambda" => Lambda {
+                params: decode::array(f.take()?)?
+                    .iter()
+                    .map(|p| decode::symbol((*p).clone()))
+                    .collect::<Result<_, _>>()?,
+                body: field(&mut f, "Body")?,
+            },
+            "Call" => Call {
+                function: Box::new(Expression::try_from(f.take()?)?),
+                args: decode_list(f.take_field("Args")?)?,
+            },
+            "Block" => Block(decode_list(f.take()?)?),
+            "Assign" => Assign {
+                name: decode::symbol(f.take()?)?,
+                value: field(&mut f, "Value")?,
+            },
+            "AssignAt" => AssignAt {
+                name: decode::symbol(f.take()?)?,
+                key: field(&mut f, "Key")?,
+                value: field(&mut f, "Value")?,
+            },
+            "Get" => Get {
+                target: Box::new(Expression::try_from(f.take()?)?),
+                key: field(&mut f, "Key")?,
+            },
+            "While" => While {
+                cond: Box::new(Expression::try_from(f.take()?)?),
+                acc: decode::symbol(f.take_field("Acc")?)?,
+                init: field(&mut f, "Init")?,
+                body: field(&mut f, "Body")?,
+                limit: decode::number(f.take_field("Limit")?)?,
+            },
+            "ForEach" => ForEach {
+                over: Box::new(Expression::try_from(f.take()?)?),
+                item: decode::symbol(f.take_field("Item")?)?,
+                key: decode::symbol(f.take_field("Key")?)?,
+                acc: decode::symbol(f.take_field("Acc")?)?,
+                init: field(&mut f, "Init")?,
+                body: field(&mut f, "Body")?,
+            },
+            "Break" => Break(Box::new(Expression::try_from(f.take()?)?)),
+            "Continue" => Continue(Box::new(Expression::try_from(f.take()?)?)),
+            "Match" => Match {
+                scrutinee: Box::new(Expression::try_from(f.take()?)?),
+                arms: decode::array(f.take_field("Arms")?)?
+                    .iter()
+                    .map(|arm| decode_arm((*arm).clone()))
+                    .collect::<Result<_, _>>()?,
+            },
+            _ => return Err(DecodeError::UnexpectedSymbol(f.tag().clone())),
+        };
+        f.finish()?;
+        Ok(expr)
+    }
+}
+
+/// Decodes the expression stored under `'Ast::Node::name`.
+fn field(f: &mut Fields, name: &str) -> Result<E, DecodeError> {
+    Ok(Box::new(Expression::try_from(f.take_field(name)?)?))
+}
+
+/// Decodes an array of expressions.
+fn decode_list(val: OwnedValue) -> Result<Vec<Expression>, DecodeError> {
+    decode::array(val)?
+        .iter()
+        .map(|e| Expression::try_from((*e).clone()))
+        .collect()
+}
+
+/// Decodes an arm of a `Match`, which is encoded as an array of the pattern and the body.
+fn decode_arm(val: OwnedValue) -> Result<(Pattern, Expression), DecodeError> {
+    let arm = decode::array(val)?;
+    if arm.len() != 2 {
+        return Err(DecodeError::UnexpectedValue {
+            expected: "a pattern and an expression",
+            found: arm.into(),
+        });
+    }
+    Ok((
+        Pattern::try_from((*arm.get_unwrapped(0)).clone())?,
+        Expression::try_from((*arm.get_unwrapped(1)).clone())?,
+    ))
+}
+
+/// Encodes a list of expressions as an array.
+fn list(exprs: Vec<Expression>) -> OwnedValue {
+    let exprs: Vec<OwnedValue> = exprs.into_iter().map(|e| e.into()).collect();
+    OwnedArray::from(exprs).into()
+}
+
+/// Encodes an operator with one operand as `{'Ast::Op: e}`.
+fn unary(tag: OwnedSymbol, e: Expression) -> OwnedValue {
+    OwnedObject::singleton(tag.into(), e.into()).into()
+}
+
+/// Encodes an operator with two operands as `{'Ast::Op::Lhs: lhs, 'Ast::Op::Rhs: rhs}`.
+fn binary(tag: OwnedSymbol, lhs: Expression, rhs: Expression) -> OwnedValue {
+    operands(tag, lhs, rhs).into()
+}
+
+/// Encodes a tolerant comparison as `binary` does, with an extra `'Ast::Op::RoundingFactor` key.
+fn floating(tag: OwnedSymbol, lhs: Expression, rhs: Expression, factor: Number) -> OwnedValue {
+    let factor_key = OwnedSymbol::new_local("RoundingFactor".to_string(), tag.clone());
+    let mut o = operands(tag, lhs, rhs);
+    o.set_mut(factor_key.into(), factor.into());
+    o.into()
+}
+
+fn operands(tag: OwnedSymbol, lhs: Expression, rhs: Expression) -> OwnedObject {
+    let mut o = OwnedObject::empty();
+    o.set_mut(OwnedSymbol::new_local("Lhs".to_string(), tag.clone()).into(), lhs.into());
+    o.set_mut(OwnedSymbol::new_local("Rhs".to_string(), tag).into(), rhs.into());
+    o
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn lit(val: f64) -> E {
+        Box::new(Expression::Literal(val.into()))
+    }
+
+    fn round_trip(expr: Expression) {
+        let encoded: OwnedValue = expr.clone().into();
+
+        assert_eq!(Expression::try_from(encoded), Ok(expr));
+    }
+
+    #[test]
+    fn round_trip_simple() {
+        round_trip(Expression::Nop);
+        round_trip(Expression::Literal(12.0.into()));
+        round_trip(Expression::Literal(symbol_o!(foo;bar).into()));
+        round_trip(Expression::Literal(
+            OwnedArray::from(vec!["a".into(), OwnedObject::empty().into()]).into(),
+        ));
+        round_trip(Expression::Literal(
+            OwnedObject::singleton(symbol_o!(Ast;Cond).into(), 1.0.into()).into(),
+        ));
+        round_trip(Expression::IdeOption {
+            id: lit(1.0),
+            options: OwnedObject::singleton("collapsed".into(), true.into()),
+        });
+    }
+
+    #[test]
+    fn round_trip_typed() {
+        round_trip(Expression::Typed {
+            expr: Box::new(Expression::Lambda {
+                params: vec![symbol_o!(x)],
+                body: Box::new(Expression::Var(symbol_o!(x))),
+            }),
+            ty: Type::Function {
+                params: vec![Type::Array(Box::new(Type::Number))],
+                ret: Box::new(Type::object()),
+            },
+        });
+    }
+
+    #[test]
+    fn round_trip_cond() {
+        for &display in &[CondDisplay::If, CondDisplay::Ternary] {
+            round_trip(Expression::Cond {
+                cond: lit(1.0),
+                if_true: lit(2.0),
+                if_false: Box::new(Expression::Nop),
+                display,
+            });
+        }
+    }
+
+    #[test]
+    fn round_trip_unary() {
+        let ops: Vec<fn(E) -> Expression> = vec![
+            Expression::LNot,
+            Expression::NotNull,
+            Expression::IsNull,
+            Expression::Sqrt,
+            Expression::Log,
+            Expression::Break,
+            Expression::Continue,
+        ];
+        for op in ops {
+            round_trip(op(lit(1.0)));
+        }
+    }
+
+    #[test]
+    fn round_trip_binary() {
+        let ops: Vec<fn(E, E) -> Expression> = vec![
+            Expression::LAnd,
+            Expression::LOr,
+            Expression::LXor,
+            Expression::Equal,
+            Expression::NotEqual,
+            Expression::LessThan,
+            Expression::GreaterThan,
+            Expression::LesserOrEqual,
+            Expression::GreaterOrEqual,
+            Expression::StrConcat,
+            Expression::Add,
+            Expression::Subtract,
+            Expression::Multiply,
+            Expression::Divide,
+            Expression::IntDivide,
+            Expression::Exponent,
+        ];
+        for op in ops {
+            round_trip(op(lit(1.0), lit(2.0)));
         }
     }
+
+    #[test]
+    fn round_trip_floating() {
+        round_trip(Expression::FloatingEqual {
+            lhs: lit(1.0),
+            rhs: lit(2.0),
+            rounding_factor: (-1.0).into(),
+        });
+        round_trip(Expression::FloatingNE {
+            lhs: lit(1.0),
+            rhs: lit(2.0),
+            rounding_factor: 0.5.into(),
+        });
+    }
+
+    #[test]
+    fn round_trip_nested() {
+        round_trip(Expression::Add(
+            Box::new(Expression::Sqrt(lit(4.0))),
+            Box::new(Expression::Cond {
+                cond: Box::new(Expression::LNot(Box::new(Expression::Nop))),
+                if_true: lit(1.0),
+                if_false: lit(2.0),
+                display: CondDisplay::Ternary,
+            }),
+        ));
+    }
+
+    #[test]
+    fn round_trip_variables() {
+        round_trip(Expression::Let {
+            name: symbol_o!(foo;a),
+            value: lit(1.0),
+            body: Box::new(Expression::Var(symbol_o!(foo;a))),
+        });
+    }
+
+    #[test]
+    fn round_trip_functions() {
+        round_trip(Expression::Call {
+            function: Box::new(Expression::Lambda {
+                params: vec![symbol_o!(a), symbol_o!(foo;b)],
+                body: Box::new(Expression::Var(symbol_o!(a))),
+            }),
+            args: vec![Expression::Nop, *lit(1.0)],
+        });
+        round_trip(Expression::Call {
+            function: Box::new(Expression::Lambda {
+                params: vec![],
+                body: lit(1.0),
+            }),
+            args: vec![],
+        });
+    }
+
+    #[test]
+    fn round_trip_statements() {
+        round_trip(Expression::Block(vec![
+            Expression::Assign {
+                name: symbol_o!(a),
+                value: lit(1.0),
+            },
+            Expression::AssignAt {
+                name: symbol_o!(a),
+                key: lit(0.0),
+                value: lit(2.0),
+            },
+            Expression::Get {
+                target: Box::new(Expression::Var(symbol_o!(a))),
+                key: lit(0.0),
+            },
+        ]));
+        round_trip(Expression::Block(vec![]));
+    }
+
+    #[test]
+    fn round_trip_loops() {
+        round_trip(Expression::While {
+            acc: symbol_o!(i),
+            init: lit(0.0),
+            cond: Box::new(Expression::LessThan(
+                Box::new(Expression::Var(symbol_o!(i))),
+                lit(10.0),
+            )),
+            body: Box::new(Expression::Add(
+                Box::new(Expression::Var(symbol_o!(i))),
+                lit(1.0),
+            )),
+            limit: 100.0.into(),
+        });
+        round_trip(Expression::ForEach {
+            item: symbol_o!(x),
+            key: symbol_o!(k),
+            over: Box::new(Expression::Literal(OwnedArray::from(vec![1.0.into()]).into())),
+            acc: symbol_o!(sum),
+            init: lit(0.0),
+            body: Box::new(Expression::Var(symbol_o!(x))),
+        });
+    }
+
+    #[test]
+    fn round_trip_match() {
+        round_trip(Expression::Match {
+            scrutinee: lit(1.0),
+            arms: vec![
+                (Pattern::Literal(1.0.into()), *lit(2.0)),
+                (
+                    Pattern::Bind(symbol_o!(x)),
+                    Expression::Var(symbol_o!(x)),
+                ),
+            ],
+        });
+    }
+
+    #[test]
+    fn decode_malformed_arm() {
+        let mut o = OwnedObject::singleton(symbol_o!(Ast;Match).into(), Expression::Nop.into());
+        o.set_mut(
+            symbol_o!(Ast;Match;Arms).into(),
+            OwnedArray::from(vec![OwnedArray::from(vec![Pattern::Wildcard.into()]).into()])
+                .into(),
+        );
+
+        assert_eq!(
+            Expression::try_from(OwnedValue::from(o)),
+            Err(DecodeError::UnexpectedValue {
+                expected: "a pattern and an expression",
+                found: OwnedArray::from(vec![Pattern::Wildcard.into()]).into(),
+            })
+        );
+    }
+
+    #[test]
+    fn decode_missing_key() {
+        let o = OwnedObject::singleton(symbol_o!(Ast;Add;Lhs).into(), Expression::Nop.into());
+
+        assert_eq!(
+            Expression::try_from(OwnedValue::from(o)),
+            Err(DecodeError::MissingKey(symbol_o!(Ast;Add;Rhs)))
+        );
+    }
+
+    #[test]
+    fn decode_unexpected_node() {
+        let o = OwnedObject::singleton(symbol_o!(Ast;Frobnicate).into(), Expression::Nop.into());
+
+        assert_eq!(
+            Expression::try_from(OwnedValue::from(o)),
+            Err(DecodeError::UnexpectedSymbol(symbol_o!(Ast;Frobnicate)))
+        );
+    }
+
+    #[test]
+    fn decode_unexpected_symbol() {
+        assert_eq!(
+            Expression::try_from(OwnedValue::from(symbol_o!(nop))),
+            Err(DecodeError::UnexpectedSymbol(symbol_o!(nop)))
+        );
+    }
+
+    #[test]
+    fn decode_unexpected_display() {
+        let mut o = match OwnedValue::from(Expression::Cond {
+            cond: lit(1.0),
+            if_true: lit(2.0),
+            if_false: lit(3.0),
+            display: CondDisplay::If,
+        }) {
+            OwnedValue::Object(o) => o,
+            _ => unreachable!(),
+        };
+        o.set_mut(symbol_o!(Ast;Cond;Display).into(), symbol_o!(Ast;CondDisplay;Switch).into());
+
+        assert_eq!(
+            Expression::try_from(OwnedValue::from(o)),
+            Err(DecodeError::UnexpectedSymbol(symbol_o!(Ast;CondDisplay;Switch)))
+        );
+    }
+
+    #[test]
+    fn decode_unexpected_key() {
+        let mut o = OwnedObject::singleton(symbol_o!(Ast;Sqrt).into(), Expression::Nop.into());
+        o.set_mut(symbol_o!(Ast;Sqrt;Base).into(), 2.0.into());
+
+        assert_eq!(
+            Expression::try_from(OwnedValue::from(o)),
+            Err(DecodeError::UnexpectedKey(symbol_o!(Ast;Sqrt;Base).into()))
+        );
+    }
+
+    #[test]
+    fn decode_unexpected_value() {
+        assert_eq!(
+            Expression::try_from(OwnedValue::from(12.0)),
+            Err(DecodeError::UnexpectedValue {
+                expected: "an AST node",
+                found: 12.0.into(),
+            })
+        );
+    }
 }
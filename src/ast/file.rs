@@ -0,0 +1,405 @@
+/*
+ * Dermis is an interpreter for a pure, statically typed, imperitive language designed to be edited with a custom IDE.
+ * Copyright (C) 2018 Amelorate
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The on-disk format of a program.
+//!
+//! A program file is the [canonical JSON](::ast::canonical) serialization of an object with a
+//! header and the program:
+//!
+//! ```text
+//! {'Ast::File: 1,
+//!  'Ast::File::Entry: 'app::main,
+//!  'Ast::File::Metadata: {"author": "..."},
+//!  'Ast::File::Program: {'Ast::Program: [...]}}
+//! ```
+//!
+//! Program files can also be stored in the [binary encoding](::ast::binary), which is much
+//! smaller.
+//!
+//! The value under `'Ast::File` is the version of the format, which is checked before anything
+//! else in the file is decoded. Files written in older versions of the format are upgraded by
+//! the [builtin migrations](::ast::migrate).
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use serde_json;
+
+use ast::binary::{self, BinaryError};
+use ast::canonical;
+use ast::decode::{self, DecodeError, Fields};
+use ast::migrate::Migrations;
+use ast::module::Program;
+use value::{Number, OwnedObject, OwnedSymbol, OwnedValue};
+
+/// The version of the file format written by this version of dermis.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// A program along with the header stored in it's file.
+#[derive(Eq, PartialEq, Debug, Clone)]
+pub struct ProgramFile {
+    /// The export that is evaluated to run the program, such as `'app::main`.
+    pub entry: OwnedSymbol,
+    /// Information about the program that does not change how it runs, such as it's author.
+    pub metadata: OwnedObject,
+    pub program: Program,
+}
+
+impl ProgramFile {
+    /// Creates a file for `program` without any metadata.
+    pub fn new(program: Program, entry: OwnedSymbol) -> ProgramFile {
+        ProgramFile {
+            entry,
+            metadata: OwnedObject::empty(),
+            program,
+        }
+    }
+
+    /// Reads a program file from `path`.
+    ///
+    /// # Errors
+    /// [`FileError::UnknownVersion`](FileError::UnknownVersion) if the file was written in a
+    /// version of the format newer than [`FORMAT_VERSION`](FORMAT_VERSION), or in an older version
+    /// that can not be upgraded.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<ProgramFile, FileError> {
+        ProgramFile::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Writes this program file to `path`, replacing the file if it exists.
+    ///
+    /// # Errors
+    /// The errors of [`ProgramFile::write`](ProgramFile::write), in which case the file at `path`
+    /// is left as it was.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), FileError> {
+        let mut bytes = vec![];
+        self.write(&mut bytes)?;
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(&bytes)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads a program file from `reader`. See [`ProgramFile::load`](ProgramFile::load).
+    ///
+    /// # Example
+    /// ```
+    /// #[macro_use]
+    /// extern crate dermis;
+    /// use dermis::ast::file::ProgramFile;
+    /// use dermis::ast::module::Program;
+    ///
+    /// # fn main() {
+    /// let file = ProgramFile::new(Program::default(), symbol_o!(app;main));
+    ///
+    /// let mut bytes = vec![];
+    /// file.write(&mut bytes).unwrap();
+    ///
+    /// assert_eq!(ProgramFile::read(&bytes[..]).unwrap(), file);
+    /// # }
+    /// ```
+    pub fn read<R: Read>(reader: R) -> Result<ProgramFile, FileError> {
+        let val: OwnedValue = serde_json::from_reader(reader)?;
+        ProgramFile::try_from(val)
+    }
+
+    /// Writes this program file to `writer`.
+    ///
+    /// Equal program files are always written as the same bytes.
+    ///
+    /// # Errors
+    /// [`FileError::Json`](FileError::Json) if the file nests values more than
+    /// [`canonical::MAX_DEPTH`](::ast::canonical::MAX_DEPTH) deep, since it could not be read
+    /// back. Nothing is written to `writer` in that case.
+    pub fn write<W: Write>(&self, mut writer: W) -> Result<(), FileError> {
+        let mut bytes = vec![];
+        canonical::to_writer(&mut bytes, &OwnedValue::from(self.clone()))?;
+        writer.write_all(&bytes)?;
+        Ok(())
+    }
+
+    /// Reads a program file in the binary encoding from `reader`.
+    pub fn read_binary<R: Read>(reader: R) -> Result<ProgramFile, FileError> {
+        ProgramFile::try_from(binary::read(reader)?)
+    }
+
+    /// Writes this program file to `writer` in the binary encoding.
+    ///
+    /// # Errors
+    /// [`FileError::Binary`](FileError::Binary) if the file nests values more than
+    /// [`binary::MAX_DEPTH`](::ast::binary::MAX_DEPTH) deep, since it could not be read back.
+    /// Nothing is written to `writer` in that case.
+    pub fn write_binary<W: Write>(&self, mut writer: W) -> Result<(), FileError> {
+        let mut bytes = vec![];
+        binary::write(&mut bytes, &OwnedValue::from(self.clone()))?;
+        writer.write_all(&bytes)?;
+        Ok(())
+    }
+}
+
+/// An error that occured while reading or writing a program file.
+#[derive(Debug)]
+pub enum FileError {
+    Io(io::Error),
+    /// The file is not valid JSON, does not hold a dermis value, or nests values too deep to be
+    /// written as JSON.
+    Json(serde_json::Error),
+    /// The file is not a valid binary encoded value.
+    Binary(BinaryError),
+    /// The file holds a value that is not a program file.
+    Decode(DecodeError),
+    /// The file was written in a version of the format this version of dermis can not read.
+    UnknownVersion(Number),
+}
+
+impl Display for FileError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            FileError::Io(err) => write!(f, "{}", err),
+            FileError::Json(err) => write!(f, "{}", err),
+            FileError::Binary(err) => write!(f, "{}", err),
+            FileError::Decode(err) => write!(f, "{}", err),
+            FileError::UnknownVersion(version) => write!(
+                f,
+                "unknown file format version {}, expected version {}",
+                version, FORMAT_VERSION
+            ),
+        }
+    }
+}
+
+impl Error for FileError {}
+
+impl From<io::Error> for FileError {
+    fn from(err: io::Error) -> FileError {
+        FileError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for FileError {
+    fn from(err: serde_json::Error) -> FileError {
+        FileError::Json(err)
+    }
+}
+
+impl From<BinaryError> for FileError {
+    fn from(err: BinaryError) -> FileError {
+        match err {
+            BinaryError::Io(err) => FileError::Io(err),
+            err => FileError::Binary(err),
+        }
+    }
+}
+
+impl From<DecodeError> for FileError {
+    fn from(err: DecodeError) -> FileError {
+        FileError::Decode(err)
+    }
+}
+
+impl From<ProgramFile> for OwnedValue {
+    fn from(file: ProgramFile) -> OwnedValue {
+        let mut o =
+            OwnedObject::singleton(symbol_o!(Ast;File).into(), f64::from(FORMAT_VERSION).into());
+        o.set_mut(symbol_o!(Ast;File;Entry).into(), file.entry.into());
+        o.set_mut(symbol_o!(Ast;File;Metadata).into(), file.metadata.into());
+        o.set_mut(symbol_o!(Ast;File;Program).into(), file.program.into());
+        o.into()
+    }
+}
+
+/// Decodes a program file from the encoding produced by `From<ProgramFile> for OwnedValue`, or by
+/// an older version of dermis.
+impl TryFrom<OwnedValue> for ProgramFile {
+    type Error = FileError;
+
+    fn try_from(val: OwnedValue) -> Result<ProgramFile, FileError> {
+        let val = Migrations::builtin().upgrade(val)?;
+        let mut f = Fields::new(decode::object(val)?)?;
+        if *f.tag() != symbol_o!(Ast;File) {
+            return Err(DecodeError::UnexpectedSymbol(f.tag().clone()).into());
+        }
+        let version = decode::number(f.take()?)?;
+        if version.into_inner() != f64::from(FORMAT_VERSION) {
+            return Err(FileError::UnknownVersion(version));
+        }
+
+        let file = ProgramFile {
+            entry: decode::symbol(f.take_field("Entry")?)?,
+            metadata: decode::object(f.take_field("Metadata")?)?,
+            program: Program::try_from(f.take_field("Program")?)?,
+        };
+        f.finish()?;
+        Ok(file)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ast::expression::Expression;
+    use ast::module::Module;
+    use std::env;
+    use std::fs;
+    use value::OwnedArray;
+
+    fn file() -> ProgramFile {
+        let mut file = main_file(Expression::Literal(
+            OwnedObject::singleton(symbol_o!(key).into(), "val".into()).into(),
+        ));
+        file.metadata.set_mut("author".into(), "Amelorate".into());
+        file
+    }
+
+    /// A file with a single module exporting `main`.
+    fn main_file(main: Expression) -> ProgramFile {
+        ProgramFile::new(
+            Program {
+                modules: vec![Module {
+                    name: symbol_o!(app),
+                    imports: vec![],
+                    exports: vec![("main".to_string(), main)],
+                }],
+            },
+            symbol_o!(app;main),
+        )
+    }
+
+    /// The most additions a sum in `main` can have before the file is nested more than
+    /// `canonical::MAX_DEPTH` deep, since each addition is three levels of JSON.
+    const DEEPEST_SUM: usize = 34;
+
+    /// `1 + 1 + ...`, with `depth` additions.
+    fn sum(depth: usize) -> Expression {
+        (0..depth).fold(Expression::Literal(1.0.into()), |lhs, _| {
+            Expression::Add(Box::new(lhs), Box::new(Expression::Literal(1.0.into())))
+        })
+    }
+
+    #[test]
+    fn read_write() {
+        let file = file();
+        let mut bytes = vec![];
+        file.write(&mut bytes).unwrap();
+
+        assert_eq!(ProgramFile::read(&bytes[..]).unwrap(), file);
+    }
+
+    #[test]
+    fn read_write_binary() {
+        let file = file();
+        let mut bytes = vec![];
+        file.write_binary(&mut bytes).unwrap();
+
+        assert_eq!(ProgramFile::read_binary(&bytes[..]).unwrap(), file);
+    }
+
+    #[test]
+    fn save_load() {
+        let path = env::temp_dir().join("dermis_file_save_load.json");
+        let file = file();
+
+        file.save(&path).unwrap();
+        let loaded = ProgramFile::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.unwrap(), file);
+    }
+
+    #[test]
+    fn save_load_deep() {
+        let path = env::temp_dir().join("dermis_file_save_load_deep.json");
+        let file = main_file(sum(DEEPEST_SUM));
+
+        file.save(&path).unwrap();
+        let loaded = ProgramFile::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.unwrap(), file);
+    }
+
+    #[test]
+    fn save_too_deep() {
+        let path = env::temp_dir().join("dermis_file_save_too_deep.json");
+        let file = main_file(sum(DEEPEST_SUM + 1));
+
+        let mut bytes = vec![];
+        match file.write(&mut bytes) {
+            Err(FileError::Json(err)) => assert!(err.to_string().contains("nested")),
+            res => panic!("expected a json error, found {:?}", res),
+        }
+        assert!(bytes.is_empty());
+        assert!(file.save(&path).is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn load_missing_file() {
+        let path = env::temp_dir().join("dermis_file_load_missing_file.json");
+
+        match ProgramFile::load(&path) {
+            Err(FileError::Io(err)) => assert_eq!(err.kind(), io::ErrorKind::NotFound),
+            res => panic!("expected an io error, found {:?}", res),
+        }
+    }
+
+    #[test]
+    fn unknown_version() {
+        let mut o = match OwnedValue::from(file()) {
+            OwnedValue::Object(o) => o,
+            _ => unreachable!(),
+        };
+        o.set_mut(symbol_o!(Ast;File).into(), 99.0.into());
+        let mut bytes = vec![];
+        serde_json::to_writer(&mut bytes, &OwnedValue::from(o)).unwrap();
+
+        match ProgramFile::read(&bytes[..]) {
+            Err(err @ FileError::UnknownVersion(_)) => assert_eq!(
+                err.to_string(),
+                "unknown file format version 99, expected version 1"
+            ),
+            res => panic!("expected an unknown version, found {:?}", res),
+        }
+    }
+
+    #[test]
+    fn not_a_file() {
+        let mut bytes = vec![];
+        serde_json::to_writer(&mut bytes, &OwnedValue::from(OwnedArray::empty())).unwrap();
+
+        match ProgramFile::read(&bytes[..]) {
+            Err(FileError::Decode(DecodeError::UnexpectedValue { expected, .. })) => {
+                assert_eq!(expected, "an object")
+            }
+            res => panic!("expected a decode error, found {:?}", res),
+        }
+    }
+
+    #[test]
+    fn invalid_json() {
+        match ProgramFile::read(&b"{'Ast::File"[..]) {
+            Err(FileError::Json(_)) => (),
+            res => panic!("expected a json error, found {:?}", res),
+        }
+    }
+}
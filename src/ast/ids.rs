@@ -0,0 +1,393 @@
+/*
+ * Dermis is an interpreter for a pure, statically typed, imperitive language designed to be edited with a custom IDE.
+ * Copyright (C) 2018 Amelorate
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Identifiers that let an IDE refer to a node across edits, for selections, breakpoints and
+//! comments.
+//!
+//! A node is identified by the [`IdeOption`](::ast::expression::Expression::IdeOption) wrapping
+//! it, which holds the node's id under `'Ide::Id`. Since the id is part of the AST, it is kept
+//! when the expression is encoded and saved, and it moves along with the node when the node is
+//! moved to another place in the AST. [`assign_ids`](assign_ids) gives an id to every node
+//! that does not have one yet.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::mem;
+
+use ast::decode::DecodeError;
+use ast::expression::Expression;
+use ast::path::NodePath;
+use value::{OwnedObject, OwnedValue};
+
+/// The largest id a node can have, since ids are stored as numbers and every whole number up to
+/// 2^53 is exact.
+pub const MAX_ID: u64 = 1 << 53;
+
+/// The identifier of a node, which is unique within an expression.
+#[derive(Eq, PartialEq, Hash, Ord, PartialOrd, Debug, Clone, Copy)]
+pub struct NodeId(pub u64);
+
+impl Display for NodeId {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+impl From<NodeId> for OwnedValue {
+    fn from(id: NodeId) -> OwnedValue {
+        (id.0 as f64).into()
+    }
+}
+
+impl NodeId {
+    /// Decodes an id, which is stored as a whole number from 0 to [`MAX_ID`](MAX_ID).
+    pub(crate) fn decode(val: OwnedValue) -> Result<NodeId, DecodeError> {
+        match val {
+            OwnedValue::Number(n)
+                if n.into_inner().fract() == 0.0
+                    && n.into_inner() >= 0.0
+                    && n.into_inner() <= MAX_ID as f64 =>
+            {
+                Ok(NodeId(n.into_inner() as u64))
+            }
+            found => Err(DecodeError::UnexpectedValue {
+                expected: "a node id",
+                found,
+            }),
+        }
+    }
+}
+
+/// Every id up to [`MAX_ID`](MAX_ID) is already used, so no new id can be given.
+#[derive(Eq, PartialEq, Debug, Clone, Copy)]
+pub struct IdsExhausted;
+
+impl Display for IdsExhausted {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "every node id up to {} is already used", MAX_ID)
+    }
+}
+
+impl error::Error for IdsExhausted {}
+
+/// The id stored in the options of an `IdeOption`, if it has a valid one.
+fn id_in(options: &OwnedObject) -> Option<NodeId> {
+    let val = options.get_opt(&symbol_o!(Ide;Id).into())?;
+    NodeId::decode((*val).clone()).ok()
+}
+
+/// Gives an id to every node of `expr` that does not have one.
+///
+/// Nodes without an id are wrapped in an `IdeOption`, or given an id in the options of the
+/// `IdeOption` already wrapping them. New ids are larger than any id already in `expr`. If more
+/// than one node has the same id, such as after a node was copied, only the first keeps it.
+///
+/// # Errors
+/// [`IdsExhausted`](IdsExhausted) if a new id would be larger than [`MAX_ID`](MAX_ID). The nodes
+/// given an id before that keep it.
+///
+/// # Example
+/// ```
+/// use dermis::ast::expression::Expression;
+/// use dermis::ast::ids::{self, NodeIndex};
+///
+/// let mut expr = Expression::Sqrt(Box::new(Expression::Literal(4.0.into())));
+/// ids::assign_ids(&mut expr).unwrap();
+///
+/// let index = NodeIndex::new(&expr);
+/// assert_eq!(index.len(), 2);
+///
+/// let sqrt = index.ids().next().unwrap();
+/// let node = index.resolve(sqrt, &expr).unwrap();
+/// match node {
+///     Expression::Sqrt(_) => (),
+///     node => panic!("expected a square root, found {:?}", node),
+/// }
+///
+/// // Assigning ids again leaves the existing ids unchanged.
+/// let before = expr.clone();
+/// ids::assign_ids(&mut expr).unwrap();
+/// assert_eq!(expr, before);
+/// ```
+pub fn assign_ids(expr: &mut Expression) -> Result<(), IdsExhausted> {
+    let mut next = NodeIndex::new(expr).next_id()?.0;
+    assign(expr, false, &mut next, &mut BTreeSet::new())
+}
+
+/// Gives ids to `expr` and it's children, where `identified` is whether `expr` is already
+/// wrapped in an `IdeOption` with an id.
+fn assign(
+    expr: &mut Expression,
+    identified: bool,
+    next: &mut u64,
+    seen: &mut BTreeSet<NodeId>,
+) -> Result<(), IdsExhausted> {
+    match expr {
+        Expression::IdeOption { id, options } => {
+            let key: OwnedValue = symbol_o!(Ide;Id).into();
+            let unique = match id_in(options) {
+                Some(existing) => seen.insert(existing),
+                None => false,
+            };
+            if !unique && (!identified || options.contains_key(&key)) {
+                if *next > MAX_ID {
+                    return Err(IdsExhausted);
+                }
+                let new = NodeId(*next);
+                *next += 1;
+                seen.insert(new);
+                options.set_mut(key, new.into());
+            }
+            assign(id, true, next, seen)
+        }
+        _ if !identified => {
+            let node = mem::replace(expr, Expression::Nop);
+            *expr = Expression::IdeOption {
+                id: Box::new(node),
+                options: OwnedObject::empty(),
+            };
+            assign(expr, false, next, seen)
+        }
+        _ => {
+            for (_, child) in expr.children_mut() {
+                assign(child, false, next, seen)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+/// A lookup from the id of each node in an expression to where the node is.
+#[derive(Eq, PartialEq, Debug, Clone, Default)]
+pub struct NodeIndex {
+    paths: BTreeMap<NodeId, NodePath>,
+}
+
+impl NodeIndex {
+    /// Finds every id in `expr`.
+    ///
+    /// If more than one node has the same id, the first one found is used.
+    pub fn new(expr: &Expression) -> NodeIndex {
+        let mut index = NodeIndex::default();
+        index.add(expr, &mut NodePath::root());
+        index
+    }
+
+    fn add(&mut self, expr: &Expression, path: &mut NodePath) {
+        if let Expression::IdeOption { options, .. } = expr {
+            if let Some(id) = id_in(options) {
+                self.paths.entry(id).or_insert_with(|| path.clone());
+            }
+        }
+        for (step, child) in expr.children() {
+            path.push(step);
+            self.add(child, path);
+            path.pop();
+        }
+    }
+
+    /// The path to the `IdeOption` holding the id.
+    pub fn path(&self, id: NodeId) -> Option<&NodePath> {
+        self.paths.get(&id)
+    }
+
+    /// Finds the node with the id in `root`, skipping any `IdeOption` wrapping it.
+    pub fn resolve<'a>(&self, id: NodeId, root: &'a Expression) -> Option<&'a Expression> {
+        let mut node = self.path(id)?.resolve(root)?;
+        while let Expression::IdeOption { id, .. } = node {
+            node = id;
+        }
+        Some(node)
+    }
+
+    /// Every id in the expression, in increasing order.
+    pub fn ids<'a>(&'a self) -> impl Iterator<Item = NodeId> + 'a {
+        self.paths.keys().cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// An id larger than every id in the expression.
+    ///
+    /// # Errors
+    /// [`IdsExhausted`](IdsExhausted) if the expression already has the id
+    /// [`MAX_ID`](MAX_ID).
+    pub fn next_id(&self) -> Result<NodeId, IdsExhausted> {
+        match self.paths.keys().next_back() {
+            Some(id) => {
+                id.0.checked_add(1)
+                    .filter(|&next| next <= MAX_ID)
+                    .map(NodeId)
+                    .ok_or(IdsExhausted)
+            }
+            None => Ok(NodeId(0)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ast::parse;
+    use ast::path::Step;
+    use std::convert::TryFrom;
+
+    fn lit(val: f64) -> Box<Expression> {
+        Box::new(Expression::Literal(val.into()))
+    }
+
+    fn with_id(expr: Expression, id: u64) -> Expression {
+        Expression::IdeOption {
+            id: Box::new(expr),
+            options: OwnedObject::singleton(symbol_o!(Ide;Id).into(), NodeId(id).into()),
+        }
+    }
+
+    #[test]
+    fn assign_every_node() {
+        let mut expr = Expression::Add(lit(1.0), lit(2.0));
+        assign_ids(&mut expr).unwrap();
+
+        assert_eq!(
+            expr,
+            with_id(
+                Expression::Add(
+                    Box::new(with_id(*lit(1.0), 1)),
+                    Box::new(with_id(*lit(2.0), 2))
+                ),
+                0
+            )
+        );
+    }
+
+    #[test]
+    fn assign_keeps_ids() {
+        let mut expr = Expression::Add(Box::new(with_id(*lit(1.0), 7)), lit(2.0));
+        assign_ids(&mut expr).unwrap();
+
+        let index = NodeIndex::new(&expr);
+        assert_eq!(
+            index.ids().collect::<Vec<_>>(),
+            vec![NodeId(7), NodeId(8), NodeId(9)]
+        );
+        assert_eq!(index.resolve(NodeId(7), &expr), Some(&*lit(1.0)));
+    }
+
+    #[test]
+    fn assign_reuses_options() {
+        let options = OwnedObject::singleton(symbol_o!(Ide;Pinned).into(), true.into());
+        let mut expr = Expression::IdeOption {
+            id: lit(1.0),
+            options: options.clone(),
+        };
+        assign_ids(&mut expr).unwrap();
+
+        assert_eq!(
+            expr,
+            Expression::IdeOption {
+                id: lit(1.0),
+                options: options.set(symbol_o!(Ide;Id).into(), 0.0.into()),
+            }
+        );
+    }
+
+    #[test]
+    fn assign_duplicate_ids() {
+        let mut expr = Expression::Add(
+            Box::new(with_id(*lit(1.0), 3)),
+            Box::new(with_id(*lit(1.0), 3)),
+        );
+        assign_ids(&mut expr).unwrap();
+
+        let index = NodeIndex::new(&expr);
+        assert_eq!(index.len(), 3);
+        let mut rhs = NodePath::root();
+        rhs.push(Step::Field("id"));
+        rhs.push(Step::Field("rhs"));
+        assert_eq!(index.path(NodeId(5)), Some(&rhs));
+    }
+
+    #[test]
+    fn decode_range() {
+        assert_eq!(NodeId::decode(0.0.into()), Ok(NodeId(0)));
+        assert_eq!(NodeId::decode((MAX_ID as f64).into()), Ok(NodeId(MAX_ID)));
+        assert!(NodeId::decode((MAX_ID as f64 * 2.0).into()).is_err());
+        assert!(NodeId::decode(1e20.into()).is_err());
+        assert!(NodeId::decode((-1.0).into()).is_err());
+        assert!(NodeId::decode(0.5.into()).is_err());
+    }
+
+    #[test]
+    fn assign_huge_id() {
+        // An id too large to be exact is not an id, so the node is given a new one.
+        let mut expr = Expression::IdeOption {
+            id: lit(1.0),
+            options: OwnedObject::singleton(symbol_o!(Ide;Id).into(), 1e20.into()),
+        };
+        assign_ids(&mut expr).unwrap();
+        assert_eq!(expr, with_id(*lit(1.0), 0));
+    }
+
+    #[test]
+    fn assign_ids_exhausted() {
+        let mut expr = Expression::Sqrt(Box::new(with_id(*lit(1.0), MAX_ID - 1)));
+        assign_ids(&mut expr).unwrap();
+        assert_eq!(
+            NodeIndex::new(&expr).path(NodeId(MAX_ID)),
+            Some(&NodePath::root())
+        );
+        assert_eq!(NodeIndex::new(&expr).next_id(), Err(IdsExhausted));
+
+        let mut expr = Expression::Add(Box::new(with_id(*lit(1.0), MAX_ID)), lit(2.0));
+        assert_eq!(assign_ids(&mut expr), Err(IdsExhausted));
+    }
+
+    #[test]
+    fn ids_survive_edits() {
+        let mut expr = parse::parse("f(x, y)").expr;
+        assign_ids(&mut expr).unwrap();
+        let index = NodeIndex::new(&expr);
+        let y = index
+            .ids()
+            .find(|id| index.resolve(*id, &expr) == Some(&Expression::Var(symbol_o!(y))))
+            .unwrap();
+
+        // Remove the first argument, which moves `y`.
+        if let Expression::IdeOption { id, .. } = &mut expr {
+            if let Expression::Call { args, .. } = &mut **id {
+                args.remove(0);
+            }
+        }
+        let encoded = OwnedValue::from(expr);
+        let decoded = Expression::try_from(encoded).unwrap();
+
+        let index = NodeIndex::new(&decoded);
+        assert_eq!(
+            index.resolve(y, &decoded),
+            Some(&Expression::Var(symbol_o!(y)))
+        );
+    }
+}
@@ -0,0 +1,392 @@
+/*
+ * Dermis is an interpreter for a pure, statically typed, imperitive language designed to be edited with a custom IDE.
+ * Copyright (C) 2018 Amelorate
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Migrations, which upgrade program files written in older versions of the file format.
+//!
+//! A migration transforms the encoded value of a [program file](::ast::file) from one version of
+//! the format to the next, before it is decoded. Files are upgraded one version at a time until
+//! they reach [`FORMAT_VERSION`](::ast::file::FORMAT_VERSION).
+//!
+//! # Versions
+//! 1. The first version of the format.
+
+use std::collections::BTreeMap;
+
+use ast::decode::{self, DecodeError};
+use ast::file::{FileError, FORMAT_VERSION};
+use value::{OwnedArray, OwnedObject, OwnedSymbol, OwnedValue};
+
+/// Upgrades an encoded program file from one version of the format to the next.
+///
+/// The migration is given the whole file, including it's header. The version stored in the file
+/// is updated after the migration succeeds, so the migration does not need to change it.
+pub type Migration = fn(OwnedObject) -> Result<OwnedObject, DecodeError>;
+
+/// A registry of migrations, keyed by the version they upgrade from.
+#[derive(Clone, Debug, Default)]
+pub struct Migrations {
+    migrations: BTreeMap<u32, Migration>,
+}
+
+impl Migrations {
+    /// Creates a registry without any migrations.
+    pub fn new() -> Migrations {
+        Migrations::default()
+    }
+
+    /// Creates a registry with the migrations between every version of the format written by
+    /// dermis.
+    pub fn builtin() -> Migrations {
+        Migrations::new()
+    }
+
+    /// Registers a migration from version `from` to version `from + 1`, replacing any migration
+    /// already registered for `from`.
+    pub fn register(&mut self, from: u32, migration: Migration) {
+        self.migrations.insert(from, migration);
+    }
+
+    /// Upgrades an encoded program file to [`FORMAT_VERSION`](::ast::file::FORMAT_VERSION).
+    ///
+    /// # Errors
+    /// [`FileError::UnknownVersion`](::ast::file::FileError::UnknownVersion) if the file is
+    /// newer than `FORMAT_VERSION`, or if there is no migration from one of the versions between
+    /// the file's version and `FORMAT_VERSION`.
+    ///
+    /// # Example
+    /// ```
+    /// #[macro_use]
+    /// extern crate dermis;
+    /// use dermis::ast::decode::DecodeError;
+    /// use dermis::ast::file::FileError;
+    /// use dermis::ast::migrate::Migrations;
+    /// use dermis::value::{OwnedObject, OwnedValue};
+    ///
+    /// # fn main() {
+    /// let old = OwnedObject::singleton(symbol_o!(Ast;File).into(), 0.0.into());
+    ///
+    /// match Migrations::builtin().upgrade(old.clone().into()) {
+    ///     Err(FileError::UnknownVersion(v)) => assert_eq!(v.into_inner(), 0.0),
+    ///     res => panic!("{:?}", res),
+    /// }
+    ///
+    /// fn add_author(mut file: OwnedObject) -> Result<OwnedObject, DecodeError> {
+    ///     file.set_mut("author".into(), "Amelorate".into());
+    ///     Ok(file)
+    /// }
+    /// let mut migrations = Migrations::builtin();
+    /// migrations.register(0, add_author);
+    ///
+    /// let upgraded = match migrations.upgrade(old.into()).unwrap() {
+    ///     OwnedValue::Object(o) => o,
+    ///     _ => unreachable!(),
+    /// };
+    /// assert_eq!(*upgraded.get(&"author".into()), "Amelorate".into());
+    /// # }
+    /// ```
+    pub fn upgrade(&self, val: OwnedValue) -> Result<OwnedValue, FileError> {
+        let mut file = decode::object(val)?;
+        let mut version = version(&file)?;
+        while version < FORMAT_VERSION {
+            let migration = match self.migrations.get(&version) {
+                Some(migration) => migration,
+                None => return Err(FileError::UnknownVersion(f64::from(version).into())),
+            };
+            file = migration(file)?;
+            version += 1;
+            file.set_mut(symbol_o!(Ast;File).into(), f64::from(version).into());
+        }
+        Ok(file.into())
+    }
+}
+
+/// Reads the version of the format an encoded program file was written in.
+fn version(file: &OwnedObject) -> Result<u32, FileError> {
+    let key = symbol_o!(Ast;File);
+    let version = match file.get_opt(&key.clone().into()) {
+        Some(version) => decode::number((*version).clone())?,
+        None => return Err(DecodeError::MissingKey(key).into()),
+    };
+
+    let v = version.into_inner();
+    if v.fract() == 0.0 && v >= 0.0 && v <= f64::from(FORMAT_VERSION) {
+        Ok(v as u32)
+    } else {
+        Err(FileError::UnknownVersion(version))
+    }
+}
+
+/// Renames the node tag `from` to `to` in an encoded AST.
+///
+/// A tag is renamed where it is a node, as `'ast::nop` is, and where it is the key of a node
+/// along with the keys of the node's fields, so renaming `'Ast::Cond` also renames
+/// `'Ast::Cond::IfTrue`.
+///
+/// Symbols that are data rather than nodes are left unchanged, such as literal values, the names
+/// of variables and modules, and the keys of objects matched by a pattern.
+pub fn rename_symbol(val: OwnedValue, from: &OwnedSymbol, to: &OwnedSymbol) -> OwnedValue {
+    match val {
+        OwnedValue::Symbol(ref sym) if sym == from => to.clone().into(),
+        OwnedValue::Array(arr) => {
+            let arr: Vec<OwnedValue> = arr
+                .iter()
+                .map(|v| rename_symbol((*v).clone(), from, to))
+                .collect();
+            OwnedArray::from(arr).into()
+        }
+        OwnedValue::Object(obj) => {
+            let mut renamed = OwnedObject::empty();
+            for (k, v) in obj.iter() {
+                let v = if is_data_key(&k) {
+                    (*v).clone()
+                } else if is_fields_key(&k) {
+                    rename_fields((*v).clone(), from, to)
+                } else {
+                    rename_symbol((*v).clone(), from, to)
+                };
+                renamed.set_mut(rename_key((*k).clone(), from, to), v);
+            }
+            renamed.into()
+        }
+        val => val,
+    }
+}
+
+/// Renames the key of a node, which is either it's tag or in the namespace of it's tag.
+fn rename_key(key: OwnedValue, from: &OwnedSymbol, to: &OwnedSymbol) -> OwnedValue {
+    match key {
+        OwnedValue::Symbol(ref sym) if sym == from => to.clone().into(),
+        OwnedValue::Symbol(ref sym) if sym.get_namespace() == Some(from) => {
+            OwnedSymbol::new_local(sym.get_name().clone(), to.clone()).into()
+        }
+        key => key,
+    }
+}
+
+/// Renames the tag in the values of an object whose keys are field names, and whose values are
+/// nodes.
+fn rename_fields(val: OwnedValue, from: &OwnedSymbol, to: &OwnedSymbol) -> OwnedValue {
+    match val {
+        OwnedValue::Object(obj) => {
+            let mut renamed = OwnedObject::empty();
+            for (k, v) in obj.iter() {
+                renamed.set_mut((*k).clone(), rename_symbol((*v).clone(), from, to));
+            }
+            renamed.into()
+        }
+        val => val,
+    }
+}
+
+/// Whether the value stored under `key` in an encoded AST is data, such as a literal value or a
+/// name, rather than nodes.
+fn is_data_key(key: &OwnedValue) -> bool {
+    [
+        symbol_o!(Ast;Literal),
+        symbol_o!(Ast;IdeOption;Options),
+        symbol_o!(Pattern;Literal),
+        symbol_o!(Pattern;Bind),
+        symbol_o!(Ast;Let),
+        symbol_o!(Ast;Var),
+        symbol_o!(Ast;Lambda),
+        symbol_o!(Ast;Assign),
+        symbol_o!(Ast;AssignAt),
+        symbol_o!(Ast;While;Acc),
+        symbol_o!(Ast;ForEach;Item),
+        symbol_o!(Ast;ForEach;Key),
+        symbol_o!(Ast;ForEach;Acc),
+        symbol_o!(Ast;Module),
+        symbol_o!(Ast;Module;Imports),
+        symbol_o!(Ast;File;Entry),
+        symbol_o!(Ast;File;Metadata),
+    ]
+    .iter()
+    .any(|data| *key == data.clone().into())
+}
+
+/// Whether the value stored under `key` in an encoded AST is an object of field names to nodes.
+fn is_fields_key(key: &OwnedValue) -> bool {
+    *key == symbol_o!(Pattern;Object).into() || *key == symbol_o!(Type;Object).into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ast::expression::Expression;
+    use ast::file::ProgramFile;
+    use ast::module::{Module, Program};
+    use ast::pattern::Pattern;
+    use std::convert::TryFrom;
+
+    /// A migration from a version 0 in which `Nop` was encoded as `'Ast::Nop` and `Add` was
+    /// called `Sum`.
+    fn version_0_to_1(mut file: OwnedObject) -> Result<OwnedObject, DecodeError> {
+        let key: OwnedValue = symbol_o!(Ast;File;Program).into();
+        let program = (*file.get(&key)).clone();
+        let program = rename_symbol(program, &symbol_o!(Ast;Nop), &symbol_o!(ast;nop));
+        let program = rename_symbol(program, &symbol_o!(Ast;Sum), &symbol_o!(Ast;Add));
+        file.set_mut(key, program);
+        Ok(file)
+    }
+
+    fn migrations() -> Migrations {
+        let mut migrations = Migrations::new();
+        migrations.register(0, version_0_to_1);
+        migrations
+    }
+
+    /// Encodes a file containing `main`, as it would have been written by version 0.
+    fn version_0(main: OwnedValue) -> OwnedValue {
+        let module = Module {
+            name: symbol_o!(app),
+            imports: vec![],
+            exports: vec![],
+        };
+        let mut module = match OwnedValue::from(module) {
+            OwnedValue::Object(o) => o,
+            _ => unreachable!(),
+        };
+        let export = OwnedArray::from(vec!["main".into(), main]);
+        module.set_mut(
+            symbol_o!(Ast;Module;Exports).into(),
+            OwnedArray::from(vec![export.into()]).into(),
+        );
+        let program = OwnedObject::singleton(
+            symbol_o!(Ast;Program).into(),
+            OwnedArray::from(vec![module.into()]).into(),
+        );
+
+        let mut file = OwnedObject::singleton(symbol_o!(Ast;File).into(), 0.0.into());
+        file.set_mut(symbol_o!(Ast;File;Entry).into(), symbol_o!(app;main).into());
+        file.set_mut(
+            symbol_o!(Ast;File;Metadata).into(),
+            OwnedObject::empty().into(),
+        );
+        file.set_mut(symbol_o!(Ast;File;Program).into(), program.into());
+        file.into()
+    }
+
+    fn upgrade_main(main: OwnedValue) -> Expression {
+        let file = migrations().upgrade(version_0(main)).unwrap();
+        let file = ProgramFile::try_from(file).unwrap();
+        file.program.modules[0].exports[0].1.clone()
+    }
+
+    #[test]
+    fn upgrade_renames_tags() {
+        let mut sum = OwnedObject::empty();
+        sum.set_mut(symbol_o!(Ast;Sum;Lhs).into(), symbol_o!(Ast;Nop).into());
+        sum.set_mut(symbol_o!(Ast;Sum;Rhs).into(), symbol_o!(Ast;Nop).into());
+
+        assert_eq!(
+            upgrade_main(sum.into()),
+            Expression::Add(Box::new(Expression::Nop), Box::new(Expression::Nop))
+        );
+    }
+
+    #[test]
+    fn upgrade_keeps_literals() {
+        let nop: OwnedValue = symbol_o!(Ast;Nop).into();
+        let arm = OwnedArray::from(vec![
+            OwnedObject::singleton(symbol_o!(Pattern;Literal).into(), nop.clone()).into(),
+            OwnedObject::singleton(symbol_o!(Ast;Literal).into(), nop.clone()).into(),
+        ]);
+        let arms = OwnedArray::from(vec![arm.into()]);
+        let mut main = OwnedObject::singleton(symbol_o!(Ast;Match).into(), nop);
+        main.set_mut(symbol_o!(Ast;Match;Arms).into(), arms.into());
+
+        assert_eq!(
+            upgrade_main(main.into()),
+            Expression::Match {
+                scrutinee: Box::new(Expression::Nop),
+                arms: vec![(
+                    Pattern::Literal(symbol_o!(Ast;Nop).into()),
+                    Expression::Literal(symbol_o!(Ast;Nop).into()),
+                )],
+            }
+        );
+    }
+
+    #[test]
+    fn upgrade_keeps_names() {
+        // `let 'Ast::Nop = nop in fn('Ast::Sum) => 'Ast::Nop`
+        let lambda = OwnedObject::singleton(
+            symbol_o!(Ast;Lambda).into(),
+            OwnedArray::from(vec![symbol_o!(Ast;Sum).into()]).into(),
+        )
+        .set(
+            symbol_o!(Ast;Lambda;Body).into(),
+            OwnedObject::singleton(symbol_o!(Ast;Var).into(), symbol_o!(Ast;Nop).into()).into(),
+        );
+        let mut main = OwnedObject::singleton(symbol_o!(Ast;Let).into(), symbol_o!(Ast;Nop).into());
+        main.set_mut(symbol_o!(Ast;Let;Value).into(), symbol_o!(Ast;Nop).into());
+        main.set_mut(symbol_o!(Ast;Let;Body).into(), lambda.into());
+
+        assert_eq!(
+            upgrade_main(main.into()),
+            Expression::Let {
+                name: symbol_o!(Ast;Nop),
+                value: Box::new(Expression::Nop),
+                body: Box::new(Expression::Lambda {
+                    params: vec![symbol_o!(Ast;Sum)],
+                    body: Box::new(Expression::Var(symbol_o!(Ast;Nop))),
+                }),
+            }
+        );
+    }
+
+    #[test]
+    fn rename_keeps_field_names() {
+        let mut fields = OwnedObject::empty();
+        fields.set_mut(symbol_o!(Ast;Nop).into(), symbol_o!(Ast;Nop).into());
+        let pattern = OwnedObject::singleton(symbol_o!(Pattern;Object).into(), fields.into());
+
+        let mut renamed = OwnedObject::empty();
+        renamed.set_mut(symbol_o!(Ast;Nop).into(), symbol_o!(ast;nop).into());
+        assert_eq!(
+            rename_symbol(pattern.into(), &symbol_o!(Ast;Nop), &symbol_o!(ast;nop)),
+            OwnedObject::singleton(symbol_o!(Pattern;Object).into(), renamed.into()).into()
+        );
+    }
+
+    #[test]
+    fn upgrade_current_version() {
+        let file = ProgramFile::new(Program::default(), symbol_o!(app;main));
+        let encoded = OwnedValue::from(file);
+
+        assert_eq!(Migrations::new().upgrade(encoded.clone()).unwrap(), encoded);
+    }
+
+    #[test]
+    fn upgrade_missing_migration() {
+        match Migrations::builtin().upgrade(version_0(symbol_o!(ast;nop).into())) {
+            Err(FileError::UnknownVersion(v)) => assert_eq!(v.into_inner(), 0.0),
+            res => panic!("expected an unknown version, found {:?}", res),
+        }
+    }
+
+    #[test]
+    fn upgrade_fractional_version() {
+        let file = OwnedObject::singleton(symbol_o!(Ast;File).into(), 0.5.into());
+
+        match migrations().upgrade(file.into()) {
+            Err(FileError::UnknownVersion(v)) => assert_eq!(v.into_inner(), 0.5),
+            res => panic!("expected an unknown version, found {:?}", res),
+        }
+    }
+}
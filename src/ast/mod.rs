@@ -19,4 +19,17 @@
 //! Definition of the language AST and file format, including seralization and deseralization into
 //! dermis values.
 
+pub mod binary;
+pub mod canonical;
+pub mod decode;
 pub mod expression;
+pub mod file;
+pub mod ids;
+pub mod migrate;
+pub mod module;
+pub mod options;
+pub mod parse;
+pub mod path;
+pub mod pretty;
+pub mod pattern;
+pub mod types;
@@ -0,0 +1,408 @@
+/*
+ * Dermis is an interpreter for a pure, statically typed, imperitive language designed to be edited with a custom IDE.
+ * Copyright (C) 2018 Amelorate
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Modules, which split a program into named parts.
+//!
+//! Each module has a symbol as it's name, such as `'std::math`, and exports bindings under that
+//! namespace. An export `sqrt` of the module `'std::math` is bound to the variable
+//! `'std::math::sqrt` in every module that imports `'std::math`.
+
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use serde::de::Error as DeError;
+use serde::{Deserialize, Deserializer};
+
+use ast::decode::{self, DecodeError, Fields};
+use ast::expression::Expression;
+use value::symbol::{self, SymbolError};
+use value::{OwnedArray, OwnedObject, OwnedSymbol, OwnedValue};
+
+#[derive(Eq, PartialEq, Hash, Ord, PartialOrd, Debug, Clone, Serialize, Deserialize)]
+pub struct Module {
+    pub name: OwnedSymbol,
+    /// The modules whose exports are in scope in this module.
+    ///
+    /// The exports of modules imported by an imported module are not in scope.
+    pub imports: Vec<OwnedSymbol>,
+    /// The bindings exported by this module, which are evaluated in order.
+    ///
+    /// Each export is in scope for the exports after it. Names must be valid symbol names, see
+    /// [`check_name`](::value::symbol::check_name).
+    #[serde(deserialize_with = "deserialize_exports")]
+    pub exports: Vec<(String, Expression)>,
+}
+
+impl Module {
+    /// The variable an export of this module is bound to, `'module::name`.
+    ///
+    /// # Errors
+    /// `name` is not a valid name, see [`check_name`](::value::symbol::check_name). The names of
+    /// the exports of a decoded or deserialized module are always valid.
+    pub fn export_symbol(&self, name: &str) -> Result<OwnedSymbol, SymbolError> {
+        OwnedSymbol::try_new_local(name.to_string(), self.name.clone())
+    }
+}
+
+/// A program made of several modules.
+#[derive(Eq, PartialEq, Hash, Ord, PartialOrd, Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Program {
+    pub modules: Vec<Module>,
+}
+
+impl Program {
+    /// Finds the module named `name`.
+    pub fn module(&self, name: &OwnedSymbol) -> Option<&Module> {
+        self.modules.iter().find(|m| m.name == *name)
+    }
+
+    /// Finds the module `root` and every module it imports, directly or indirectly, ordered so
+    /// that every module comes after the modules it imports.
+    ///
+    /// # Errors
+    /// [`ModuleError::UnknownModule`](ModuleError::UnknownModule) if a module is imported that is
+    /// not part of this program, or [`ModuleError::ImportCycle`](ModuleError::ImportCycle) if a
+    /// module imports itself, directly or indirectly.
+    ///
+    /// # Example
+    /// ```
+    /// #[macro_use]
+    /// extern crate dermis;
+    /// use dermis::ast::module::{Module, ModuleError, Program};
+    ///
+    /// # fn main() {
+    /// let module = |name, imports| Module {
+    ///     name,
+    ///     imports,
+    ///     exports: vec![],
+    /// };
+    /// let program = Program {
+    ///     modules: vec![
+    ///         module(symbol_o!(a), vec![symbol_o!(b)]),
+    ///         module(symbol_o!(b), vec![symbol_o!(a)]),
+    ///     ],
+    /// };
+    ///
+    /// assert_eq!(
+    ///     program.load_order(&symbol_o!(a)),
+    ///     Err(ModuleError::ImportCycle(vec![
+    ///         symbol_o!(a),
+    ///         symbol_o!(b),
+    ///         symbol_o!(a),
+    ///     ]))
+    /// );
+    /// # }
+    /// ```
+    pub fn load_order(&self, root: &OwnedSymbol) -> Result<Vec<&Module>, ModuleError> {
+        let mut order = vec![];
+        self.visit(root, &mut vec![], &mut order)?;
+        Ok(order)
+    }
+
+    /// Adds `name` and it's imports to `order`, if they are not already in it.
+    ///
+    /// `importing` holds the modules whose imports are being visited, which would form a cycle if
+    /// they were imported again.
+    fn visit<'a>(
+        &'a self,
+        name: &OwnedSymbol,
+        importing: &mut Vec<OwnedSymbol>,
+        order: &mut Vec<&'a Module>,
+    ) -> Result<(), ModuleError> {
+        if order.iter().any(|m| m.name == *name) {
+            return Ok(());
+        }
+        if let Some(i) = importing.iter().position(|n| n == name) {
+            let mut cycle = importing[i..].to_vec();
+            cycle.push(name.clone());
+            return Err(ModuleError::ImportCycle(cycle));
+        }
+        let module = self
+            .module(name)
+            .ok_or_else(|| ModuleError::UnknownModule(name.clone()))?;
+
+        importing.push(name.clone());
+        for import in &module.imports {
+            self.visit(import, importing, order)?;
+        }
+        importing.pop();
+        order.push(module);
+        Ok(())
+    }
+}
+
+/// An error in how the modules of a program import each other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModuleError {
+    /// A module was imported that is not part of the program.
+    UnknownModule(OwnedSymbol),
+    /// A module imports itself, directly or through other modules.
+    ///
+    /// Holds the modules in the cycle, starting and ending with the same module.
+    ImportCycle(Vec<OwnedSymbol>),
+}
+
+impl Display for ModuleError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ModuleError::UnknownModule(name) => write!(f, "unknown module {}", name),
+            ModuleError::ImportCycle(cycle) => {
+                write!(f, "import cycle: ")?;
+                let mut first = true;
+                for name in cycle {
+                    if !first {
+                        write!(f, " -> ")?;
+                    } else {
+                        first = false;
+                    }
+                    write!(f, "{}", name)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Error for ModuleError {}
+
+impl From<Module> for OwnedValue {
+    fn from(module: Module) -> OwnedValue {
+        let imports: Vec<OwnedValue> = module.imports.into_iter().map(|i| i.into()).collect();
+        let exports: Vec<OwnedValue> = module
+            .exports
+            .into_iter()
+            .map(|(name, expr)| OwnedArray::from(vec![name.into(), expr.into()]).into())
+            .collect();
+
+        let mut o = OwnedObject::singleton(symbol_o!(Ast;Module).into(), module.name.into());
+        o.set_mut(
+            symbol_o!(Ast;Module;Imports).into(),
+            OwnedArray::from(imports).into(),
+        );
+        o.set_mut(
+            symbol_o!(Ast;Module;Exports).into(),
+            OwnedArray::from(exports).into(),
+        );
+        o.into()
+    }
+}
+
+impl From<Program> for OwnedValue {
+    fn from(program: Program) -> OwnedValue {
+        let modules: Vec<OwnedValue> = program.modules.into_iter().map(|m| m.into()).collect();
+        OwnedObject::singleton(
+            symbol_o!(Ast;Program).into(),
+            OwnedArray::from(modules).into(),
+        )
+        .into()
+    }
+}
+
+/// Decodes a module from the encoding produced by `From<Module> for OwnedValue`.
+impl TryFrom<OwnedValue> for Module {
+    type Error = DecodeError;
+
+    fn try_from(val: OwnedValue) -> Result<Module, DecodeError> {
+        let mut f = Fields::new(decode::object(val)?)?;
+        if *f.tag() != symbol_o!(Ast;Module) {
+            return Err(DecodeError::UnexpectedSymbol(f.tag().clone()));
+        }
+
+        let module = Module {
+            name: decode::symbol(f.take()?)?,
+            imports: decode::array(f.take_field("Imports")?)?
+                .iter()
+                .map(|i| decode::symbol((*i).clone()))
+                .collect::<Result<_, _>>()?,
+            exports: decode::array(f.take_field("Exports")?)?
+                .iter()
+                .map(|e| decode_export((*e).clone()))
+                .collect::<Result<_, _>>()?,
+        };
+        f.finish()?;
+        Ok(module)
+    }
+}
+
+/// Decodes a program from the encoding produced by `From<Program> for OwnedValue`.
+impl TryFrom<OwnedValue> for Program {
+    type Error = DecodeError;
+
+    fn try_from(val: OwnedValue) -> Result<Program, DecodeError> {
+        let mut f = Fields::new(decode::object(val)?)?;
+        if *f.tag() != symbol_o!(Ast;Program) {
+            return Err(DecodeError::UnexpectedSymbol(f.tag().clone()));
+        }
+
+        let program = Program {
+            modules: decode::array(f.take()?)?
+                .iter()
+                .map(|m| Module::try_from((*m).clone()))
+                .collect::<Result<_, _>>()?,
+        };
+        f.finish()?;
+        Ok(program)
+    }
+}
+
+/// Decodes an export of a module, which is encoded as an array of the name and the expression.
+fn decode_export(val: OwnedValue) -> Result<(String, Expression), DecodeError> {
+    let export = decode::array(val)?;
+    if export.len() == 2 {
+        if let OwnedValue::String(ref name) = *export.get_unwrapped(0) {
+            symbol::check_name(name)
+                .map_err(|err| DecodeError::InvalidSymbolName(name.clone(), err))?;
+            let expr = Expression::try_from((*export.get_unwrapped(1)).clone())?;
+            return Ok((name.clone(), expr));
+        }
+    }
+    Err(DecodeError::UnexpectedValue {
+        expected: "an export name and an expression",
+        found: export.into(),
+    })
+}
+
+fn deserialize_exports<'de, D: Deserializer<'de>>(
+    d: D,
+) -> Result<Vec<(String, Expression)>, D::Error> {
+    let exports: Vec<(String, Expression)> = Vec::deserialize(d)?;
+    for (name, _) in &exports {
+        symbol::check_name(name).map_err(|err| {
+            D::Error::custom(format_args!(
+                "{:?} is not a valid export name: {}",
+                name, err
+            ))
+        })?;
+    }
+    Ok(exports)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json;
+
+    fn module(name: OwnedSymbol, imports: Vec<OwnedSymbol>) -> Module {
+        Module {
+            name,
+            imports,
+            exports: vec![],
+        }
+    }
+
+    #[test]
+    fn load_order() {
+        // a imports b and c, which both import d.
+        let program = Program {
+            modules: vec![
+                module(symbol_o!(a), vec![symbol_o!(b), symbol_o!(c)]),
+                module(symbol_o!(b), vec![symbol_o!(d)]),
+                module(symbol_o!(c), vec![symbol_o!(d)]),
+                module(symbol_o!(d), vec![]),
+                module(symbol_o!(unused), vec![]),
+            ],
+        };
+
+        let order: Vec<&OwnedSymbol> = program
+            .load_order(&symbol_o!(a))
+            .unwrap()
+            .into_iter()
+            .map(|m| &m.name)
+            .collect();
+
+        assert_eq!(
+            order,
+            vec![&symbol_o!(d), &symbol_o!(b), &symbol_o!(c), &symbol_o!(a)]
+        );
+    }
+
+    #[test]
+    fn load_order_self_import() {
+        let program = Program {
+            modules: vec![module(symbol_o!(a), vec![symbol_o!(a)])],
+        };
+
+        let err = program.load_order(&symbol_o!(a)).unwrap_err();
+
+        assert_eq!(
+            err,
+            ModuleError::ImportCycle(vec![symbol_o!(a), symbol_o!(a)])
+        );
+        assert_eq!(err.to_string(), "import cycle: 'a -> 'a");
+    }
+
+    #[test]
+    fn load_order_unknown_module() {
+        let program = Program {
+            modules: vec![module(symbol_o!(a), vec![symbol_o!(std;missing)])],
+        };
+
+        assert_eq!(
+            program.load_order(&symbol_o!(a)),
+            Err(ModuleError::UnknownModule(symbol_o!(std;missing)))
+        );
+    }
+
+    #[test]
+    fn round_trip_program() {
+        let program = Program {
+            modules: vec![
+                Module {
+                    name: symbol_o!(std;math),
+                    imports: vec![],
+                    exports: vec![("pi".to_string(), Expression::Literal(3.0.into()))],
+                },
+                module(symbol_o!(app), vec![symbol_o!(std;math)]),
+            ],
+        };
+        let encoded: OwnedValue = program.clone().into();
+
+        assert_eq!(Program::try_from(encoded), Ok(program));
+    }
+
+    #[test]
+    fn decode_export_with_space() {
+        let mut module: OwnedObject = match OwnedValue::from(module(symbol_o!(a), vec![])) {
+            OwnedValue::Object(o) => o,
+            _ => unreachable!(),
+        };
+        let export = OwnedArray::from(vec!["a b".into(), Expression::Nop.into()]);
+        module.set_mut(
+            symbol_o!(Ast;Module;Exports).into(),
+            OwnedArray::from(vec![export.clone().into()]).into(),
+        );
+
+        assert_eq!(
+            Module::try_from(OwnedValue::from(module)),
+            Err(DecodeError::InvalidSymbolName(
+                "a b".to_string(),
+                SymbolError::InvalidChar(' ')
+            ))
+        );
+    }
+
+    #[test]
+    fn deserialize_export_with_space() {
+        let json = r#"{"name":{"Global":{"name":"a"}},"imports":[],"exports":[["a b","Nop"]]}"#;
+
+        assert!(serde_json::from_str::<Module>(json).is_err());
+        assert!(serde_json::from_str::<Module>(&json.replace("a b", "ab")).is_ok());
+    }
+}
@@ -0,0 +1,252 @@
+/*
+ * Dermis is an interpreter for a pure, statically typed, imperitive language designed to be edited with a custom IDE.
+ * Copyright (C) 2018 Amelorate
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The options an IDE stores on an expression with
+//! [`IdeOption`](::ast::expression::Expression::IdeOption).
+//!
+//! The options known to dermis are stored under keys in the namespace `'Ide`, for example
+//! `{'Ide::Collapsed: 'true, 'Ide::Comments: ["TODO"]}`. Any other key, such as one used by an
+//! IDE plugin, is kept as it is.
+
+use std::convert::TryFrom;
+
+use ast::decode::{self, DecodeError};
+use ast::ids::NodeId;
+use value::{OwnedArray, OwnedObject, OwnedValue};
+
+/// The options of an [`IdeOption`](::ast::expression::Expression::IdeOption).
+///
+/// # Example
+/// ```
+/// #[macro_use]
+/// extern crate dermis;
+/// use dermis::ast::options::IdeOptions;
+/// use dermis::value::OwnedObject;
+/// use std::convert::TryFrom;
+///
+/// # fn main() {
+/// let mut obj = OwnedObject::singleton(symbol_o!(Ide;Pinned).into(), true.into());
+/// obj.set_mut(symbol_o!(plugin;lint).into(), "off".into());
+///
+/// let options = IdeOptions::try_from(obj.clone()).unwrap();
+/// assert!(options.pinned);
+/// assert_eq!(*options.extra.get(&symbol_o!(plugin;lint).into()), "off".into());
+///
+/// assert_eq!(OwnedObject::from(options), obj);
+/// # }
+/// ```
+#[derive(Eq, PartialEq, Hash, Ord, PartialOrd, Debug, Clone, Default)]
+pub struct IdeOptions {
+    /// The id of the node, see [`ast::ids`](::ast::ids).
+    pub id: Option<NodeId>,
+    /// Whether the expression is shown collapsed, hiding it's children.
+    pub collapsed: bool,
+    /// The colour the expression is highlighted with, such as `"#ff8800"`.
+    pub colour: Option<String>,
+    /// A name shown in place of the expression.
+    pub display_name: Option<String>,
+    /// Whether the expression is kept in view while scrolling.
+    pub pinned: bool,
+    /// Comments on the expression, in the order they are shown.
+    pub comments: Vec<String>,
+    /// The options not known to dermis, which are kept so that they are not lost when the
+    /// options are written back.
+    ///
+    /// The keys of the options known to dermis are left out when the options are encoded, since
+    /// those options are stored in the fields above.
+    pub extra: OwnedObject,
+}
+
+/// The keys of the options known to dermis.
+fn known_keys() -> [OwnedValue; 6] {
+    [
+        symbol_o!(Ide;Id).into(),
+        symbol_o!(Ide;Collapsed).into(),
+        symbol_o!(Ide;Colour).into(),
+        symbol_o!(Ide;DisplayName).into(),
+        symbol_o!(Ide;Pinned).into(),
+        symbol_o!(Ide;Comments).into(),
+    ]
+}
+
+/// Encodes the options, leaving out the known options that have their default value.
+impl From<IdeOptions> for OwnedObject {
+    fn from(options: IdeOptions) -> OwnedObject {
+        let mut o = options.extra;
+        for key in &known_keys() {
+            o.remove_mut(key);
+        }
+        if let Some(id) = options.id {
+            o.set_mut(symbol_o!(Ide;Id).into(), id.into());
+        }
+        if options.collapsed {
+            o.set_mut(symbol_o!(Ide;Collapsed).into(), true.into());
+        }
+        if let Some(colour) = options.colour {
+            o.set_mut(symbol_o!(Ide;Colour).into(), colour.into());
+        }
+        if let Some(name) = options.display_name {
+            o.set_mut(symbol_o!(Ide;DisplayName).into(), name.into());
+        }
+        if options.pinned {
+            o.set_mut(symbol_o!(Ide;Pinned).into(), true.into());
+        }
+        if !options.comments.is_empty() {
+            let comments: Vec<OwnedValue> = options.comments.into_iter().map(From::from).collect();
+            o.set_mut(
+                symbol_o!(Ide;Comments).into(),
+                OwnedArray::from(comments).into(),
+            );
+        }
+        o
+    }
+}
+
+impl TryFrom<OwnedObject> for IdeOptions {
+    type Error = DecodeError;
+
+    fn try_from(mut o: OwnedObject) -> Result<IdeOptions, DecodeError> {
+        let mut take = |key: OwnedValue| o.pop_mut(&key).map(|v| (*v).clone());
+
+        let id = match take(symbol_o!(Ide;Id).into()) {
+            Some(v) => Some(NodeId::decode(v)?),
+            None => None,
+        };
+        let collapsed = match take(symbol_o!(Ide;Collapsed).into()) {
+            Some(v) => decode::boolean(v)?,
+            None => false,
+        };
+        let colour = match take(symbol_o!(Ide;Colour).into()) {
+            Some(v) => Some(decode::string(v)?),
+            None => None,
+        };
+        let display_name = match take(symbol_o!(Ide;DisplayName).into()) {
+            Some(v) => Some(decode::string(v)?),
+            None => None,
+        };
+        let pinned = match take(symbol_o!(Ide;Pinned).into()) {
+            Some(v) => decode::boolean(v)?,
+            None => false,
+        };
+        let comments = match take(symbol_o!(Ide;Comments).into()) {
+            Some(v) => decode::array(v)?
+                .iter()
+                .map(|c| decode::string((*c).clone()))
+                .collect::<Result<_, _>>()?,
+            None => vec![],
+        };
+
+        Ok(IdeOptions {
+            id,
+            collapsed,
+            colour,
+            display_name,
+            pinned,
+            comments,
+            extra: o,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut options = IdeOptions {
+            id: Some(NodeId(4)),
+            collapsed: true,
+            colour: Some("#ff8800".to_string()),
+            display_name: Some("main loop".to_string()),
+            pinned: false,
+            comments: vec!["TODO".to_string(), "check the limit".to_string()],
+            extra: OwnedObject::singleton("collapsed".into(), 1.0.into()),
+        };
+        options
+            .extra
+            .set_mut(symbol_o!(plugin;data).into(), OwnedArray::empty().into());
+
+        let encoded = OwnedObject::from(options.clone());
+
+        assert_eq!(encoded.len(), 7);
+        assert_eq!(IdeOptions::try_from(encoded), Ok(options));
+    }
+
+    #[test]
+    fn defaults() {
+        assert_eq!(
+            IdeOptions::try_from(OwnedObject::empty()),
+            Ok(IdeOptions::default())
+        );
+        assert_eq!(
+            OwnedObject::from(IdeOptions::default()),
+            OwnedObject::empty()
+        );
+
+        let o = OwnedObject::singleton(symbol_o!(Ide;Pinned).into(), false.into());
+        assert_eq!(IdeOptions::try_from(o), Ok(IdeOptions::default()));
+    }
+
+    #[test]
+    fn known_keys_in_extra() {
+        let options = IdeOptions {
+            pinned: false,
+            comments: vec!["new".to_string()],
+            extra: OwnedObject::singleton(symbol_o!(Ide;Pinned).into(), true.into()).set(
+                symbol_o!(Ide;Comments).into(),
+                OwnedArray::from(vec!["old".into()]).into(),
+            ),
+            ..IdeOptions::default()
+        };
+
+        let encoded = OwnedObject::from(options);
+
+        assert_eq!(
+            encoded,
+            OwnedObject::singleton(
+                symbol_o!(Ide;Comments).into(),
+                OwnedArray::from(vec!["new".into()]).into(),
+            )
+        );
+    }
+
+    #[test]
+    fn invalid_option() {
+        let o = OwnedObject::singleton(symbol_o!(Ide;Collapsed).into(), 1.0.into());
+        assert_eq!(
+            IdeOptions::try_from(o),
+            Err(DecodeError::UnexpectedValue {
+                expected: "'true or 'false",
+                found: 1.0.into(),
+            })
+        );
+
+        let o = OwnedObject::singleton(
+            symbol_o!(Ide;Comments).into(),
+            OwnedArray::from(vec!["ok".into(), symbol_o!(no).into()]).into(),
+        );
+        assert_eq!(
+            IdeOptions::try_from(o),
+            Err(DecodeError::UnexpectedValue {
+                expected: "a string",
+                found: symbol_o!(no).into(),
+            })
+        );
+    }
+}
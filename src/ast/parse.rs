@@ -0,0 +1,1501 @@
+/*
+ * Dermis is an interpreter for a pure, statically typed, imperitive language designed to be edited with a custom IDE.
+ * Copyright (C) 2018 Amelorate
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Parses the text written by the [pretty-printer](::ast::pretty) back into an expression, for
+//! writing programs in tests and code review.
+//!
+//! The syntax is described in the [`pretty`](::ast::pretty) module. Whitespace is not
+//! significant, except that the segments of a variable's path must not be separated by spaces:
+//! `ns::name` is a variable, while `head :: tail` in a pattern is a cons pattern.
+//!
+//! The parser recovers from errors, so every error in the text is reported at once. An
+//! expression that could not be parsed is replaced by `Nop`.
+//!
+//! Expressions, values, types and patterns can not be nested more than
+//! [`MAX_DEPTH`](MAX_DEPTH) deep, so that parsing can not overflow the stack.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use ast::expression::{CondDisplay, Expression};
+use ast::path::NodePath;
+use ast::pattern::Pattern;
+use ast::pretty::{string_literal, Prec, KEYWORDS};
+use ast::types::Type;
+use value::symbol::{is_symbol_char, SymbolError};
+use value::{Number, OwnedArray, OwnedObject, OwnedSymbol, OwnedValue};
+
+type E = Box<Expression>;
+
+/// How deeply expressions, values, types and patterns can be nested in the parsed text.
+///
+/// This is low enough that a thread with the default stack size can parse it in a debug build.
+pub const MAX_DEPTH: usize = 32;
+
+/// A range of bytes in the parsed text.
+#[derive(Eq, PartialEq, Hash, Ord, PartialOrd, Debug, Clone, Copy, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+/// An error found while parsing, along with where in the text it was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub span: Span,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{} at {}", self.kind, self.span)
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A character was found that does not start any token.
+    UnexpectedChar(char),
+    /// A string was not closed before the end of the text.
+    UnterminatedString,
+    /// A backslash in a string was followed by a character that can not be escaped.
+    InvalidEscape(char),
+    /// A token was found where it can not be used.
+    Expected { expected: String, found: String },
+    /// A comparison was used as the operand of another comparison without parentheses, such as
+    /// `a < b < c`.
+    ChainedComparison,
+    /// Something other than a variable or an element of a variable was assigned to.
+    InvalidAssignment,
+    /// A symbol or variable had a name that is not valid, such as `'_`.
+    InvalidSymbol(SymbolError),
+    /// Something was nested more than [`MAX_DEPTH`](MAX_DEPTH) deep.
+    TooDeep,
+}
+
+impl Display for ParseErrorKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        use self::ParseErrorKind::*;
+        match self {
+            UnexpectedChar(c) => write!(f, "unexpected character `{}`", c),
+            UnterminatedString => write!(f, "unterminated string"),
+            InvalidEscape(c) => write!(f, "invalid escape `\\{}`", c),
+            Expected { expected, found } => write!(f, "expected {} but found {}", expected, found),
+            ChainedComparison => write!(f, "comparisons can not be chained without parentheses"),
+            InvalidAssignment => write!(f, "only a variable or it's elements can be assigned to"),
+            InvalidSymbol(err) => write!(f, "invalid symbol name: {}", err),
+            TooDeep => write!(f, "nested more than {} deep", MAX_DEPTH),
+        }
+    }
+}
+
+/// The result of parsing an expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Parsed {
+    /// The parsed expression, with every expression that could not be parsed replaced by `Nop`.
+    pub expr: Expression,
+    /// The text each node of `expr` was parsed from.
+    pub spans: BTreeMap<NodePath, Span>,
+    /// The errors found while parsing, in the order they appear in the text.
+    pub errors: Vec<ParseError>,
+}
+
+impl Parsed {
+    /// The parsed expression if there were no errors, or the errors otherwise.
+    pub fn into_result(self) -> Result<Expression, Vec<ParseError>> {
+        if self.errors.is_empty() {
+            Ok(self.expr)
+        } else {
+            Err(self.errors)
+        }
+    }
+}
+
+/// Parses an expression, recovering from any errors found.
+///
+/// # Example
+/// ```
+/// #[macro_use]
+/// extern crate dermis;
+/// use dermis::ast::expression::Expression;
+/// use dermis::ast::parse::{self, Span};
+/// use dermis::ast::path::{NodePath, Step};
+///
+/// # fn main() {
+/// let parsed = parse::parse("1 + limit");
+///
+/// assert_eq!(
+///     parsed.expr,
+///     Expression::Add(
+///         Box::new(Expression::Literal(1.0.into())),
+///         Box::new(Expression::Nop)
+///     )
+/// );
+/// assert_eq!(
+///     parsed.errors[0].to_string(),
+///     "expected an expression but found `limit` at 4..9"
+/// );
+///
+/// let mut rhs = NodePath::root();
+/// rhs.push(Step::Field("rhs"));
+/// assert_eq!(parsed.spans[&rhs], Span { start: 4, end: 9 });
+///
+/// let expr: Expression = "xs[0] ? 'yes : 'no".parse().unwrap();
+/// assert_eq!(expr.to_string(), "xs[0] ? 'yes : 'no");
+/// # }
+/// ```
+pub fn parse(src: &str) -> Parsed {
+    let mut errors = vec![];
+    let tokens = lex(src, &mut errors);
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        prev_end: 0,
+        depth: 0,
+        errors,
+    };
+
+    let (expr, node) = parser.expr();
+    if *parser.peek() != Token::Eof {
+        parser.expected("end of input");
+    }
+
+    let mut spans = BTreeMap::new();
+    collect_spans(&expr, node, &mut NodePath::root(), &mut spans);
+    let mut errors = parser.errors;
+    errors.sort_by_key(|e| e.span.start);
+    Parsed {
+        expr,
+        spans,
+        errors,
+    }
+}
+
+impl FromStr for Expression {
+    type Err = Vec<ParseError>;
+
+    fn from_str(s: &str) -> Result<Expression, Vec<ParseError>> {
+        parse(s).into_result()
+    }
+}
+
+/// Operators and punctuation, with the longer ones first so that they are matched before their
+/// prefixes.
+const PUNCTUATION: &[&str] = &[
+    "::", ":=", "=>", "->", "==", "!=", "<=", ">=", "&&", "||", "^^", "++", "**", "//", "(", ")",
+    "[", "]", "{", "}", ",", ";", ":", "=", "?", "@", "!", "<", ">", "+", "-", "*", "/",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    String(String),
+    Symbol(OwnedSymbol),
+    /// A variable name or keyword, as the segments of it's path.
+    Name(Vec<String>),
+    Punct(&'static str),
+    Eof,
+}
+
+/// Describes a token for an error message.
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Number(n) => n.to_string(),
+        Token::String(s) => string_literal(s),
+        Token::Symbol(s) => s.to_string(),
+        Token::Name(path) => format!("`{}`", path.join("::")),
+        Token::Punct(p) => format!("`{}`", p),
+        Token::Eof => "end of input".to_string(),
+    }
+}
+
+fn is_name_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// The length of the segments at the start of `s` that match `segment`, separated by `::`.
+fn path_len<F: Fn(&str) -> usize>(s: &str, segment: F) -> usize {
+    let mut len = segment(s);
+    if len == 0 {
+        return 0;
+    }
+    while s[len..].starts_with("::") && segment(&s[len + 2..]) != 0 {
+        len += 2 + segment(&s[len + 2..]);
+    }
+    len
+}
+
+fn name_len(s: &str) -> usize {
+    match s.chars().next() {
+        Some(c) if is_name_start(c) => s.find(|c| !is_name_char(c)).unwrap_or(s.len()),
+        _ => 0,
+    }
+}
+
+fn symbol_name_len(s: &str) -> usize {
+    s.find(|c| !is_symbol_char(c)).unwrap_or(s.len())
+}
+
+fn number_len(s: &str) -> usize {
+    let digits = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let mut len = digits(s);
+    if s[len..].starts_with('.') && digits(&s[len + 1..]) != 0 {
+        len += 1 + digits(&s[len + 1..]);
+    }
+    let rest = &s[len..];
+    if rest.starts_with('e') || rest.starts_with('E') {
+        let sign = if rest[1..].starts_with('-') || rest[1..].starts_with('+') {
+            1
+        } else {
+            0
+        };
+        let exponent = digits(&rest[1 + sign..]);
+        if exponent != 0 {
+            len += 1 + sign + exponent;
+        }
+    }
+    len
+}
+
+/// Reads a string at the start of `s`, returning it's contents and the length of it's text.
+fn string(s: &str, start: usize, errors: &mut Vec<ParseError>) -> (String, usize) {
+    let mut contents = String::new();
+    let mut chars = s.char_indices().skip(1);
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return (contents, i + 1),
+            '\\' => match chars.next() {
+                Some((_, '"')) => contents.push('"'),
+                Some((_, '\\')) => contents.push('\\'),
+                Some((_, 'n')) => contents.push('\n'),
+                Some((_, 't')) => contents.push('\t'),
+                Some((_, 'r')) => contents.push('\r'),
+                Some((j, c)) => {
+                    errors.push(ParseError {
+                        kind: ParseErrorKind::InvalidEscape(c),
+                        span: Span {
+                            start: start + i,
+                            end: start + j + c.len_utf8(),
+                        },
+                    });
+                    contents.push(c);
+                }
+                None => break,
+            },
+            c => contents.push(c),
+        }
+    }
+    errors.push(ParseError {
+        kind: ParseErrorKind::UnterminatedString,
+        span: Span {
+            start,
+            end: start + s.len(),
+        },
+    });
+    (contents, s.len())
+}
+
+/// Creates a symbol from the segments of it's path.
+fn symbol<'a, I: IntoIterator<Item = &'a str>>(path: I) -> Result<OwnedSymbol, SymbolError> {
+    let mut segments = path.into_iter();
+    let first = segments.next().expect("a path has at least one segment");
+    segments.try_fold(
+        OwnedSymbol::try_new_global(first.to_string())?,
+        |ns, name| OwnedSymbol::try_new_local(name.to_string(), ns),
+    )
+}
+
+/// Splits `src` into tokens, ending with `Token::Eof`.
+fn lex(src: &str, errors: &mut Vec<ParseError>) -> Vec<(Token, Span)> {
+    let mut tokens = vec![];
+    let mut pos = 0;
+    while let Some(c) = src[pos..].chars().next() {
+        let start = pos;
+        let rest = &src[pos..];
+        let token = if c.is_whitespace() {
+            pos += c.len_utf8();
+            continue;
+        } else if c.is_ascii_digit() {
+            let len = number_len(rest);
+            pos += len;
+            Token::Number(
+                rest[..len]
+                    .parse()
+                    .expect("number_len only accepts valid numbers"),
+            )
+        } else if c == '"' {
+            let (s, len) = string(rest, start, errors);
+            pos += len;
+            Token::String(s)
+        } else if c == '\'' {
+            let len = path_len(&rest[1..], symbol_name_len);
+            if len == 0 {
+                errors.push(ParseError {
+                    kind: ParseErrorKind::Expected {
+                        expected: "a symbol name".to_string(),
+                        found: match rest[1..].chars().next() {
+                            Some(c) => format!("`{}`", c),
+                            None => "end of input".to_string(),
+                        },
+                    },
+                    span: Span {
+                        start,
+                        end: start + 1,
+                    },
+                });
+                pos += 1;
+                continue;
+            }
+            pos += 1 + len;
+            match symbol(rest[1..=len].split("::")) {
+                Ok(sym) => Token::Symbol(sym),
+                Err(err) => {
+                    errors.push(ParseError {
+                        kind: ParseErrorKind::InvalidSymbol(err),
+                        span: Span { start, end: pos },
+                    });
+                    continue;
+                }
+            }
+        } else if is_name_start(c) {
+            let len = path_len(rest, name_len);
+            pos += len;
+            // A lone `_` is the wildcard pattern, but it can not be part of a path.
+            if rest[..len].contains("::") {
+                if let Err(err) = symbol(rest[..len].split("::")) {
+                    errors.push(ParseError {
+                        kind: ParseErrorKind::InvalidSymbol(err),
+                        span: Span { start, end: pos },
+                    });
+                    continue;
+                }
+            }
+            if &rest[..len] == "inf" {
+                Token::Number(f64::INFINITY)
+            } else {
+                Token::Name(rest[..len].split("::").map(String::from).collect())
+            }
+        } else if let Some(p) = PUNCTUATION.iter().find(|p| rest.starts_with(*p)) {
+            pos += p.len();
+            Token::Punct(p)
+        } else {
+            pos += c.len_utf8();
+            errors.push(ParseError {
+                kind: ParseErrorKind::UnexpectedChar(c),
+                span: Span { start, end: pos },
+            });
+            continue;
+        };
+        tokens.push((token, Span { start, end: pos }));
+    }
+    tokens.push((
+        Token::Eof,
+        Span {
+            start: pos,
+            end: pos,
+        },
+    ));
+    tokens
+}
+
+/// The spans of a parsed expression and it's children, in the order of
+/// [`Expression::children`](::ast::expression::Expression::children).
+#[derive(Debug)]
+struct Node {
+    span: Span,
+    children: Vec<Node>,
+}
+
+fn collect_spans(
+    expr: &Expression,
+    node: Node,
+    path: &mut NodePath,
+    spans: &mut BTreeMap<NodePath, Span>,
+) {
+    spans.insert(path.clone(), node.span);
+    for ((step, child), child_node) in expr.children().into_iter().zip(node.children) {
+        path.push(step);
+        collect_spans(child, child_node, path, spans);
+        path.pop();
+    }
+}
+
+type Spanned = (Expression, Node);
+
+/// Marks that parsing failed, after the error was recorded.
+#[derive(Debug)]
+struct Failed;
+
+struct Parser {
+    tokens: Vec<(Token, Span)>,
+    pos: usize,
+    /// The end of the last token consumed.
+    prev_end: usize,
+    /// How many calls to `nested` are being parsed.
+    depth: usize,
+    errors: Vec<ParseError>,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].0
+    }
+
+    fn peek_second(&self) -> &Token {
+        &self.tokens[(self.pos + 1).min(self.tokens.len() - 1)].0
+    }
+
+    fn span(&self) -> Span {
+        self.tokens[self.pos].1
+    }
+
+    fn bump(&mut self) -> Token {
+        let (token, span) = self.tokens[self.pos].clone();
+        if token != Token::Eof {
+            self.pos += 1;
+        }
+        self.prev_end = span.end;
+        token
+    }
+
+    fn is(&self, punct: &str) -> bool {
+        match self.peek() {
+            Token::Punct(p) => *p == punct,
+            _ => false,
+        }
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        let is = self.is(punct);
+        if is {
+            self.bump();
+        }
+        is
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        match self.peek() {
+            Token::Name(path) => path.len() == 1 && path[0] == keyword,
+            _ => false,
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let is = self.is_keyword(keyword);
+        if is {
+            self.bump();
+        }
+        is
+    }
+
+    /// Records that `expected` was expected instead of the next token.
+    fn expected(&mut self, expected: &str) -> Failed {
+        self.errors.push(ParseError {
+            kind: ParseErrorKind::Expected {
+                expected: expected.to_string(),
+                found: describe(self.peek()),
+            },
+            span: self.span(),
+        });
+        Failed
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<(), Failed> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            Err(self.expected(&format!("`{}`", punct)))
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), Failed> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            Err(self.expected(&format!("`{}`", keyword)))
+        }
+    }
+
+    /// Expects the bracket that closes a group, skipping anything before it after reporting an
+    /// error.
+    fn close(&mut self, punct: &str) -> Result<(), Failed> {
+        if self.eat(punct) {
+            return Ok(());
+        }
+        self.expected(&format!("`{}`", punct));
+        self.synchronize();
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            Err(Failed)
+        }
+    }
+
+    /// Skips tokens until the end of the list or statement that contains the current token.
+    fn synchronize(&mut self) {
+        let mut depth = 0usize;
+        loop {
+            match self.peek() {
+                Token::Eof => return,
+                Token::Punct(",") | Token::Punct(";") if depth == 0 => return,
+                Token::Punct(")") | Token::Punct("]") | Token::Punct("}") => {
+                    if depth == 0 {
+                        return;
+                    }
+                    depth -= 1;
+                }
+                Token::Punct("(") | Token::Punct("[") | Token::Punct("{") => depth += 1,
+                _ => (),
+            }
+            self.bump();
+        }
+    }
+
+    /// Parses with `parse` one level deeper, or reports an error if that is past
+    /// [`MAX_DEPTH`](MAX_DEPTH).
+    fn nested<T, F>(&mut self, parse: F) -> Result<T, Failed>
+    where
+        F: FnOnce(&mut Parser) -> Result<T, Failed>,
+    {
+        if self.depth >= MAX_DEPTH {
+            self.errors.push(ParseError {
+                kind: ParseErrorKind::TooDeep,
+                span: self.span(),
+            });
+            return Err(Failed);
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// Finishes a node that started at `start` and ends at the last token consumed.
+    fn finish(&self, start: usize, expr: Expression, children: Vec<Node>) -> Spanned {
+        let span = Span {
+            start,
+            end: self.prev_end.max(start),
+        };
+        (expr, Node { span, children })
+    }
+
+    /// Parses the items of a list up to and including `close`, after it's opening bracket.
+    fn list<T, F>(&mut self, sep: &str, close: &str, mut item: F) -> Result<Vec<T>, Failed>
+    where
+        F: FnMut(&mut Parser) -> Result<T, Failed>,
+    {
+        let mut items = vec![];
+        loop {
+            if self.eat(close) {
+                return Ok(items);
+            }
+            match item(self) {
+                Ok(i) => items.push(i),
+                Err(Failed) => self.synchronize(),
+            }
+            if self.eat(close) {
+                return Ok(items);
+            }
+            if self.eat(sep) {
+                continue;
+            }
+            self.expected(&format!("`{}` or `{}`", sep, close));
+            self.synchronize();
+            if self.eat(close) {
+                return Ok(items);
+            }
+            if !self.eat(sep) {
+                return Err(Failed);
+            }
+        }
+    }
+
+    /// Parses an expression, replacing it with `Nop` if it can not be parsed.
+    fn expr(&mut self) -> Spanned {
+        let start = self.span().start;
+        match self.nested(Parser::lowest) {
+            Ok(expr) => expr,
+            Err(Failed) => self.recover(start),
+        }
+    }
+
+    /// Replaces an expression that could not be parsed with `Nop`, after skipping the rest of it.
+    fn recover(&mut self, start: usize) -> Spanned {
+        self.synchronize();
+        self.finish(start, Expression::Nop, vec![])
+    }
+
+    /// Parses an expression that extends as far right as possible, such as `let`.
+    fn lowest(&mut self) -> Result<Spanned, Failed> {
+        let start = self.span().start;
+        if self.eat_keyword("let") {
+            let name = self.variable_name()?;
+            self.expect("=")?;
+            let value = self.expr();
+            self.expect_keyword("in")?;
+            let body = self.expr();
+            let expr = Expression::Let {
+                name,
+                value: Box::new(value.0),
+                body: Box::new(body.0),
+            };
+            return Ok(self.finish(start, expr, vec![value.1, body.1]));
+        }
+        if self.eat_keyword("fn") {
+            self.expect("(")?;
+            let params = self.list(",", ")", Parser::variable_name)?;
+            self.expect("=>")?;
+            let body = self.expr();
+            let expr = Expression::Lambda {
+                params,
+                body: Box::new(body.0),
+            };
+            return Ok(self.finish(start, expr, vec![body.1]));
+        }
+
+        let target = self.ternary()?;
+        if !self.is(":=") {
+            return Ok(target);
+        }
+        let target_span = self.span();
+        self.bump();
+        let value = self.expr();
+        let (expr, mut node) = target;
+        match expr {
+            Expression::Var(name) => Ok(self.finish(
+                start,
+                Expression::Assign {
+                    name,
+                    value: Box::new(value.0),
+                },
+                vec![value.1],
+            )),
+            Expression::Get { target, key } => match *target {
+                Expression::Var(name) => {
+                    let key_node = node.children.pop().expect("a get has a key");
+                    Ok(self.finish(
+                        start,
+                        Expression::AssignAt {
+                            name,
+                            key,
+                            value: Box::new(value.0),
+                        },
+                        vec![key_node, value.1],
+                    ))
+                }
+                _ => Err(self.invalid_assignment(target_span)),
+            },
+            _ => Err(self.invalid_assignment(target_span)),
+        }
+    }
+
+    fn invalid_assignment(&mut self, span: Span) -> Failed {
+        self.errors.push(ParseError {
+            kind: ParseErrorKind::InvalidAssignment,
+            span,
+        });
+        Failed
+    }
+
+    fn ternary(&mut self) -> Result<Spanned, Failed> {
+        let start = self.span().start;
+        let cond = self.binary(Prec::Or)?;
+        if !self.eat("?") {
+            return Ok(cond);
+        }
+        let if_true = self.expr();
+        self.expect(":")?;
+        let if_false = self.nested(Parser::ternary)?;
+        let expr = Expression::Cond {
+            cond: Box::new(cond.0),
+            if_true: Box::new(if_true.0),
+            if_false: Box::new(if_false.0),
+            display: CondDisplay::Ternary,
+        };
+        Ok(self.finish(start, expr, vec![cond.1, if_true.1, if_false.1]))
+    }
+
+    /// The binary operator at the next token, if it has the precedence `prec`.
+    fn operator(&self, prec: Prec) -> Option<fn(E, E) -> Expression> {
+        use ast::expression::Expression::*;
+        let op = match self.peek() {
+            Token::Punct(p) => *p,
+            _ => return None,
+        };
+        let make: fn(E, E) -> Expression = match (prec, op) {
+            (Prec::Or, "||") => LOr,
+            (Prec::Or, "^^") => LXor,
+            (Prec::And, "&&") => LAnd,
+            (Prec::Compare, "==") => Equal,
+            (Prec::Compare, "!=") => NotEqual,
+            (Prec::Compare, "<") => LessThan,
+            (Prec::Compare, ">") => GreaterThan,
+            (Prec::Compare, "<=") => LesserOrEqual,
+            (Prec::Compare, ">=") => GreaterOrEqual,
+            (Prec::Add, "++") => StrConcat,
+            (Prec::Add, "+") => Add,
+            (Prec::Add, "-") => Subtract,
+            (Prec::Multiply, "*") => Multiply,
+            (Prec::Multiply, "/") => Divide,
+            (Prec::Multiply, "//") => IntDivide,
+            (Prec::Exponent, "**") => Exponent,
+            _ => return None,
+        };
+        Some(make)
+    }
+
+    /// Parses the binary operators that bind at least as tightly as `prec`.
+    fn binary(&mut self, prec: Prec) -> Result<Spanned, Failed> {
+        if prec >= Prec::Prefix {
+            return self.prefix();
+        }
+        let start = self.span().start;
+        let mut lhs = self.binary(prec.next())?;
+        while let Some(make) = self.operator(prec) {
+            self.bump();
+            // Exponents group to the right.
+            let rhs_start = self.span().start;
+            let rhs = if prec == Prec::Exponent {
+                self.nested(|p| p.binary(prec))
+            } else {
+                self.binary(prec.next())
+            };
+            let rhs = match rhs {
+                Ok(rhs) => rhs,
+                Err(Failed) => self.recover(rhs_start),
+            };
+            lhs = self.finish(
+                start,
+                make(Box::new(lhs.0), Box::new(rhs.0)),
+                vec![lhs.1, rhs.1],
+            );
+            if prec == Prec::Compare && self.operator(prec).is_some() {
+                self.errors.push(ParseError {
+                    kind: ParseErrorKind::ChainedComparison,
+                    span: self.span(),
+                });
+                return Err(Failed);
+            }
+        }
+        Ok(lhs)
+    }
+
+    fn prefix(&mut self) -> Result<Spanned, Failed> {
+        let start = self.span().start;
+        if self.eat("!") {
+            let operand = self.nested(|p| p.binary(Prec::Prefix))?;
+            return Ok(self.finish(
+                start,
+                Expression::LNot(Box::new(operand.0)),
+                vec![operand.1],
+            ));
+        }
+        if self.eat("@") {
+            let options = match self.value()? {
+                OwnedValue::Object(o) => o,
+                _ => return Err(self.invalid_options(start)),
+            };
+            let id = self.nested(|p| p.binary(Prec::Prefix))?;
+            let expr = Expression::IdeOption {
+                id: Box::new(id.0),
+                options,
+            };
+            return Ok(self.finish(start, expr, vec![id.1]));
+        }
+        self.postfix()
+    }
+
+    fn invalid_options(&mut self, start: usize) -> Failed {
+        self.errors.push(ParseError {
+            kind: ParseErrorKind::Expected {
+                expected: "an object of options".to_string(),
+                found: "a value".to_string(),
+            },
+            span: Span {
+                start,
+                end: self.prev_end,
+            },
+        });
+        Failed
+    }
+
+    fn postfix(&mut self) -> Result<Spanned, Failed> {
+        let start = self.span().start;
+        let mut expr = self.atom()?;
+        loop {
+            if self.eat("(") {
+                let args = self.list(",", ")", |p| Ok(p.expr()))?;
+                let (args, arg_nodes): (Vec<_>, Vec<_>) = args.into_iter().unzip();
+                let mut children = vec![expr.1];
+                children.extend(arg_nodes);
+                let call = Expression::Call {
+                    function: Box::new(expr.0),
+                    args,
+                };
+                expr = self.finish(start, call, children);
+            } else if self.eat("[") {
+                let key = self.expr();
+                self.close("]")?;
+                let get = Expression::Get {
+                    target: Box::new(expr.0),
+                    key: Box::new(key.0),
+                };
+                expr = self.finish(start, get, vec![expr.1, key.1]);
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn atom(&mut self) -> Result<Spanned, Failed> {
+        let start = self.span().start;
+        let expr = match self.peek().clone() {
+            Token::Number(_) | Token::String(_) | Token::Symbol(_) => {
+                Expression::Literal(self.value()?)
+            }
+            Token::Punct("-") | Token::Punct("[") | Token::Punct("{") => {
+                Expression::Literal(self.value()?)
+            }
+            Token::Punct("(") => {
+                self.bump();
+                let inner = self.expr();
+                if self.eat(":") {
+                    let ty = self.ty()?;
+                    self.close(")")?;
+                    let expr = Expression::Typed {
+                        expr: Box::new(inner.0),
+                        ty,
+                    };
+                    return Ok(self.finish(start, expr, vec![inner.1]));
+                }
+                self.close(")")?;
+                return Ok(inner);
+            }
+            Token::Name(ref path) if path.len() == 1 && is_reserved(&path[0]) => {
+                return self.keyword(start, &path[0]);
+            }
+            Token::Name(path) => {
+                self.bump();
+                Expression::Var(
+                    symbol(path.iter().map(String::as_str)).expect("paths are checked when lexed"),
+                )
+            }
+            _ => return Err(self.expected("an expression")),
+        };
+        Ok(self.finish(start, expr, vec![]))
+    }
+
+    /// Parses an expression that starts with a keyword.
+    fn keyword(&mut self, start: usize, keyword: &str) -> Result<Spanned, Failed> {
+        let unary: fn(E) -> Expression = match keyword {
+            "nop" => {
+                self.bump();
+                return Ok(self.finish(start, Expression::Nop, vec![]));
+            }
+            "var" => {
+                self.bump();
+                let name = self.quoted_variable()?;
+                return Ok(self.finish(start, Expression::Var(name), vec![]));
+            }
+            "if" => {
+                self.bump();
+                return self.if_else(start);
+            }
+            "do" => {
+                self.bump();
+                self.expect("{")?;
+                let exprs = self.list(";", "}", |p| Ok(p.expr()))?;
+                let (exprs, nodes) = exprs.into_iter().unzip();
+                return Ok(self.finish(start, Expression::Block(exprs), nodes));
+            }
+            "while" => return self.while_loop(start),
+            "for" => return self.for_each(start),
+            "match" => return self.match_expr(start),
+            "approx_eq" | "approx_ne" => return self.floating(start, keyword == "approx_eq"),
+            "sqrt" => Expression::Sqrt,
+            "log" => Expression::Log,
+            "is_null" => Expression::IsNull,
+            "not_null" => Expression::NotNull,
+            "break" => Expression::Break,
+            "continue" => Expression::Continue,
+            _ => return Err(self.expected("an expression")),
+        };
+        self.bump();
+        self.expect("(")?;
+        let operand = self.expr();
+        self.close(")")?;
+        Ok(self.finish(start, unary(Box::new(operand.0)), vec![operand.1]))
+    }
+
+    /// Parses the rest of an `if` after the keyword, including any `else if`.
+    fn if_else(&mut self, start: usize) -> Result<Spanned, Failed> {
+        let cond = self.expr();
+        let if_true = self.braced()?;
+        self.expect_keyword("else")?;
+        let if_false = if self.is_keyword("if") {
+            let start = self.span().start;
+            self.bump();
+            self.nested(|p| p.if_else(start))?
+        } else {
+            self.braced()?
+        };
+        let expr = Expression::Cond {
+            cond: Box::new(cond.0),
+            if_true: Box::new(if_true.0),
+            if_false: Box::new(if_false.0),
+            display: CondDisplay::If,
+        };
+        Ok(self.finish(start, expr, vec![cond.1, if_true.1, if_false.1]))
+    }
+
+    /// Parses an expression in braces.
+    fn braced(&mut self) -> Result<Spanned, Failed> {
+        self.expect("{")?;
+        let expr = self.expr();
+        self.close("}")?;
+        Ok(expr)
+    }
+
+    fn while_loop(&mut self, start: usize) -> Result<Spanned, Failed> {
+        self.bump();
+        let acc = self.variable_name()?;
+        self.expect("=")?;
+        let init = self.expr();
+        self.expect(";")?;
+        let cond = self.expr();
+        self.expect(";")?;
+        self.expect_keyword("limit")?;
+        let limit = self.number()?;
+        let body = self.braced()?;
+        let expr = Expression::While {
+            acc,
+            init: Box::new(init.0),
+            cond: Box::new(cond.0),
+            body: Box::new(body.0),
+            limit,
+        };
+        Ok(self.finish(start, expr, vec![init.1, cond.1, body.1]))
+    }
+
+    fn for_each(&mut self, start: usize) -> Result<Spanned, Failed> {
+        self.bump();
+        let key = self.variable_name()?;
+        self.expect(",")?;
+        let item = self.variable_name()?;
+        self.expect_keyword("in")?;
+        let over = self.expr();
+        self.expect(";")?;
+        let acc = self.variable_name()?;
+        self.expect("=")?;
+        let init = self.expr();
+        let body = self.braced()?;
+        let expr = Expression::ForEach {
+            item,
+            key,
+            over: Box::new(over.0),
+            acc,
+            init: Box::new(init.0),
+            body: Box::new(body.0),
+        };
+        Ok(self.finish(start, expr, vec![over.1, init.1, body.1]))
+    }
+
+    fn match_expr(&mut self, start: usize) -> Result<Spanned, Failed> {
+        self.bump();
+        let scrutinee = self.expr();
+        self.expect("{")?;
+        let arms = self.list(",", "}", |p| {
+            let pattern = p.pattern()?;
+            p.expect("=>")?;
+            Ok((pattern, p.expr()))
+        })?;
+        let mut children = vec![scrutinee.1];
+        let arms = arms
+            .into_iter()
+            .map(|(pattern, (body, node))| {
+                children.push(node);
+                (pattern, body)
+            })
+            .collect();
+        let expr = Expression::Match {
+            scrutinee: Box::new(scrutinee.0),
+            arms,
+        };
+        Ok(self.finish(start, expr, children))
+    }
+
+    fn floating(&mut self, start: usize, equal: bool) -> Result<Spanned, Failed> {
+        self.bump();
+        self.expect("(")?;
+        let lhs = self.expr();
+        self.expect(",")?;
+        let rhs = self.expr();
+        self.expect(",")?;
+        let rounding_factor = self.number()?;
+        self.close(")")?;
+        let (lhs, lhs_node) = (Box::new(lhs.0), lhs.1);
+        let (rhs, rhs_node) = (Box::new(rhs.0), rhs.1);
+        let expr = if equal {
+            Expression::FloatingEqual {
+                lhs,
+                rhs,
+                rounding_factor,
+            }
+        } else {
+            Expression::FloatingNE {
+                lhs,
+                rhs,
+                rounding_factor,
+            }
+        };
+        Ok(self.finish(start, expr, vec![lhs_node, rhs_node]))
+    }
+
+    /// Parses the name of a variable being declared or assigned to.
+    fn variable_name(&mut self) -> Result<OwnedSymbol, Failed> {
+        if self.eat_keyword("var") {
+            return self.quoted_variable();
+        }
+        let name = match self.peek() {
+            Token::Name(path) if !(path.len() == 1 && is_reserved(&path[0])) => {
+                symbol(path.iter().map(String::as_str)).expect("paths are checked when lexed")
+            }
+            _ => return Err(self.expected("a variable name")),
+        };
+        self.bump();
+        Ok(name)
+    }
+
+    /// Parses `('name)` after the keyword `var`.
+    fn quoted_variable(&mut self) -> Result<OwnedSymbol, Failed> {
+        self.expect("(")?;
+        let name = match self.peek().clone() {
+            Token::Symbol(name) => name,
+            _ => return Err(self.expected("a symbol")),
+        };
+        self.bump();
+        self.close(")")?;
+        Ok(name)
+    }
+
+    fn number(&mut self) -> Result<Number, Failed> {
+        let negative = self.is("-");
+        let n = match (negative, self.peek(), self.peek_second()) {
+            (false, Token::Number(n), _) | (true, _, Token::Number(n)) => *n,
+            _ => return Err(self.expected("a number")),
+        };
+        if negative {
+            self.bump();
+        }
+        self.bump();
+        Ok(if negative { -n } else { n }.into())
+    }
+
+    /// Parses a value, as it is displayed.
+    fn value(&mut self) -> Result<OwnedValue, Failed> {
+        self.nested(Parser::value_inner)
+    }
+
+    fn value_inner(&mut self) -> Result<OwnedValue, Failed> {
+        match self.peek().clone() {
+            Token::Number(_) | Token::Punct("-") => Ok(OwnedValue::Number(self.number()?)),
+            Token::String(s) => {
+                self.bump();
+                Ok(s.into())
+            }
+            Token::Symbol(s) => {
+                self.bump();
+                Ok(s.into())
+            }
+            Token::Punct("[") => {
+                self.bump();
+                let elems = self.list(",", "]", Parser::value)?;
+                Ok(OwnedArray::from(elems).into())
+            }
+            Token::Punct("{") => {
+                self.bump();
+                let entries = self.list(",", "}", |p| {
+                    let key = p.value()?;
+                    p.expect(":")?;
+                    Ok((key, p.value()?))
+                })?;
+                let mut obj = OwnedObject::empty();
+                for (k, v) in entries {
+                    obj.set_mut(k, v);
+                }
+                Ok(obj.into())
+            }
+            _ => Err(self.expected("a value")),
+        }
+    }
+
+    fn ty(&mut self) -> Result<Type, Failed> {
+        self.nested(Parser::ty_inner)
+    }
+
+    fn ty_inner(&mut self) -> Result<Type, Failed> {
+        if self.eat("[") {
+            let elem = self.ty()?;
+            self.close("]")?;
+            return Ok(Type::Array(Box::new(elem)));
+        }
+        if self.eat("{") {
+            let fields = self.list(",", "}", |p| {
+                let key = p.value()?;
+                p.expect(":")?;
+                Ok((key, p.ty()?))
+            })?;
+            return Ok(Type::Object(fields.into_iter().collect()));
+        }
+        if self.eat_keyword("fn") {
+            self.expect("(")?;
+            let params = self.list(",", ")", Parser::ty)?;
+            self.expect("->")?;
+            let ret = self.ty()?;
+            return Ok(Type::Function {
+                params,
+                ret: Box::new(ret),
+            });
+        }
+        let ty = match self.peek() {
+            Token::Name(path) if path.len() == 1 => match path[0].as_str() {
+                "any" => Type::Any,
+                "number" => Type::Number,
+                "string" => Type::String,
+                "symbol" => Type::Symbol,
+                "bool" => Type::Bool,
+                "object" => Type::object(),
+                _ => return Err(self.expected("a type")),
+            },
+            _ => return Err(self.expected("a type")),
+        };
+        self.bump();
+        Ok(ty)
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, Failed> {
+        self.nested(Parser::pattern_inner)
+    }
+
+    fn pattern_inner(&mut self) -> Result<Pattern, Failed> {
+        let head = self.pattern_head()?;
+        if self.eat("::") {
+            let tail = self.pattern()?;
+            return Ok(Pattern::Cons {
+                head: Box::new(head),
+                tail: Box::new(tail),
+            });
+        }
+        Ok(head)
+    }
+
+    fn pattern_head(&mut self) -> Result<Pattern, Failed> {
+        match self.peek().clone() {
+            Token::Number(_) | Token::String(_) | Token::Symbol(_) | Token::Punct("-") => {
+                Ok(Pattern::Literal(self.value()?))
+            }
+            Token::Punct("[") if *self.peek_second() == Token::Punct("]") => {
+                self.bump();
+                self.bump();
+                Ok(Pattern::EmptyArray)
+            }
+            Token::Punct("{") => {
+                self.bump();
+                let fields = self.list(",", "}", |p| {
+                    let key = p.value()?;
+                    p.expect(":")?;
+                    Ok((key, p.pattern()?))
+                })?;
+                Ok(Pattern::Object(fields.into_iter().collect()))
+            }
+            Token::Punct("(") => {
+                self.bump();
+                let pattern = self.pattern()?;
+                self.close(")")?;
+                Ok(pattern)
+            }
+            Token::Name(ref path) if path.len() == 1 && path[0] == "_" => {
+                self.bump();
+                Ok(Pattern::Wildcard)
+            }
+            Token::Name(ref path) if path.len() == 1 && path[0] == "lit" => {
+                self.bump();
+                Ok(Pattern::Literal(self.value()?))
+            }
+            Token::Name(_) => Ok(Pattern::Bind(self.variable_name()?)),
+            _ => Err(self.expected("a pattern")),
+        }
+    }
+}
+
+/// Whether `name` can not be used as the name of a variable without `var('name)`.
+fn is_reserved(name: &str) -> bool {
+    name == "_" || KEYWORDS.contains(&name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ast::path::Step;
+    use ast::pretty;
+
+    fn lit(val: f64) -> E {
+        Box::new(Expression::Literal(val.into()))
+    }
+
+    fn var(name: OwnedSymbol) -> E {
+        Box::new(Expression::Var(name))
+    }
+
+    /// Parses `src` and prints it again, which gives back the same text if it was canonical.
+    fn reprint(src: &str) -> String {
+        match src.parse::<Expression>() {
+            Ok(expr) => expr.to_string(),
+            Err(errors) => panic!("failed to parse {:?}: {:?}", src, errors),
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let sources = [
+            "nop",
+            "{\"k\": \"say \\\"hi\\\"\\n\", 'a::b: [1.5, -2]}",
+            "(1 + 2) * 3 ** 2 ** x",
+            "1 - (2 - 3)",
+            "!(a < b) && c || d ^^ e",
+            "(a ? 1 : 2) + 3",
+            "a ? b : c ? d : e",
+            "if a { 1 } else if b { 2 } else { 3 }",
+            "let xs = [] in do { xs[0] := 1; y := xs[0] }",
+            "fn(xs) => for i, x in xs; sum = 0 { sum + x }",
+            "while i = 0; i < 10; limit 100 { i + 1 }",
+            "match x { [] => 0, (h :: t) :: _ => h, {'k: _} => 1, lit [1] => 2, \"s\" => 3 }",
+            "(approx_eq(sqrt(2), 1.5, -1) : bool)",
+            "(f : fn([number], {'k: string}) -> object)",
+            "@{\"collapsed\": 'true} nop",
+            "var('app::if) := std::math::pi",
+            "f(a)(b)[c] ++ break(is_null(log(-2.5)))",
+            "inf",
+            "-inf",
+            "[inf, -inf] ++ var('inf)",
+        ];
+        for src in sources.iter() {
+            assert_eq!(reprint(src), *src);
+        }
+
+        assert_eq!(
+            "-inf".parse::<Expression>(),
+            Ok(Expression::Literal(f64::NEG_INFINITY.into()))
+        );
+    }
+
+    #[test]
+    fn round_trip_wrapped() {
+        let src = "fn(xs) => for i, x in xs; sum = 0 { if x > 0 { sum + x } else { sum } }";
+        let expr: Expression = src.parse().unwrap();
+        let wrapped = pretty::render(&expr, 20);
+
+        assert!(wrapped.lines().count() > 1);
+        assert_eq!(wrapped.parse::<Expression>().unwrap(), expr);
+    }
+
+    #[test]
+    fn structure() {
+        assert_eq!(
+            "2 ** 3 ** x".parse::<Expression>().unwrap(),
+            Expression::Exponent(
+                lit(2.0),
+                Box::new(Expression::Exponent(lit(3.0), var(symbol_o!(x))))
+            )
+        );
+        assert_eq!(
+            "a ? b : c".parse::<Expression>().unwrap(),
+            Expression::Cond {
+                cond: var(symbol_o!(a)),
+                if_true: var(symbol_o!(b)),
+                if_false: var(symbol_o!(c)),
+                display: CondDisplay::Ternary,
+            }
+        );
+        assert_eq!(
+            "'ns::name".parse::<Expression>().unwrap(),
+            Expression::Literal(symbol_o!(ns;name).into())
+        );
+    }
+
+    #[test]
+    fn spans() {
+        let parsed = parse("f(1, x + 2)");
+        let path = |steps: &[Step]| {
+            let mut path = NodePath::root();
+            for step in steps {
+                path.push(*step);
+            }
+            path
+        };
+
+        assert!(parsed.errors.is_empty());
+        assert_eq!(parsed.spans.len(), 6);
+        assert_eq!(parsed.spans[&path(&[])], Span { start: 0, end: 11 });
+        assert_eq!(
+            parsed.spans[&path(&[Step::Index("args", 1)])],
+            Span { start: 5, end: 10 }
+        );
+        assert_eq!(
+            parsed.spans[&path(&[Step::Index("args", 1), Step::Field("rhs")])],
+            Span { start: 9, end: 10 }
+        );
+    }
+
+    #[test]
+    fn recovery() {
+        let parsed = parse("f(1 +, 2, (3 4))");
+
+        assert_eq!(
+            parsed.expr,
+            Expression::Call {
+                function: var(symbol_o!(f)),
+                args: vec![
+                    Expression::Add(lit(1.0), Box::new(Expression::Nop)),
+                    *lit(2.0),
+                    *lit(3.0),
+                ],
+            }
+        );
+        assert_eq!(
+            parsed
+                .errors
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "expected an expression but found `,` at 5..6",
+                "expected `)` but found 4 at 13..14",
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_errors() {
+        let parsed = parse("\"a\\qb # c");
+
+        assert_eq!(parsed.expr, Expression::Literal("aqb # c".into()));
+        assert_eq!(
+            parsed.errors.iter().map(|e| &e.kind).collect::<Vec<_>>(),
+            vec![
+                &ParseErrorKind::UnterminatedString,
+                &ParseErrorKind::InvalidEscape('q'),
+            ]
+        );
+
+        let parsed = parse("1 # 2");
+        assert_eq!(parsed.errors[0].kind, ParseErrorKind::UnexpectedChar('#'));
+
+        for src in &["'_", "'a::_", "a::_ + 1"] {
+            let parsed = parse(src);
+            assert_eq!(
+                parsed.errors[0],
+                ParseError {
+                    kind: ParseErrorKind::InvalidSymbol(SymbolError::Reserved),
+                    span: Span {
+                        start: 0,
+                        end: src.find(' ').unwrap_or(src.len()),
+                    },
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_syntax() {
+        let kinds = |src: &str| {
+            parse(src)
+                .errors
+                .into_iter()
+                .map(|e| e.kind)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(kinds("a < b < c"), vec![ParseErrorKind::ChainedComparison]);
+        assert_eq!(kinds("1 := 2"), vec![ParseErrorKind::InvalidAssignment]);
+        assert_eq!(
+            kinds(""),
+            vec![ParseErrorKind::Expected {
+                expected: "an expression".to_string(),
+                found: "end of input".to_string(),
+            }]
+        );
+        assert_eq!(
+            kinds("if x { 1 }"),
+            vec![ParseErrorKind::Expected {
+                expected: "`else`".to_string(),
+                found: "end of input".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn too_deep() {
+        let parens = |depth| format!("{}a{}", "(".repeat(depth), ")".repeat(depth));
+
+        assert_eq!(parse(&parens(MAX_DEPTH - 1)).errors, vec![]);
+        assert_eq!(
+            parse(&parens(MAX_DEPTH)).errors,
+            vec![ParseError {
+                kind: ParseErrorKind::TooDeep,
+                span: Span {
+                    start: MAX_DEPTH,
+                    end: MAX_DEPTH + 1,
+                },
+            }]
+        );
+
+        let n = 10_000;
+        for src in &[
+            parens(n),
+            format!("{}1", "!".repeat(n)),
+            format!("{}[]{}", "[".repeat(n), "]".repeat(n)),
+            format!("{}1", "2 ** ".repeat(n)),
+            format!("{}1", "a ? 1 : ".repeat(n)),
+            format!("(1 : {}number{})", "[".repeat(n), "]".repeat(n)),
+            format!("match 1 {{ {}_{} => 1 }}", "(".repeat(n), ")".repeat(n)),
+        ] {
+            let errors = parse(src).errors;
+            assert!(
+                errors.iter().any(|e| e.kind == ParseErrorKind::TooDeep),
+                "{:?}",
+                errors
+            );
+        }
+    }
+}
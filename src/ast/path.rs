@@ -0,0 +1,106 @@
+/*
+ * Dermis is an interpreter for a pure, statically typed, imperitive language designed to be edited with a custom IDE.
+ * Copyright (C) 2018 Amelorate
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Paths that locate a node within an AST, used to point diagnostics at the node they are about.
+
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use ast::expression::Expression;
+
+/// One step from a node to one of it's children.
+#[derive(Eq, PartialEq, Hash, Ord, PartialOrd, Debug, Clone, Copy)]
+pub enum Step {
+    /// The child stored in a field of the node, such as the `if_true` of a `Cond`.
+    Field(&'static str),
+    /// The child at an index of a list in the node, such as the arguments of a `Call`.
+    Index(&'static str, usize),
+}
+
+impl Display for Step {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Step::Field(name) => write!(f, "{}", name),
+            Step::Index(name, i) => write!(f, "{}[{}]", name, i),
+        }
+    }
+}
+
+/// The location of a node, as the steps taken to reach it from the root of the AST.
+///
+/// # Example
+/// ```
+/// use dermis::ast::expression::Expression;
+/// use dermis::ast::path::{NodePath, Step};
+///
+/// let expr = Expression::Add(
+///     Box::new(Expression::Literal(1.0.into())),
+///     Box::new(Expression::Sqrt(Box::new(Expression::Literal(4.0.into())))),
+/// );
+///
+/// let mut path = NodePath::root();
+/// path.push(Step::Field("rhs"));
+/// path.push(Step::Field("operand"));
+///
+/// assert_eq!(path.to_string(), "root.rhs.operand");
+/// assert_eq!(path.resolve(&expr), Some(&Expression::Literal(4.0.into())));
+/// ```
+#[derive(Eq, PartialEq, Hash, Ord, PartialOrd, Debug, Clone, Default)]
+pub struct NodePath(Vec<Step>);
+
+impl NodePath {
+    /// The path to the root node.
+    pub fn root() -> NodePath {
+        NodePath::default()
+    }
+
+    pub fn push(&mut self, step: Step) {
+        self.0.push(step)
+    }
+
+    pub fn pop(&mut self) -> Option<Step> {
+        self.0.pop()
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.0
+    }
+
+    /// Finds the node this path points to in `root`, if it exists.
+    pub fn resolve<'a>(&self, root: &'a Expression) -> Option<&'a Expression> {
+        let mut node = root;
+        for step in &self.0 {
+            node = node
+                .children()
+                .into_iter()
+                .find(|(s, _)| s == step)
+                .map(|(_, child)| child)?;
+        }
+        Some(node)
+    }
+}
+
+impl Display for NodePath {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "root")?;
+        for step in &self.0 {
+            write!(f, ".{}", step)?;
+        }
+        Ok(())
+    }
+}
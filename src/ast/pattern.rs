@@ -0,0 +1,164 @@
+/*
+ * Dermis is an interpreter for a pure, statically typed, imperitive language designed to be edited with a custom IDE.
+ * Copyright (C) 2018 Amelorate
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Patterns, which destructure values in a [`Match`](::ast::expression::Expression::Match).
+//!
+//! A pattern is encoded as a value in the same way as the rest of the AST, with it's keys and
+//! symbols in the namespace `'Pattern`, for example `{'Pattern::Bind: 'x}`.
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use ast::decode::{self, DecodeError, Fields};
+use value::{OwnedObject, OwnedSymbol, OwnedValue};
+
+#[derive(Eq, PartialEq, Hash, Ord, PartialOrd, Debug, Clone, Serialize, Deserialize)]
+pub enum Pattern {
+    /// Matches any value.
+    Wildcard,
+    /// Matches any value, and binds it to a variable.
+    Bind(OwnedSymbol),
+    /// Matches a value equal to a constant, such as a number, string or symbol.
+    Literal(OwnedValue),
+    /// Matches an array without any elements.
+    EmptyArray,
+    /// Matches an array with at least one element, where `head` matches the first element and
+    /// `tail` matches an array of the rest of the elements.
+    Cons {
+        head: Box<Pattern>,
+        tail: Box<Pattern>,
+    },
+    /// Matches an object that has each of the keys, where the value of each key matches it's
+    /// pattern.
+    ///
+    /// Other keys of the object are ignored, so an empty object pattern matches any object.
+    Object(#[serde(with = "::ast::types::field_pairs")] BTreeMap<OwnedValue, Pattern>),
+}
+
+impl From<Pattern> for OwnedValue {
+    fn from(pattern: Pattern) -> OwnedValue {
+        use self::Pattern::*;
+        match pattern {
+            Wildcard => symbol_o!(Pattern;Wildcard).into(),
+            EmptyArray => symbol_o!(Pattern;EmptyArray).into(),
+            Bind(name) => {
+                OwnedObject::singleton(symbol_o!(Pattern;Bind).into(), name.into()).into()
+            }
+            Literal(val) => OwnedObject::singleton(symbol_o!(Pattern;Literal).into(), val).into(),
+            Cons { head, tail } => {
+                let mut o = OwnedObject::singleton(symbol_o!(Pattern;Cons).into(), (*head).into());
+                o.set_mut(symbol_o!(Pattern;Cons;Tail).into(), (*tail).into());
+                o.into()
+            }
+            Object(fields) => {
+                let mut o = OwnedObject::empty();
+                for (k, p) in fields {
+                    o.set_mut(k, p.into());
+                }
+                OwnedObject::singleton(symbol_o!(Pattern;Object).into(), o.into()).into()
+            }
+        }
+    }
+}
+
+/// Decodes a pattern from the encoding produced by `From<Pattern> for OwnedValue`.
+impl TryFrom<OwnedValue> for Pattern {
+    type Error = DecodeError;
+
+    fn try_from(val: OwnedValue) -> Result<Pattern, DecodeError> {
+        let mut f = match val {
+            OwnedValue::Symbol(sym) => {
+                return if sym == symbol_o!(Pattern;Wildcard) {
+                    Ok(Pattern::Wildcard)
+                } else if sym == symbol_o!(Pattern;EmptyArray) {
+                    Ok(Pattern::EmptyArray)
+                } else {
+                    Err(DecodeError::UnexpectedSymbol(sym))
+                }
+            }
+            OwnedValue::Object(o) => Fields::with_root(o, symbol_o!(Pattern))?,
+            found => {
+                return Err(DecodeError::UnexpectedValue {
+                    expected: "a pattern",
+                    found,
+                })
+            }
+        };
+
+        let tag = f.tag().get_name().clone();
+        let pattern = match &tag[..] {
+            "Bind" => Pattern::Bind(decode::symbol(f.take()?)?),
+            "Literal" => Pattern::Literal(f.take()?),
+            "Cons" => Pattern::Cons {
+                head: Box::new(Pattern::try_from(f.take()?)?),
+                tail: Box::new(Pattern::try_from(f.take_field("Tail")?)?),
+            },
+            "Object" => Pattern::Object(
+                decode::object(f.take()?)?
+                    .iter()
+                    .map(|(k, p)| Ok(((*k).clone(), Pattern::try_from((*p).clone())?)))
+                    .collect::<Result<_, DecodeError>>()?,
+            ),
+            _ => return Err(DecodeError::UnexpectedSymbol(f.tag().clone())),
+        };
+        f.finish()?;
+        Ok(pattern)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn round_trip(pattern: Pattern) {
+        let encoded: OwnedValue = pattern.clone().into();
+
+        assert_eq!(Pattern::try_from(encoded), Ok(pattern));
+    }
+
+    #[test]
+    fn round_trip_patterns() {
+        round_trip(Pattern::Wildcard);
+        round_trip(Pattern::EmptyArray);
+        round_trip(Pattern::Bind(symbol_o!(x)));
+        round_trip(Pattern::Literal("a".into()));
+        round_trip(Pattern::Cons {
+            head: Box::new(Pattern::Literal(1.0.into())),
+            tail: Box::new(Pattern::Bind(symbol_o!(rest))),
+        });
+        round_trip(Pattern::Object(
+            vec![
+                ("x".into(), Pattern::Bind(symbol_o!(x))),
+                (
+                    symbol_o!(kind).into(),
+                    Pattern::Literal(symbol_o!(point).into()),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        ));
+    }
+
+    #[test]
+    fn decode_unexpected_pattern() {
+        assert_eq!(
+            Pattern::try_from(OwnedValue::from(symbol_o!(Pattern;Bind))),
+            Err(DecodeError::UnexpectedSymbol(symbol_o!(Pattern;Bind)))
+        );
+    }
+}
@@ -0,0 +1,938 @@
+/*
+ * Dermis is an interpreter for a pure, statically typed, imperitive language designed to be edited with a custom IDE.
+ * Copyright (C) 2018 Amelorate
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Renders the AST as source text, for reading programs outside of the IDE.
+//!
+//! Values are written as they are displayed, such as `'ns::name`, `"string"`, `[1, 2]` and
+//! `{'key: 1}`, and variables as the path of their name without a quote, such as `ns::name`.
+//! The infinite numbers are written as `inf` and `-inf`.
+//! A `Cond` is written as `if cond { a } else { b }` or as `cond ? a : b`, as chosen by it's
+//! [`CondDisplay`](::ast::expression::CondDisplay).
+//!
+//! | Expression          | Text                                    |
+//! |---------------------|-----------------------------------------|
+//! | `Nop`               | `nop`                                   |
+//! | `IdeOption`         | `@{options} id`                         |
+//! | `Typed`             | `(expr : type)`                         |
+//! | Logical operators   | `a && b`, `a \|\| b`, `a ^^ b`, `!a`, `is_null(a)`, `not_null(a)` |
+//! | Comparisons         | `a == b`, `a != b`, `a < b`, `a > b`, `a <= b`, `a >= b`, `approx_eq(a, b, factor)`, `approx_ne(a, b, factor)` |
+//! | Arithmetic          | `a ++ b`, `a + b`, `a - b`, `a * b`, `a / b`, `a // b`, `a ** b`, `sqrt(a)`, `log(a)` |
+//! | `Let`               | `let x = value in body`                 |
+//! | `Block`             | `do { a; b }`                           |
+//! | `Assign`, `AssignAt`| `x := value`, `x[key] := value`         |
+//! | `Get`               | `target[key]`                           |
+//! | `While`             | `while acc = init; cond; limit n { body }` |
+//! | `ForEach`           | `for key, item in over; acc = init { body }` |
+//! | `Break`, `Continue` | `break(value)`, `continue(value)`       |
+//! | `Lambda`, `Call`    | `fn(a, b) => body`, `f(a, b)`           |
+//! | `Match`             | `match value { pattern => body, ... }`  |
+//!
+//! Patterns are written as `_`, a variable name to bind, a literal value, `[]`, `head :: tail` or
+//! `{key: pattern}`. An array or object literal in a pattern is written as `lit [1, 2]`.
+//!
+//! A variable whose name is a keyword or is not an identifier is written as `var('name)`.
+//!
+//! The text is read back into an expression by the [parser](::ast::parse).
+
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use ast::expression::{CondDisplay, Expression};
+use ast::pattern::Pattern;
+use value::{OwnedObject, OwnedSymbol, OwnedValue};
+
+/// The width of a line used when an expression is displayed.
+pub const DEFAULT_WIDTH: usize = 80;
+
+/// The number of spaces each level of indentation is indented by.
+const INDENT: usize = 4;
+
+/// Words that can not be used as a variable name.
+pub(crate) const KEYWORDS: &[&str] = &[
+    "approx_eq",
+    "approx_ne",
+    "break",
+    "continue",
+    "do",
+    "else",
+    "fn",
+    "for",
+    "if",
+    "in",
+    "inf",
+    "is_null",
+    "let",
+    "limit",
+    "lit",
+    "log",
+    "match",
+    "nop",
+    "not_null",
+    "sqrt",
+    "var",
+    "while",
+];
+
+/// Renders an expression as source text, breaking lines that would be longer than `width` where
+/// possible.
+///
+/// # Example
+/// ```
+/// #[macro_use]
+/// extern crate dermis;
+/// use dermis::ast::expression::{CondDisplay, Expression};
+/// use dermis::ast::pretty;
+///
+/// # fn main() {
+/// let cond = |display| Expression::Cond {
+///     cond: Box::new(Expression::Var(symbol_o!(ready))),
+///     if_true: Box::new(Expression::Literal("go".into())),
+///     if_false: Box::new(Expression::Literal("wait".into())),
+///     display,
+/// };
+///
+/// assert_eq!(pretty::render(&cond(CondDisplay::Ternary), 80), "ready ? \"go\" : \"wait\"");
+/// assert_eq!(
+///     pretty::render(&cond(CondDisplay::If), 80),
+///     "if ready { \"go\" } else { \"wait\" }"
+/// );
+/// assert_eq!(
+///     pretty::render(&cond(CondDisplay::If), 20),
+///     "if ready {\n    \"go\"\n} else {\n    \"wait\"\n}"
+/// );
+/// # }
+/// ```
+pub fn render(expr: &Expression, width: usize) -> String {
+    layout(&expression(expr, Prec::Lowest), width)
+}
+
+impl Display for Expression {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", render(self, DEFAULT_WIDTH))
+    }
+}
+
+/// A document to be laid out, which may be broken onto several lines.
+#[derive(Debug, Clone)]
+enum Doc {
+    Text(String),
+    /// A space, or a newline if the enclosing group does not fit on one line.
+    Line,
+    /// Nothing, or a newline if the enclosing group does not fit on one line.
+    Break,
+    Concat(Vec<Doc>),
+    /// Indents the newlines in the document by another level.
+    Nest(Box<Doc>),
+    /// A part of the document that is put on one line if it fits.
+    Group(Box<Doc>),
+}
+
+fn text<S: Into<String>>(s: S) -> Doc {
+    Doc::Text(s.into())
+}
+
+fn concat(docs: Vec<Doc>) -> Doc {
+    Doc::Concat(docs)
+}
+
+fn nest(doc: Doc) -> Doc {
+    Doc::Nest(Box::new(doc))
+}
+
+fn group(doc: Doc) -> Doc {
+    Doc::Group(Box::new(doc))
+}
+
+/// Joins documents with `sep` followed by a `Line`.
+fn join(docs: Vec<Doc>, sep: &str) -> Doc {
+    let mut joined = vec![];
+    for (i, doc) in docs.into_iter().enumerate() {
+        if i != 0 {
+            joined.push(text(sep));
+            joined.push(Doc::Line);
+        }
+        joined.push(doc);
+    }
+    concat(joined)
+}
+
+/// `open` and `close` around `docs` separated by `sep`, with the contents indented if they do not
+/// fit on one line.
+fn bracketed(open: &str, docs: Vec<Doc>, sep: &str, close: &str) -> Doc {
+    if docs.is_empty() {
+        return text(format!("{}{}", open, close));
+    }
+    group(concat(vec![
+        text(open),
+        nest(concat(vec![Doc::Break, join(docs, sep)])),
+        Doc::Break,
+        text(close),
+    ]))
+}
+
+/// `open` and `close` around a body, which is padded with spaces when it fits on one line and
+/// indented on it's own lines otherwise.
+fn braced(open: Doc, body: Doc, close: &str) -> Doc {
+    concat(vec![
+        open,
+        text(" {"),
+        nest(concat(vec![Doc::Line, body])),
+        Doc::Line,
+        text(close),
+    ])
+}
+
+/// Lays out a document in lines of at most `width` characters where possible.
+fn layout(doc: &Doc, width: usize) -> String {
+    let mut out = String::new();
+    let mut col = 0;
+    let mut stack: Vec<(usize, bool, &Doc)> = vec![(0, false, doc)];
+    while let Some((indent, flat, doc)) = stack.pop() {
+        match doc {
+            Doc::Text(s) => {
+                out.push_str(s);
+                col += s.chars().count();
+            }
+            Doc::Line | Doc::Break if flat => {
+                if let Doc::Line = doc {
+                    out.push(' ');
+                    col += 1;
+                }
+            }
+            Doc::Line | Doc::Break => {
+                out.push('\n');
+                out.extend((0..indent).map(|_| ' '));
+                col = indent;
+            }
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|d| (indent, flat, d))),
+            Doc::Nest(doc) => stack.push((indent + INDENT, flat, doc)),
+            Doc::Group(doc) => {
+                let flat = flat || fits(width as isize - col as isize, doc, &stack);
+                stack.push((indent, flat, doc));
+            }
+        }
+    }
+    out
+}
+
+/// Whether `doc` fits in `remaining` columns when put on one line, along with whatever follows it
+/// on the same line.
+fn fits(mut remaining: isize, doc: &Doc, rest: &[(usize, bool, &Doc)]) -> bool {
+    let mut stack: Vec<(bool, &Doc)> = vec![(true, doc)];
+    let mut rest = rest.iter().rev();
+    while remaining >= 0 {
+        let (flat, doc) = match stack.pop() {
+            Some(next) => next,
+            None => match rest.next() {
+                Some(&(_, flat, doc)) => (flat, doc),
+                None => return true,
+            },
+        };
+        match doc {
+            Doc::Text(s) => remaining -= s.chars().count() as isize,
+            Doc::Line if flat => remaining -= 1,
+            Doc::Break if flat => (),
+            Doc::Line | Doc::Break => return true,
+            Doc::Concat(docs) => stack.extend(docs.iter().rev().map(|d| (flat, d))),
+            Doc::Nest(doc) | Doc::Group(doc) => stack.push((flat, doc)),
+        }
+    }
+    false
+}
+
+/// How tightly an expression binds to it's operands, from loosest to tightest.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Debug, Clone, Copy)]
+pub(crate) enum Prec {
+    /// Expressions that extend as far right as possible, such as `let`.
+    Lowest,
+    Ternary,
+    Or,
+    And,
+    Compare,
+    Add,
+    Multiply,
+    Exponent,
+    Prefix,
+    Postfix,
+    Atom,
+}
+
+impl Prec {
+    pub(crate) fn next(self) -> Prec {
+        use self::Prec::*;
+        match self {
+            Lowest => Ternary,
+            Ternary => Or,
+            Or => And,
+            And => Compare,
+            Compare => Add,
+            Add => Multiply,
+            Multiply => Exponent,
+            Exponent => Prefix,
+            Prefix => Postfix,
+            Postfix | Atom => Atom,
+        }
+    }
+}
+
+/// The text of a binary operator, and how tightly it binds.
+fn binary_op(expr: &Expression) -> Option<(&'static str, Prec, &Expression, &Expression)> {
+    use ast::expression::Expression::*;
+    Some(match expr {
+        LOr(l, r) => ("||", Prec::Or, l, r),
+        LXor(l, r) => ("^^", Prec::Or, l, r),
+        LAnd(l, r) => ("&&", Prec::And, l, r),
+        Equal(l, r) => ("==", Prec::Compare, l, r),
+        NotEqual(l, r) => ("!=", Prec::Compare, l, r),
+        LessThan(l, r) => ("<", Prec::Compare, l, r),
+        GreaterThan(l, r) => (">", Prec::Compare, l, r),
+        LesserOrEqual(l, r) => ("<=", Prec::Compare, l, r),
+        GreaterOrEqual(l, r) => (">=", Prec::Compare, l, r),
+        StrConcat(l, r) => ("++", Prec::Add, l, r),
+        Add(l, r) => ("+", Prec::Add, l, r),
+        Subtract(l, r) => ("-", Prec::Add, l, r),
+        Multiply(l, r) => ("*", Prec::Multiply, l, r),
+        Divide(l, r) => ("/", Prec::Multiply, l, r),
+        IntDivide(l, r) => ("//", Prec::Multiply, l, r),
+        Exponent(l, r) => ("**", Prec::Exponent, l, r),
+        _ => return None,
+    })
+}
+
+/// Renders `expr`, in parentheses if it binds looser than `prec`.
+fn expression(expr: &Expression, prec: Prec) -> Doc {
+    let (doc, own) = unparenthesized(expr);
+    if own < prec {
+        concat(vec![text("("), doc, text(")")])
+    } else {
+        doc
+    }
+}
+
+fn unparenthesized(expr: &Expression) -> (Doc, Prec) {
+    use ast::expression::Expression::*;
+    if let Some((op, prec, lhs, rhs)) = binary_op(expr) {
+        // Comparisons do not chain, and exponents group to the right.
+        let (l, r) = match prec {
+            Prec::Compare => (prec.next(), prec.next()),
+            Prec::Exponent => (prec.next(), prec),
+            _ => (prec, prec.next()),
+        };
+        let doc = group(concat(vec![
+            expression(lhs, l),
+            nest(concat(vec![
+                Doc::Line,
+                text(op),
+                text(" "),
+                expression(rhs, r),
+            ])),
+        ]));
+        return (doc, prec);
+    }
+
+    match expr {
+        Nop => (text("nop"), Prec::Atom),
+        Literal(val) => (value(val), Prec::Atom),
+        IdeOption { id, options } => (
+            concat(vec![
+                text("@"),
+                value(&options.clone().into()),
+                text(" "),
+                expression(id, Prec::Prefix),
+            ]),
+            Prec::Prefix,
+        ),
+        Typed { expr, ty } => (
+            concat(vec![
+                text("("),
+                expression(expr, Prec::Lowest),
+                text(format!(" : {})", ty)),
+            ]),
+            Prec::Atom,
+        ),
+        Cond {
+            cond,
+            if_true,
+            if_false,
+            display: CondDisplay::Ternary,
+        } => (
+            group(concat(vec![
+                expression(cond, Prec::Or),
+                nest(concat(vec![
+                    Doc::Line,
+                    text("? "),
+                    expression(if_true, Prec::Lowest),
+                    Doc::Line,
+                    text(": "),
+                    expression(if_false, Prec::Ternary),
+                ])),
+            ])),
+            Prec::Ternary,
+        ),
+        Cond { .. } => (group(if_else(expr)), Prec::Atom),
+        LNot(e) => (
+            concat(vec![text("!"), expression(e, Prec::Prefix)]),
+            Prec::Prefix,
+        ),
+        NotNull(e) => (builtin("not_null", vec![e]), Prec::Atom),
+        IsNull(e) => (builtin("is_null", vec![e]), Prec::Atom),
+        Sqrt(e) => (builtin("sqrt", vec![e]), Prec::Atom),
+        Log(e) => (builtin("log", vec![e]), Prec::Atom),
+        Break(e) => (builtin("break", vec![e]), Prec::Atom),
+        Continue(e) => (builtin("continue", vec![e]), Prec::Atom),
+        FloatingEqual {
+            lhs,
+            rhs,
+            rounding_factor,
+        } => (
+            floating("approx_eq", lhs, rhs, &(*rounding_factor).into()),
+            Prec::Atom,
+        ),
+        FloatingNE {
+            lhs,
+            rhs,
+            rounding_factor,
+        } => (
+            floating("approx_ne", lhs, rhs, &(*rounding_factor).into()),
+            Prec::Atom,
+        ),
+        Let { name, value, body } => (
+            group(concat(vec![
+                group(concat(vec![
+                    text(format!("let {} =", variable(name))),
+                    nest(concat(vec![Doc::Line, expression(value, Prec::Lowest)])),
+                    text(" in"),
+                ])),
+                Doc::Line,
+                expression(body, Prec::Lowest),
+            ])),
+            Prec::Lowest,
+        ),
+        Var(name) => (text(variable(name)), Prec::Atom),
+        Block(exprs) => (
+            group(braced(
+                text("do"),
+                join(
+                    exprs.iter().map(|e| expression(e, Prec::Lowest)).collect(),
+                    ";",
+                ),
+                "}",
+            )),
+            Prec::Atom,
+        ),
+        Assign { name, value } => (
+            group(concat(vec![
+                text(format!("{} :=", variable(name))),
+                nest(concat(vec![Doc::Line, expression(value, Prec::Lowest)])),
+            ])),
+            Prec::Lowest,
+        ),
+        AssignAt { name, key, value } => (
+            group(concat(vec![
+                text(variable(name)),
+                text("["),
+                expression(key, Prec::Lowest),
+                text("] :="),
+                nest(concat(vec![Doc::Line, expression(value, Prec::Lowest)])),
+            ])),
+            Prec::Lowest,
+        ),
+        Get { target, key } => (
+            concat(vec![
+                expression(target, Prec::Postfix),
+                text("["),
+                expression(key, Prec::Lowest),
+                text("]"),
+            ]),
+            Prec::Postfix,
+        ),
+        While {
+            acc,
+            init,
+            cond,
+            body,
+            limit,
+        } => (
+            group(braced(
+                concat(vec![
+                    text(format!("while {} = ", variable(acc))),
+                    expression(init, Prec::Lowest),
+                    text("; "),
+                    expression(cond, Prec::Lowest),
+                    text(format!("; limit {}", limit)),
+                ]),
+                expression(body, Prec::Lowest),
+                "}",
+            )),
+            Prec::Atom,
+        ),
+        ForEach {
+            item,
+            key,
+            over,
+            acc,
+            init,
+            body,
+        } => (
+            group(braced(
+                concat(vec![
+                    text(format!("for {}, {} in ", variable(key), variable(item))),
+                    expression(over, Prec::Lowest),
+                    text(format!("; {} = ", variable(acc))),
+                    expression(init, Prec::Lowest),
+                ]),
+                expression(body, Prec::Lowest),
+                "}",
+            )),
+            Prec::Atom,
+        ),
+        Lambda { params, body } => (
+            group(concat(vec![
+                text(format!(
+                    "fn({}) =>",
+                    params.iter().map(variable).collect::<Vec<_>>().join(", ")
+                )),
+                nest(concat(vec![Doc::Line, expression(body, Prec::Lowest)])),
+            ])),
+            Prec::Lowest,
+        ),
+        Call { function, args } => (
+            concat(vec![
+                expression(function, Prec::Postfix),
+                bracketed(
+                    "(",
+                    args.iter().map(|a| expression(a, Prec::Lowest)).collect(),
+                    ",",
+                    ")",
+                ),
+            ]),
+            Prec::Postfix,
+        ),
+        Match { scrutinee, arms } => {
+            let arms = arms
+                .iter()
+                .map(|(p, body)| {
+                    group(concat(vec![
+                        pattern(p),
+                        text(" =>"),
+                        nest(concat(vec![Doc::Line, expression(body, Prec::Lowest)])),
+                    ]))
+                })
+                .collect();
+            (
+                group(braced(
+                    concat(vec![text("match "), expression(scrutinee, Prec::Lowest)]),
+                    join(arms, ","),
+                    "}",
+                )),
+                Prec::Atom,
+            )
+        }
+        _ => unreachable!("binary operators are handled above"),
+    }
+}
+
+/// Renders a `Cond`, joining an `if` in the else branch as `else if`.
+fn if_else(expr: &Expression) -> Doc {
+    match expr {
+        Expression::Cond {
+            cond,
+            if_true,
+            if_false,
+            display: CondDisplay::If,
+        } => {
+            let head = braced(
+                concat(vec![text("if "), expression(cond, Prec::Lowest)]),
+                expression(if_true, Prec::Lowest),
+                "} else",
+            );
+            let tail = match **if_false {
+                Expression::Cond {
+                    display: CondDisplay::If,
+                    ..
+                } => concat(vec![text(" "), if_else(if_false)]),
+                _ => braced(text(""), expression(if_false, Prec::Lowest), "}"),
+            };
+            concat(vec![head, tail])
+        }
+        expr => expression(expr, Prec::Lowest),
+    }
+}
+
+/// Renders a builtin operator that is written like a function call.
+fn builtin(name: &str, args: Vec<&Expression>) -> Doc {
+    let args = args
+        .into_iter()
+        .map(|a| expression(a, Prec::Lowest))
+        .collect();
+    concat(vec![text(name), bracketed("(", args, ",", ")")])
+}
+
+fn floating(name: &str, lhs: &Expression, rhs: &Expression, factor: &OwnedValue) -> Doc {
+    concat(vec![
+        text(name),
+        bracketed(
+            "(",
+            vec![
+                expression(lhs, Prec::Lowest),
+                expression(rhs, Prec::Lowest),
+                value(factor),
+            ],
+            ",",
+            ")",
+        ),
+    ])
+}
+
+/// Renders a value as it is displayed, with strings escaped.
+fn value(val: &OwnedValue) -> Doc {
+    match val {
+        OwnedValue::String(s) => text(string_literal(s)),
+        OwnedValue::Array(arr) => bracketed("[", arr.iter().map(|v| value(&v)).collect(), ",", "]"),
+        OwnedValue::Object(obj) => bracketed("{", entries(obj, value), ",", "}"),
+        OwnedValue::Number(n) if n.into_inner() == f64::INFINITY => text("inf"),
+        OwnedValue::Number(n) if n.into_inner() == f64::NEG_INFINITY => text("-inf"),
+        val => text(val.to_string()),
+    }
+}
+
+/// Renders the entries of an object, sorted by key, as `key: value`.
+fn entries<F: Fn(&OwnedValue) -> Doc>(obj: &OwnedObject, render: F) -> Vec<Doc> {
+    let mut entries: Vec<_> = obj.iter().collect();
+    entries.sort();
+    entries
+        .into_iter()
+        .map(|(k, v)| concat(vec![value(&k), text(": "), render(&v)]))
+        .collect()
+}
+
+fn pattern(p: &Pattern) -> Doc {
+    match p {
+        Pattern::Wildcard => text("_"),
+        Pattern::Bind(name) => text(variable(name)),
+        Pattern::Literal(val @ OwnedValue::Array(_))
+        | Pattern::Literal(val @ OwnedValue::Object(_)) => concat(vec![text("lit "), value(val)]),
+        Pattern::Literal(val) => value(val),
+        Pattern::EmptyArray => text("[]"),
+        Pattern::Cons { head, tail } => {
+            let head = match **head {
+                Pattern::Cons { .. } => concat(vec![text("("), pattern(head), text(")")]),
+                _ => pattern(head),
+            };
+            concat(vec![head, text(" :: "), pattern(tail)])
+        }
+        Pattern::Object(fields) => {
+            let fields = fields
+                .iter()
+                .map(|(k, p)| concat(vec![value(k), text(": "), pattern(p)]))
+                .collect();
+            bracketed("{", fields, ",", "}")
+        }
+    }
+}
+
+/// Quotes and escapes a string.
+pub(crate) fn string_literal(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Renders the name of a variable as it's path, such as `ns::name`.
+fn variable(name: &OwnedSymbol) -> String {
+    let mut path = vec![name.get_name()];
+    let mut ns = name.get_namespace();
+    while let Some(sym) = ns {
+        path.push(sym.get_name());
+        ns = sym.get_namespace();
+    }
+    path.reverse();
+
+    if path.iter().all(|s| is_identifier(s)) {
+        path.iter()
+            .map(|s| s.as_str())
+            .collect::<Vec<_>>()
+            .join("::")
+    } else {
+        format!("var({})", name)
+    }
+}
+
+/// Whether `s` can be written as part of a variable name without quoting it.
+pub(crate) fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    let starts_well = match chars.next() {
+        Some(c) => c.is_alphabetic() || c == '_',
+        None => false,
+    };
+    starts_well
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+        && s != "_"
+        && !KEYWORDS.contains(&s)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ast::types::Type;
+    use std::collections::BTreeMap;
+    use value::OwnedArray;
+
+    type E = Box<Expression>;
+
+    fn lit(val: f64) -> E {
+        Box::new(Expression::Literal(val.into()))
+    }
+
+    fn var(name: OwnedSymbol) -> E {
+        Box::new(Expression::Var(name))
+    }
+
+    #[test]
+    fn precedence() {
+        // (1 + 2) * 3 ** 2 ** x
+        let expr = Expression::Multiply(
+            Box::new(Expression::Add(lit(1.0), lit(2.0))),
+            Box::new(Expression::Exponent(
+                lit(3.0),
+                Box::new(Expression::Exponent(lit(2.0), var(symbol_o!(x)))),
+            )),
+        );
+        assert_eq!(expr.to_string(), "(1 + 2) * 3 ** 2 ** x");
+
+        let expr =
+            Expression::Subtract(lit(1.0), Box::new(Expression::Subtract(lit(2.0), lit(3.0))));
+        assert_eq!(expr.to_string(), "1 - (2 - 3)");
+
+        let expr = Expression::LNot(Box::new(Expression::LessThan(lit(1.0), lit(2.0))));
+        assert_eq!(expr.to_string(), "!(1 < 2)");
+    }
+
+    #[test]
+    fn values() {
+        let mut obj = OwnedObject::empty();
+        obj.set_mut(symbol_o!(b).into(), "say \"hi\"\n".into());
+        obj.set_mut(
+            symbol_o!(a;b).into(),
+            OwnedArray::from(vec![1.5.into(), (-2.0).into()]).into(),
+        );
+
+        assert_eq!(
+            Expression::Literal(obj.into()).to_string(),
+            "{'a::b: [1.5, -2], 'b: \"say \\\"hi\\\"\\n\"}"
+        );
+    }
+
+    #[test]
+    fn variables() {
+        assert_eq!(
+            Expression::Var(symbol_o!(std;math;pi)).to_string(),
+            "std::math::pi"
+        );
+        assert_eq!(
+            Expression::Var(symbol_o!(app;if)).to_string(),
+            "var('app::if)"
+        );
+        assert_eq!(
+            Expression::Var(OwnedSymbol::new_global("a-b".to_string())).to_string(),
+            "var('a-b)"
+        );
+    }
+
+    #[test]
+    fn else_if() {
+        let expr = Expression::Cond {
+            cond: var(symbol_o!(a)),
+            if_true: lit(1.0),
+            if_false: Box::new(Expression::Cond {
+                cond: var(symbol_o!(b)),
+                if_true: lit(2.0),
+                if_false: lit(3.0),
+                display: CondDisplay::If,
+            }),
+            display: CondDisplay::If,
+        };
+
+        assert_eq!(expr.to_string(), "if a { 1 } else if b { 2 } else { 3 }");
+        assert_eq!(
+            render(&expr, 10),
+            "if a {\n    1\n} else if b {\n    2\n} else {\n    3\n}"
+        );
+    }
+
+    #[test]
+    fn ternary_in_operator() {
+        let expr = Expression::Add(
+            Box::new(Expression::Cond {
+                cond: var(symbol_o!(a)),
+                if_true: lit(1.0),
+                if_false: lit(2.0),
+                display: CondDisplay::Ternary,
+            }),
+            lit(3.0),
+        );
+
+        assert_eq!(expr.to_string(), "(a ? 1 : 2) + 3");
+    }
+
+    #[test]
+    fn wrapping() {
+        let args = (0..8)
+            .map(|i| Expression::Var(OwnedSymbol::new_global(format!("argument{}", i))))
+            .collect();
+        let expr = Expression::Call {
+            function: var(symbol_o!(f)),
+            args,
+        };
+
+        assert_eq!(
+            expr.to_string(),
+            "f(\n    argument0,\n    argument1,\n    argument2,\n    argument3,\n    argument4,\n    \
+             argument5,\n    argument6,\n    argument7\n)"
+        );
+        assert_eq!(
+            render(&expr, 200),
+            "f(argument0, argument1, argument2, argument3, argument4, argument5, argument6, \
+             argument7)"
+        );
+    }
+
+    #[test]
+    fn statements() {
+        let expr = Expression::Let {
+            name: symbol_o!(xs),
+            value: Box::new(Expression::Literal(OwnedArray::empty().into())),
+            body: Box::new(Expression::Block(vec![
+                Expression::AssignAt {
+                    name: symbol_o!(xs),
+                    key: lit(0.0),
+                    value: lit(1.0),
+                },
+                Expression::Assign {
+                    name: symbol_o!(y),
+                    value: Box::new(Expression::Get {
+                        target: var(symbol_o!(xs)),
+                        key: lit(0.0),
+                    }),
+                },
+            ])),
+        };
+
+        assert_eq!(
+            expr.to_string(),
+            "let xs = [] in do { xs[0] := 1; y := xs[0] }"
+        );
+    }
+
+    #[test]
+    fn loops_and_functions() {
+        let expr = Expression::Lambda {
+            params: vec![symbol_o!(xs)],
+            body: Box::new(Expression::ForEach {
+                item: symbol_o!(x),
+                key: symbol_o!(i),
+                over: var(symbol_o!(xs)),
+                acc: symbol_o!(sum),
+                init: lit(0.0),
+                body: Box::new(Expression::Add(var(symbol_o!(sum)), var(symbol_o!(x)))),
+            }),
+        };
+
+        assert_eq!(
+            expr.to_string(),
+            "fn(xs) => for i, x in xs; sum = 0 { sum + x }"
+        );
+
+        let expr = Expression::While {
+            acc: symbol_o!(i),
+            init: lit(0.0),
+            cond: Box::new(Expression::LessThan(var(symbol_o!(i)), lit(10.0))),
+            body: Box::new(Expression::Add(var(symbol_o!(i)), lit(1.0))),
+            limit: 100.0.into(),
+        };
+
+        assert_eq!(
+            render(&expr, 34),
+            "while i = 0; i < 10; limit 100 {\n    i + 1\n}"
+        );
+    }
+
+    #[test]
+    fn match_patterns() {
+        let mut fields = BTreeMap::new();
+        fields.insert(symbol_o!(k).into(), Pattern::Wildcard);
+        let expr = Expression::Match {
+            scrutinee: var(symbol_o!(x)),
+            arms: vec![
+                (Pattern::EmptyArray, *lit(0.0)),
+                (
+                    Pattern::Cons {
+                        head: Box::new(Pattern::Bind(symbol_o!(h))),
+                        tail: Box::new(Pattern::Wildcard),
+                    },
+                    Expression::Var(symbol_o!(h)),
+                ),
+                (Pattern::Object(fields), *lit(1.0)),
+                (
+                    Pattern::Literal(OwnedArray::from(vec![1.0.into()]).into()),
+                    *lit(2.0),
+                ),
+            ],
+        };
+
+        assert_eq!(
+            expr.to_string(),
+            "match x { [] => 0, h :: _ => h, {'k: _} => 1, lit [1] => 2 }"
+        );
+        assert_eq!(
+            render(&expr, 40),
+            "match x {\n    [] => 0,\n    h :: _ => h,\n    {'k: _} => 1,\n    lit [1] => 2\n}"
+        );
+    }
+
+    #[test]
+    fn builtins() {
+        let expr = Expression::Typed {
+            expr: Box::new(Expression::FloatingEqual {
+                lhs: Box::new(Expression::Sqrt(lit(2.0))),
+                rhs: lit(1.5),
+                rounding_factor: (-1.0).into(),
+            }),
+            ty: Type::Bool,
+        };
+
+        assert_eq!(expr.to_string(), "(approx_eq(sqrt(2), 1.5, -1) : bool)");
+
+        let expr = Expression::IdeOption {
+            id: Box::new(Expression::Nop),
+            options: OwnedObject::singleton("collapsed".into(), true.into()),
+        };
+
+        assert_eq!(expr.to_string(), "@{\"collapsed\": 'true} nop");
+    }
+}
@@ -0,0 +1,419 @@
+/*
+ * Dermis is an interpreter for a pure, statically typed, imperitive language designed to be edited with a custom IDE.
+ * Copyright (C) 2018 Amelorate
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The types of dermis values, as used by the [type checker](::typeck) and in type annotations.
+//!
+//! A type is encoded as a value in the same way as the rest of the AST, with it's keys and
+//! symbols in the namespace `'Type` instead of `'Ast`, for example `'Type::Number` or
+//! `{'Type::Array: 'Type::String}`.
+
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use ast::decode::{self, DecodeError, Fields};
+use value::{OwnedArray, OwnedObject, OwnedValue};
+
+#[derive(Eq, PartialEq, Hash, Ord, PartialOrd, Debug, Clone, Serialize, Deserialize)]
+pub enum Type {
+    /// A value whose type is not known until it is evaluated.
+    ///
+    /// A value of this type may be used anywhere, and any value may be used where this type is
+    /// expected.
+    Any,
+    Number,
+    String,
+    Symbol,
+    /// The symbols `'true` and `'false`.
+    Bool,
+    /// An array where every element has the given type.
+    Array(Box<Type>),
+    /// An object that has at least the given fields, each holding a value of the given type.
+    ///
+    /// Without any fields, this is the type of every object, including null.
+    Object(#[serde(with = "field_pairs")] BTreeMap<OwnedValue, Type>),
+    Function {
+        params: Vec<Type>,
+        ret: Box<Type>,
+    },
+}
+
+impl Type {
+    /// The type of every object, including null.
+    pub fn object() -> Type {
+        Type::Object(BTreeMap::new())
+    }
+
+    /// Checks if a value of this type can be used where a value of type `other` is expected.
+    ///
+    /// # Example
+    /// ```
+    /// use dermis::ast::types::Type;
+    ///
+    /// assert!(Type::Bool.is_subtype_of(&Type::Symbol));
+    /// assert!(!Type::Symbol.is_subtype_of(&Type::Bool));
+    /// assert!(Type::Any.is_subtype_of(&Type::Bool));
+    /// ```
+    pub fn is_subtype_of(&self, other: &Type) -> bool {
+        use self::Type::*;
+        match (self, other) {
+            (Any, _) | (_, Any) => true,
+            (Bool, Symbol) => true,
+            (Array(a), Array(b)) => a.is_subtype_of(b),
+            (Object(a), Object(b)) => b
+                .iter()
+                .all(|(k, b)| a.get(k).is_some_and(|a| a.is_subtype_of(b))),
+            (
+                Function { params, ret },
+                Function {
+                    params: other_params,
+                    ret: other_ret,
+                },
+            ) => {
+                params.len() == other_params.len()
+                    && other_params
+                        .iter()
+                        .zip(params)
+                        .all(|(o, p)| o.is_subtype_of(p))
+                    && ret.is_subtype_of(other_ret)
+            }
+            (a, b) => a == b,
+        }
+    }
+
+    /// The most specific type that both `self` and `other` are subtypes of.
+    pub fn join(&self, other: &Type) -> Type {
+        use self::Type::*;
+        match (self, other) {
+            (Array(a), Array(b)) => Array(Box::new(a.join(b))),
+            (Object(a), Object(b)) => Object(
+                a.iter()
+                    .filter_map(|(k, a)| b.get(k).map(|b| (k.clone(), a.join(b))))
+                    .collect(),
+            ),
+            (a, b) if a.is_subtype_of(b) && *a != Any => b.clone(),
+            (a, b) if b.is_subtype_of(a) && *b != Any => a.clone(),
+            _ => Any,
+        }
+    }
+
+    /// The type of a constant value.
+    pub fn of_value(val: &OwnedValue) -> Type {
+        match val {
+            OwnedValue::Number(_) => Type::Number,
+            OwnedValue::String(_) => Type::String,
+            OwnedValue::Symbol(_) if *val == true.into() || *val == false.into() => Type::Bool,
+            OwnedValue::Symbol(_) => Type::Symbol,
+            OwnedValue::Array(arr) => {
+                let elem =
+                    arr.iter()
+                        .map(|v| Type::of_value(&v))
+                        .fold(None, |acc: Option<Type>, t| match acc {
+                            Some(acc) => Some(acc.join(&t)),
+                            None => Some(t),
+                        });
+                Type::Array(Box::new(elem.unwrap_or(Type::Any)))
+            }
+            OwnedValue::Object(obj) => Type::Object(
+                obj.iter()
+                    .map(|(k, v)| ((*k).clone(), Type::of_value(&v)))
+                    .collect(),
+            ),
+            OwnedValue::Function(fun) => Type::Function {
+                params: vec![Type::Any; fun.params().len()],
+                ret: Box::new(Type::Any),
+            },
+        }
+    }
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        use self::Type::*;
+        match self {
+            Any => write!(f, "any"),
+            Number => write!(f, "number"),
+            String => write!(f, "string"),
+            Symbol => write!(f, "symbol"),
+            Bool => write!(f, "bool"),
+            Array(elem) => write!(f, "[{}]", elem),
+            Object(fields) if fields.is_empty() => write!(f, "object"),
+            Object(fields) => {
+                write!(f, "{{")?;
+                let mut first = true;
+                for (k, t) in fields {
+                    if !first {
+                        write!(f, ", ")?;
+                    } else {
+                        first = false;
+                    }
+                    write!(f, "{}: {}", k, t)?;
+                }
+                write!(f, "}}")
+            }
+            Function { params, ret } => {
+                write!(f, "fn(")?;
+                let mut first = true;
+                for p in params {
+                    if !first {
+                        write!(f, ", ")?;
+                    } else {
+                        first = false;
+                    }
+                    write!(f, "{}", p)?;
+                }
+                write!(f, ") -> {}", ret)
+            }
+        }
+    }
+}
+
+impl From<Type> for OwnedValue {
+    fn from(ty: Type) -> OwnedValue {
+        use self::Type::*;
+        match ty {
+            Any => symbol_o!(Type;Any).into(),
+            Number => symbol_o!(Type;Number).into(),
+            String => symbol_o!(Type;String).into(),
+            Symbol => symbol_o!(Type;Symbol).into(),
+            Bool => symbol_o!(Type;Bool).into(),
+            Array(elem) => {
+                OwnedObject::singleton(symbol_o!(Type;Array).into(), (*elem).into()).into()
+            }
+            Object(fields) => {
+                let mut o = OwnedObject::empty();
+                for (k, t) in fields {
+                    o.set_mut(k, t.into());
+                }
+                OwnedObject::singleton(symbol_o!(Type;Object).into(), o.into()).into()
+            }
+            Function { params, ret } => {
+                let params: Vec<OwnedValue> = params.into_iter().map(|p| p.into()).collect();
+                let mut o = OwnedObject::singleton(
+                    symbol_o!(Type;Function).into(),
+                    OwnedArray::from(params).into(),
+                );
+                o.set_mut(symbol_o!(Type;Function;Return).into(), (*ret).into());
+                o.into()
+            }
+        }
+    }
+}
+
+/// Decodes a type from the encoding produced by `From<Type> for OwnedValue`.
+impl TryFrom<OwnedValue> for Type {
+    type Error = DecodeError;
+
+    fn try_from(val: OwnedValue) -> Result<Type, DecodeError> {
+        let mut f = match val {
+            OwnedValue::Symbol(sym) => {
+                return match sym.get_namespace() {
+                    Some(ns) if *ns == symbol_o!(Type) => match &sym.get_name()[..] {
+                        "Any" => Ok(Type::Any),
+                        "Number" => Ok(Type::Number),
+                        "String" => Ok(Type::String),
+                        "Symbol" => Ok(Type::Symbol),
+                        "Bool" => Ok(Type::Bool),
+                        _ => Err(DecodeError::UnexpectedSymbol(sym.clone())),
+                    },
+                    _ => Err(DecodeError::UnexpectedSymbol(sym.clone())),
+                }
+            }
+            OwnedValue::Object(o) => Fields::with_root(o, symbol_o!(Type))?,
+            found => {
+                return Err(DecodeError::UnexpectedValue {
+                    expected: "a type",
+                    found,
+                })
+            }
+        };
+
+        let tag = f.tag().get_name().clone();
+        let ty = match &tag[..] {
+            "Array" => Type::Array(Box::new(Type::try_from(f.take()?)?)),
+            "Object" => Type::Object(
+                decode::object(f.take()?)?
+                    .iter()
+                    .map(|(k, t)| Ok(((*k).clone(), Type::try_from((*t).clone())?)))
+                    .collect::<Result<_, DecodeError>>()?,
+            ),
+            "Function" => Type::Function {
+                params: decode::array(f.take()?)?
+                    .iter()
+                    .map(|p| Type::try_from((*p).clone()))
+                    .collect::<Result<_, _>>()?,
+                ret: Box::new(Type::try_from(f.take_field("Return")?)?),
+            },
+            _ => return Err(DecodeError::UnexpectedSymbol(f.tag().clone())),
+        };
+        f.finish()?;
+        Ok(ty)
+    }
+}
+
+/// Serializes a map keyed by values, such as the fields of an object type, as a sequence of
+/// pairs, as the keys are not strings.
+pub(crate) mod field_pairs {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::collections::BTreeMap;
+
+    use value::OwnedValue;
+
+    pub fn serialize<S, V>(fields: &BTreeMap<OwnedValue, V>, s: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+        V: Serialize,
+    {
+        s.collect_seq(fields.iter())
+    }
+
+    pub fn deserialize<'de, D, V>(d: D) -> Result<BTreeMap<OwnedValue, V>, D::Error>
+    where
+        D: Deserializer<'de>,
+        V: Deserialize<'de>,
+    {
+        let pairs: Vec<(OwnedValue, V)> = Vec::deserialize(d)?;
+        Ok(pairs.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json;
+
+    fn array(elem: Type) -> Type {
+        Type::Array(Box::new(elem))
+    }
+
+    fn object(fields: Vec<(&str, Type)>) -> Type {
+        Type::Object(fields.into_iter().map(|(k, t)| (k.into(), t)).collect())
+    }
+
+    #[test]
+    fn of_value() {
+        assert_eq!(Type::of_value(&1.0.into()), Type::Number);
+        assert_eq!(Type::of_value(&true.into()), Type::Bool);
+        assert_eq!(Type::of_value(&symbol_o!(foo).into()), Type::Symbol);
+        assert_eq!(Type::of_value(&OwnedObject::empty().into()), Type::object());
+        assert_eq!(
+            Type::of_value(&OwnedObject::singleton("a".into(), 1.0.into()).into()),
+            object(vec![("a", Type::Number)])
+        );
+        assert_eq!(
+            Type::of_value(&OwnedArray::from(vec![true.into(), symbol_o!(a).into()]).into()),
+            array(Type::Symbol)
+        );
+        assert_eq!(
+            Type::of_value(&OwnedArray::from(vec![1.0.into(), "a".into()]).into()),
+            array(Type::Any)
+        );
+    }
+
+    #[test]
+    fn function_subtype() {
+        let f = |param: Type, ret: Type| Type::Function {
+            params: vec![param],
+            ret: Box::new(ret),
+        };
+
+        assert!(f(Type::Symbol, Type::Bool).is_subtype_of(&f(Type::Bool, Type::Symbol)));
+        assert!(!f(Type::Bool, Type::Bool).is_subtype_of(&f(Type::Symbol, Type::Bool)));
+        assert!(!f(Type::Bool, Type::Symbol).is_subtype_of(&f(Type::Bool, Type::Bool)));
+    }
+
+    #[test]
+    fn join() {
+        assert_eq!(Type::Bool.join(&Type::Symbol), Type::Symbol);
+        assert_eq!(Type::Number.join(&Type::Number), Type::Number);
+        assert_eq!(Type::Number.join(&Type::String), Type::Any);
+        assert_eq!(Type::Any.join(&Type::Number), Type::Any);
+        assert_eq!(
+            array(Type::Bool).join(&array(Type::Symbol)),
+            array(Type::Symbol)
+        );
+    }
+
+    #[test]
+    fn object_subtype() {
+        let point = object(vec![("x", Type::Number), ("y", Type::Number)]);
+
+        assert!(point.is_subtype_of(&object(vec![("x", Type::Number)])));
+        assert!(point.is_subtype_of(&Type::object()));
+        assert!(!Type::object().is_subtype_of(&point));
+        assert!(!point.is_subtype_of(&object(vec![("x", Type::String)])));
+        assert_eq!(
+            point.join(&object(vec![("x", Type::Number), ("z", Type::Bool)])),
+            object(vec![("x", Type::Number)])
+        );
+    }
+
+    fn round_trip(ty: Type) {
+        let encoded: OwnedValue = ty.clone().into();
+
+        assert_eq!(Type::try_from(encoded), Ok(ty));
+    }
+
+    #[test]
+    fn round_trip_types() {
+        round_trip(Type::Any);
+        round_trip(Type::Bool);
+        round_trip(array(array(Type::String)));
+        round_trip(Type::object());
+        round_trip(object(vec![("x", Type::Number), ("f", Type::Symbol)]));
+        round_trip(Type::Function {
+            params: vec![Type::Number, array(Type::Any)],
+            ret: Box::new(object(vec![("a", Type::Bool)])),
+        });
+    }
+
+    #[test]
+    fn decode_unexpected_type() {
+        assert_eq!(
+            Type::try_from(OwnedValue::from(symbol_o!(Type;Integer))),
+            Err(DecodeError::UnexpectedSymbol(symbol_o!(Type;Integer)))
+        );
+        assert_eq!(
+            Type::try_from(OwnedValue::from(symbol_o!(Ast;Number))),
+            Err(DecodeError::UnexpectedSymbol(symbol_o!(Ast;Number)))
+        );
+    }
+
+    #[test]
+    fn serialize_object_type() {
+        let ty = object(vec![("x", Type::Number)]);
+        let json = serde_json::to_string(&ty).unwrap();
+
+        assert_eq!(serde_json::from_str::<Type>(&json).unwrap(), ty);
+    }
+
+    #[test]
+    fn display() {
+        let t = Type::Function {
+            params: vec![array(Type::Number), Type::Bool],
+            ret: Box::new(Type::object()),
+        };
+
+        assert_eq!(t.to_string(), "fn([number], bool) -> object");
+        assert_eq!(
+            object(vec![("x", Type::Number)]).to_string(),
+            "{\"x\": number}"
+        );
+    }
+}
@@ -0,0 +1,134 @@
+/*
+ * Dermis is an interpreter for a pure, statically typed, imperitive language designed to be edited with a custom IDE.
+ * Copyright (C) 2018 Amelorate
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The errors reported by an [`Interpreter`](::Interpreter).
+
+use std::error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::sync::PoisonError;
+
+use ast::path::NodePath;
+use eval::EvalError;
+use value::symbol::{ParseSymbolError, SymbolError};
+use value::OwnedSymbol;
+
+/// An error reported by an [`Interpreter`](::Interpreter), in place of a panic.
+///
+/// # Example
+/// ```
+/// use dermis::ast::expression::Expression;
+/// use dermis::ast::path::{NodePath, Step};
+/// use dermis::eval::EvalError;
+/// use dermis::{Error, Interpreter};
+///
+/// let expr = Expression::Add(
+///     Box::new(Expression::Literal(1.0.into())),
+///     Box::new(Expression::Literal("two".into())),
+/// );
+///
+/// let err = Interpreter::new().eval(&expr).unwrap_err();
+///
+/// let mut path = NodePath::root();
+/// path.push(Step::Field("rhs"));
+/// assert_eq!(
+///     err,
+///     Error::Eval {
+///         error: EvalError::TypeMismatch {
+///             expected: "number",
+///             found: "two".into(),
+///         },
+///         path: Some(path),
+///     }
+/// );
+/// assert_eq!(err.to_string(), "root.rhs: expected a number but found \"two\"");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A symbol was given a name it can not have, such as one containing a space.
+    InvalidSymbol { name: String, error: SymbolError },
+    /// Text that was not the text of a symbol was parsed as one, see
+    /// [`Symbol::parse`](::value::Symbol::parse).
+    ParseSymbol {
+        text: String,
+        error: ParseSymbolError,
+    },
+    /// The symbol table of the interpreter was poisoned by a thread that panicked while writing to
+    /// it.
+    PoisonedInterpreter,
+    /// A symbol was used after the interpreter it was created by was dropped.
+    DroppedInterpreter,
+    /// The interpreter ran out of ids for anonymous symbols, see
+    /// [`Interpreter::gensym`](::Interpreter::gensym).
+    AnonymousSymbolsExhausted,
+    /// An expression could not be evaluated.
+    Eval {
+        error: EvalError,
+        /// The path to the node the error occured at, from the expression being evaluated.
+        ///
+        /// An error in the body of a function is reported at the `Call`, as an
+        /// [`EvalError::InFunction`](::eval::EvalError::InFunction) with the path from the body.
+        /// An error that is not caused by any one node, such as an import cycle, has no path.
+        path: Option<NodePath>,
+    },
+    /// An export of a program could not be evaluated.
+    Export {
+        /// The export the error occured in, such as `'app::main`.
+        export: OwnedSymbol,
+        /// The error, with the path to the node it occured at from the expression of the export.
+        error: Box<Error>,
+    },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidSymbol { name, error } => {
+                write!(f, "{:?} is not a valid symbol name: {}", name, error)
+            }
+            Error::ParseSymbol { text, error } => {
+                write!(f, "{:?} is not the text of a symbol: {}", text, error)
+            }
+            Error::PoisonedInterpreter => write!(f, "the interpreter's symbol table was poisoned"),
+            Error::DroppedInterpreter => write!(f, "the interpreter was dropped"),
+            Error::AnonymousSymbolsExhausted => {
+                write!(f, "the interpreter ran out of anonymous symbols")
+            }
+            Error::Eval {
+                error,
+                path: Some(path),
+            } => write!(f, "{}: {}", path, error),
+            Error::Eval { error, path: None } => write!(f, "{}", error),
+            Error::Export { export, error } => write!(f, "in {}, {}", export, error),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+impl From<EvalError> for Error {
+    fn from(error: EvalError) -> Error {
+        Error::Eval { error, path: None }
+    }
+}
+
+impl<T> From<PoisonError<T>> for Error {
+    fn from(_: PoisonError<T>) -> Error {
+        Error::PoisonedInterpreter
+    }
+}
@@ -0,0 +1,145 @@
+/*
+ * Dermis is an interpreter for a pure, statically typed, imperitive language designed to be edited with a custom IDE.
+ * Copyright (C) 2018 Amelorate
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The variables in scope during evaluation.
+
+use im::HashMap;
+use std::iter::FromIterator;
+use std::sync::Arc;
+
+use eval::EvalError;
+use value::{OwnedSymbol, Value};
+
+/// A lexical scope, mapping variable names to their values.
+///
+/// This is built on [`im::HashMap`](HashMap), so binding a variable creates a new scope that
+/// shares its storage with the enclosing scope instead of copying it.
+///
+/// # Example
+/// ```
+/// #[macro_use]
+/// extern crate dermis;
+/// use dermis::eval::Env;
+/// use dermis::value::Value;
+///
+/// # fn main() {
+/// let outer = Env::new().bind(symbol_o!(a), 1.0.into());
+/// let inner = outer.bind(symbol_o!(a), 2.0.into());
+///
+/// assert_eq!(outer.lookup(&symbol_o!(a)), Ok(Value::from(1.0)));
+/// assert_eq!(inner.lookup(&symbol_o!(a)), Ok(Value::from(2.0)));
+/// # }
+/// ```
+#[derive(Ord, PartialOrd, PartialEq, Eq, Hash, Debug, Clone, Default)]
+pub struct Env(HashMap<OwnedSymbol, Value>);
+
+impl Env {
+    pub fn new() -> Self {
+        Env::default()
+    }
+
+    /// Returns a new scope with `name` bound to `value`, shadowing any previous binding of
+    /// `name`.
+    pub fn bind(&self, name: OwnedSymbol, value: Value) -> Env {
+        Env(self.0.insert(name, value))
+    }
+
+    /// Binds `name` to `value` in this scope, shadowing any previous binding of `name`.
+    pub fn bind_mut(&mut self, name: OwnedSymbol, value: Value) {
+        self.0.insert_mut(name, value)
+    }
+
+    /// Changes the value of the variable `name`, which must already be bound.
+    ///
+    /// # Errors
+    /// [`EvalError::UnboundVariable`](EvalError::UnboundVariable) if `name` is not bound in this
+    /// scope.
+    pub fn assign(&mut self, name: &OwnedSymbol, value: Value) -> Result<(), EvalError> {
+        if self.0.contains_key(name) {
+            self.0.insert_mut(name.clone(), value);
+            Ok(())
+        } else {
+            Err(EvalError::UnboundVariable(name.clone()))
+        }
+    }
+
+    /// Ends the binding of `name`, putting back the binding it shadowed, if any.
+    pub(crate) fn restore_mut(&mut self, name: &OwnedSymbol, shadowed: Option<Value>) {
+        match shadowed {
+            Some(value) => self.0.insert_mut(name.clone(), value),
+            None => {
+                self.0.remove_mut(name);
+            }
+        }
+    }
+
+    /// Returns the value bound to `name`.
+    ///
+    /// # Errors
+    /// [`EvalError::UnboundVariable`](EvalError::UnboundVariable) if `name` is not bound in this
+    /// scope.
+    pub fn lookup(&self, name: &OwnedSymbol) -> Result<Value, EvalError> {
+        self.0
+            .get(name)
+            .map(|val| (*val).clone())
+            .ok_or_else(|| EvalError::UnboundVariable(name.clone()))
+    }
+
+    /// Iterates over every variable in scope, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (Arc<OwnedSymbol>, Arc<Value>)> {
+        self.0.iter()
+    }
+}
+
+impl FromIterator<(OwnedSymbol, Value)> for Env {
+    fn from_iter<I>(iter: I) -> Env
+    where
+        I: IntoIterator<Item = (OwnedSymbol, Value)>,
+    {
+        Env(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn env_unbound() {
+        let env = Env::new().bind(symbol_o!(foo;a), 1.0.into());
+
+        assert_eq!(
+            env.lookup(&symbol_o!(bar;a)),
+            Err(EvalError::UnboundVariable(symbol_o!(bar;a)))
+        );
+    }
+
+    #[test]
+    fn env_assign() {
+        let outer = Env::new().bind(symbol_o!(a), 1.0.into());
+        let mut inner = outer.clone();
+
+        assert_eq!(inner.assign(&symbol_o!(a), 2.0.into()), Ok(()));
+        assert_eq!(inner.lookup(&symbol_o!(a)), Ok(2.0.into()));
+        assert_eq!(outer.lookup(&symbol_o!(a)), Ok(1.0.into()));
+        assert_eq!(
+            inner.assign(&symbol_o!(b), 2.0.into()),
+            Err(EvalError::UnboundVariable(symbol_o!(b)))
+        );
+    }
+}
@@ -0,0 +1,2256 @@
+/*
+ * Dermis is an interpreter for a pure, statically typed, imperitive language designed to be edited with a custom IDE.
+ * Copyright (C) 2018 Amelorate
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! A tree-walking evaluator for the language AST.
+
+pub mod env;
+
+pub use self::env::Env;
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+use ast::expression::Expression;
+use ast::module::{ModuleError, Program};
+use ast::path::{NodePath, Step};
+use ast::pattern::Pattern;
+use value::OwnedSymbol;
+use value::{Array, Function, Number, Object, OwnedValue, Value};
+use Interpreter;
+
+/// An error that occured while evaluating an [`Expression`](::ast::expression::Expression).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EvalError {
+    /// An operator was given a value of a type it can not operate on.
+    TypeMismatch {
+        expected: &'static str,
+        found: OwnedValue,
+    },
+    /// An arithmetic operation resulted in a value that is not a number, for example `0 / 0`.
+    NotANumber {
+        operation: &'static str,
+        operands: Vec<Number>,
+    },
+    /// A variable was used that has not been bound by an enclosing `Let`.
+    UnboundVariable(OwnedSymbol),
+    /// A function was called with the wrong number of arguments.
+    ArityMismatch { expected: usize, found: usize },
+    /// An array was indexed with a number that is not a whole number, or assigned to past it's
+    /// end.
+    InvalidIndex { index: Number, len: usize },
+    /// A `Break` or `Continue` was evaluated outside of a loop.
+    BreakOutsideLoop,
+    /// A `While` loop was still running after it's iteration limit.
+    IterationLimit(Number),
+    /// None of the arms of a `Match` matched the value.
+    NoMatch(OwnedValue),
+    /// The modules of a program could not be loaded.
+    Module(ModuleError),
+    /// An error occured in the body of a called function, at `path` from the body.
+    InFunction {
+        error: Box<EvalError>,
+        path: NodePath,
+    },
+}
+
+impl Display for EvalError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            EvalError::TypeMismatch { expected, found } => {
+                write!(f, "expected a {} but found {}", expected, found)
+            }
+            EvalError::NotANumber {
+                operation,
+                operands,
+            } => {
+                write!(f, "{} of ", operation)?;
+                let mut first = true;
+                for n in operands {
+                    if !first {
+                        write!(f, " and ")?;
+                    } else {
+                        first = false;
+                    }
+                    write!(f, "{}", n)?;
+                }
+                write!(f, " is not a number")
+            }
+            EvalError::UnboundVariable(name) => write!(f, "unbound variable {}", name),
+            EvalError::ArityMismatch { expected, found } => write!(
+                f,
+                "function takes {} arguments but was given {}",
+                expected, found
+            ),
+            EvalError::InvalidIndex { index, len } => {
+                write!(f, "invalid index {} into an array of length {}", index, len)
+            }
+            EvalError::BreakOutsideLoop => write!(f, "break or continue outside of a loop"),
+            EvalError::IterationLimit(limit) => {
+                write!(f, "loop did not finish within {} iterations", limit)
+            }
+            EvalError::NoMatch(val) => write!(f, "no arm of the match covers {}", val),
+            EvalError::Module(err) => write!(f, "{}", err),
+            EvalError::InFunction { error, path } => {
+                write!(f, "in a function, {}: {}", path, error)
+            }
+        }
+    }
+}
+
+impl Error for EvalError {}
+
+impl From<ModuleError> for EvalError {
+    fn from(err: ModuleError) -> EvalError {
+        EvalError::Module(err)
+    }
+}
+
+/// Why evaluation of an expression stopped before producing a value.
+#[derive(Debug)]
+enum Unwind {
+    /// An error occured at the node being evaluated.
+    Error(EvalError),
+    /// An error occured at a node that has already been left.
+    Traced(Trace),
+    /// A `Break` is leaving the innermost loop, which results in the given value.
+    Break(Value),
+    /// A `Continue` is leaving the current iteration of the innermost loop, with the given value
+    /// as the accumulator for the next iteration.
+    Continue(Value),
+}
+
+/// An error, and the path to the node it occured at from the node being evaluated.
+#[derive(Debug)]
+struct Trace {
+    error: EvalError,
+    /// The steps of the path, starting at the node the error occured at.
+    steps: Vec<Step>,
+}
+
+impl Unwind {
+    /// An error that occured at the child reached by `step` from the node being evaluated.
+    fn at(error: EvalError, step: Step) -> Unwind {
+        Unwind::Traced(Trace {
+            error,
+            steps: vec![step],
+        })
+    }
+
+    /// Adds `step` to the path of an error leaving the child it reaches.
+    fn through(self, step: Step) -> Unwind {
+        match self {
+            Unwind::Error(error) => Unwind::at(error, step),
+            Unwind::Traced(mut trace) => {
+                trace.steps.push(step);
+                Unwind::Traced(trace)
+            }
+            unwind => unwind,
+        }
+    }
+
+    /// Converts a `Break` or `Continue` that was not caught by a loop into an error.
+    fn into_error(self) -> EvalError {
+        match self {
+            Unwind::Error(err) | Unwind::Traced(Trace { error: err, .. }) => err,
+            Unwind::Break(_) | Unwind::Continue(_) => EvalError::BreakOutsideLoop,
+        }
+    }
+
+    /// Converts into an error with the path from the node that was evaluated.
+    fn into_located(self) -> ::Error {
+        let (error, path) = self.into_parts();
+        ::Error::Eval { error, path }
+    }
+
+    /// Converts into an error with the path from the body of a function, which is reported at the
+    /// `Call`.
+    fn into_call_error(self) -> Unwind {
+        match self.into_parts() {
+            (error, Some(path)) => Unwind::Error(EvalError::InFunction {
+                error: Box::new(error),
+                path,
+            }),
+            (error, None) => Unwind::Error(error),
+        }
+    }
+
+    /// Splits into the error and the path to the node it occured at, from the node that was
+    /// evaluated.
+    fn into_parts(self) -> (EvalError, Option<NodePath>) {
+        match self {
+            Unwind::Error(error) => (error, Some(NodePath::root())),
+            Unwind::Traced(Trace { error, steps }) => {
+                let mut path = NodePath::root();
+                for step in steps.into_iter().rev() {
+                    path.push(step);
+                }
+                (error, Some(path))
+            }
+            unwind => (unwind.into_error(), None),
+        }
+    }
+}
+
+impl From<EvalError> for Unwind {
+    fn from(err: EvalError) -> Unwind {
+        Unwind::Error(err)
+    }
+}
+
+impl Interpreter {
+    /// Evaluates an expression, returning the value it results in.
+    ///
+    /// # Example
+    /// ```
+    /// use dermis::ast::expression::{CondDisplay, Expression};
+    /// use dermis::value::Value;
+    /// use dermis::Interpreter;
+    ///
+    /// let mut interpreter = Interpreter::new();
+    ///
+    /// let expr = Expression::Cond {
+    ///     cond: Box::new(Expression::Literal(1.0.into())),
+    ///     if_true: Box::new(Expression::Literal("yes".into())),
+    ///     if_false: Box::new(Expression::Literal("no".into())),
+    ///     display: CondDisplay::If,
+    /// };
+    ///
+    /// assert_eq!(interpreter.eval(&expr).unwrap(), Value::from("yes"));
+    /// ```
+    ///
+    /// # Errors
+    /// An [`Error::Eval`](::Error::Eval) with the path to the node the error occured at.
+    pub fn eval(&mut self, expr: &Expression) -> Result<Value, ::Error> {
+        self.eval_in(expr, &mut Env::new())
+            .map_err(Unwind::into_located)
+    }
+
+    /// Evaluates a program, returning the value of the export `entry`, such as `'app::main`.
+    ///
+    /// Only the module of `entry` and the modules it imports are evaluated, and each of them only
+    /// once.
+    ///
+    /// # Errors
+    /// An [`Error::Export`](::Error::Export) if an export could not be evaluated, with the path to
+    /// the node the error occured at from that export, or an [`Error::Eval`](::Error::Eval) if
+    /// the program could not be loaded.
+    ///
+    /// # Example
+    /// ```
+    /// #[macro_use]
+    /// extern crate dermis;
+    /// use dermis::ast::expression::Expression;
+    /// use dermis::ast::module::{Module, Program};
+    /// use dermis::value::Value;
+    /// use dermis::Interpreter;
+    ///
+    /// # fn main() {
+    /// let program = Program {
+    ///     modules: vec![
+    ///         Module {
+    ///             name: symbol_o!(consts),
+    ///             imports: vec![],
+    ///             exports: vec![("two".to_string(), Expression::Literal(2.0.into()))],
+    ///         },
+    ///         Module {
+    ///             name: symbol_o!(app),
+    ///             imports: vec![symbol_o!(consts)],
+    ///             exports: vec![(
+    ///                 "main".to_string(),
+    ///                 Expression::Multiply(
+    ///                     Box::new(Expression::Var(symbol_o!(consts;two))),
+    ///                     Box::new(Expression::Literal(21.0.into())),
+    ///                 ),
+    ///             )],
+    ///         },
+    ///     ],
+    /// };
+    ///
+    /// let val = Interpreter::new().eval_program(&program, &symbol_o!(app;main));
+    /// assert_eq!(val, Ok(Value::from(42.0)));
+    /// # }
+    /// ```
+    pub fn eval_program(
+        &mut self,
+        program: &Program,
+        entry: &OwnedSymbol,
+    ) -> Result<Value, ::Error> {
+        let root = entry
+            .get_namespace()
+            .ok_or_else(|| EvalError::UnboundVariable(entry.clone()))?;
+
+        let mut exports: HashMap<&OwnedSymbol, Vec<(OwnedSymbol, Value)>> = HashMap::new();
+        for module in program.load_order(root).map_err(EvalError::from)? {
+            let mut env: Env = module
+                .imports
+                .iter()
+                .flat_map(|i| exports[i].iter().cloned())
+                .collect();
+            let mut own = vec![];
+            for (name, expr) in &module.exports {
+                let name = module
+                    .export_symbol(name)
+                    .map_err(|error| ::Error::InvalidSymbol {
+                        name: name.clone(),
+                        error,
+                    })?;
+                let val =
+                    self.eval_in(expr, &mut env.clone())
+                        .map_err(|unwind| ::Error::Export {
+                            export: name.clone(),
+                            error: Box::new(unwind.into_located()),
+                        })?;
+                env.bind_mut(name.clone(), val.clone());
+                own.push((name, val));
+            }
+            exports.insert(&module.name, own);
+        }
+
+        exports[root]
+            .iter()
+            .find(|(name, _)| name == entry)
+            .map(|(_, val)| val.clone())
+            .ok_or_else(|| EvalError::UnboundVariable(entry.clone()).into())
+    }
+
+    /// Evaluates the child of a node reached by `step`, see
+    /// [`Expression::children`](::ast::expression::Expression::children).
+    fn eval_child(
+        &mut self,
+        step: Step,
+        expr: &Expression,
+        env: &mut Env,
+    ) -> Result<Value, Unwind> {
+        self.eval_in(expr, env)
+            .map_err(|unwind| unwind.through(step))
+    }
+
+    /// Evaluates an expression with the variables in `env` in scope.
+    ///
+    /// Assignments made by the expression are written to `env`.
+    fn eval_in(&mut self, expr: &Expression, env: &mut Env) -> Result<Value, Unwind> {
+        use ast::expression::Expression::*;
+        match expr {
+            Nop => Ok(Value::Object(Object::empty())),
+            Literal(val) => Ok(Value::from_owned(val, self)),
+            IdeOption { id, options: _ } => self.eval_child(Step::Field("id"), id, env),
+            Typed { expr, ty: _ } => self.eval_child(Step::Field("expr"), expr, env),
+            Cond {
+                cond,
+                if_true,
+                if_false,
+                display: _,
+            } => {
+                if self.eval_child(Step::Field("cond"), cond, env)?.is_truthy() {
+                    self.eval_child(Step::Field("if_true"), if_true, env)
+                } else {
+                    self.eval_child(Step::Field("if_false"), if_false, env)
+                }
+            }
+            LAnd(lhs, rhs) => {
+                let val = self.eval_child(Step::Field("lhs"), lhs, env)?.is_truthy()
+                    && self.eval_child(Step::Field("rhs"), rhs, env)?.is_truthy();
+                Ok(Value::from_bool(val, self))
+            }
+            LOr(lhs, rhs) => {
+                let val = self.eval_child(Step::Field("lhs"), lhs, env)?.is_truthy()
+                    || self.eval_child(Step::Field("rhs"), rhs, env)?.is_truthy();
+                Ok(Value::from_bool(val, self))
+            }
+            LXor(lhs, rhs) => {
+                let val = self.eval_child(Step::Field("lhs"), lhs, env)?.is_truthy()
+                    != self.eval_child(Step::Field("rhs"), rhs, env)?.is_truthy();
+                Ok(Value::from_bool(val, self))
+            }
+            LNot(e) => {
+                let val = !self.eval_child(Step::Field("operand"), e, env)?.is_truthy();
+                Ok(Value::from_bool(val, self))
+            }
+            NotNull(e) => {
+                let val = !self.eval_child(Step::Field("operand"), e, env)?.is_null();
+                Ok(Value::from_bool(val, self))
+            }
+            IsNull(e) => {
+                let val = self.eval_child(Step::Field("operand"), e, env)?.is_null();
+                Ok(Value::from_bool(val, self))
+            }
+            FloatingEqual {
+                lhs,
+                rhs,
+                rounding_factor,
+            } => {
+                let val = tolerant_eq(
+                    self.eval_number(Step::Field("lhs"), lhs, env)?,
+                    self.eval_number(Step::Field("rhs"), rhs, env)?,
+                    *rounding_factor,
+                );
+                Ok(Value::from_bool(val, self))
+            }
+            FloatingNE {
+                lhs,
+                rhs,
+                rounding_factor,
+            } => {
+                let val = !tolerant_eq(
+                    self.eval_number(Step::Field("lhs"), lhs, env)?,
+                    self.eval_number(Step::Field("rhs"), rhs, env)?,
+                    *rounding_factor,
+                );
+                Ok(Value::from_bool(val, self))
+            }
+            Equal(lhs, rhs) => self.compare(lhs, rhs, env, |o| o == Ordering::Equal),
+            NotEqual(lhs, rhs) => self.compare(lhs, rhs, env, |o| o != Ordering::Equal),
+            LessThan(lhs, rhs) => self.compare(lhs, rhs, env, |o| o == Ordering::Less),
+            GreaterThan(lhs, rhs) => self.compare(lhs, rhs, env, |o| o == Ordering::Greater),
+            LesserOrEqual(lhs, rhs) => self.compare(lhs, rhs, env, |o| o != Ordering::Greater),
+            GreaterOrEqual(lhs, rhs) => self.compare(lhs, rhs, env, |o| o != Ordering::Less),
+            StrConcat(lhs, rhs) => {
+                let lhs = self.eval_string(Step::Field("lhs"), lhs, env)?;
+                let rhs = self.eval_string(Step::Field("rhs"), rhs, env)?;
+                Ok(Value::String(lhs + &rhs))
+            }
+            Add(lhs, rhs) => self.arithmetic("addition", lhs, rhs, env, |l, r| l + r),
+            Subtract(lhs, rhs) => self.arithmetic("subtraction", lhs, rhs, env, |l, r| l - r),
+            Multiply(lhs, rhs) => self.arithmetic("multiplication", lhs, rhs, env, |l, r| l * r),
+            Divide(lhs, rhs) => self.arithmetic("division", lhs, rhs, env, |l, r| l / r),
+            IntDivide(lhs, rhs) => {
+                self.arithmetic("integer division", lhs, rhs, env, |l, r| (l / r).floor())
+            }
+            Exponent(lhs, rhs) => {
+                self.arithmetic("exponentiation", lhs, rhs, env, |l, r| l.powf(r))
+            }
+            Sqrt(e) => {
+                let n = self.eval_number(Step::Field("operand"), e, env)?;
+                Ok(checked_number(
+                    "square root",
+                    vec![n],
+                    n.into_inner().sqrt(),
+                )?)
+            }
+            Log(e) => {
+                let n = self.eval_number(Step::Field("operand"), e, env)?;
+                Ok(checked_number("logarithm", vec![n], n.into_inner().ln())?)
+            }
+            Let { name, value, body } => {
+                let value = self.eval_child(Step::Field("value"), value, env)?;
+                let shadowed = env.lookup(name).ok();
+                env.bind_mut(name.clone(), value);
+                let result = self.eval_child(Step::Field("body"), body, env);
+                env.restore_mut(name, shadowed);
+                result
+            }
+            Var(name) => Ok(env.lookup(name)?),
+            Lambda { params, body } => Ok(Value::Function(Function::new(
+                params.clone(),
+                Arc::new((**body).clone()),
+                env.clone(),
+            ))),
+            Call { function, args } => {
+                let function = match self.eval_child(Step::Field("function"), function, env)? {
+                    Value::Function(fun) => fun,
+                    val => {
+                        return Err(Unwind::at(
+                            EvalError::TypeMismatch {
+                                expected: "function",
+                                found: val.into(),
+                            },
+                            Step::Field("function"),
+                        ))
+                    }
+                };
+                if function.params.len() != args.len() {
+                    return Err(Unwind::Error(EvalError::ArityMismatch {
+                        expected: function.params.len(),
+                        found: args.len(),
+                    }));
+                }
+
+                let mut scope = function.env.clone();
+                for (i, (param, arg)) in function.params.iter().zip(args).enumerate() {
+                    let arg = self.eval_child(Step::Index("args", i), arg, env)?;
+                    scope = scope.bind(param.clone(), arg);
+                }
+                self.eval_in(&function.body, &mut scope)
+                    .map_err(Unwind::into_call_error)
+            }
+            Block(exprs) => {
+                let mut result = Value::Object(Object::empty());
+                for (i, expr) in exprs.iter().enumerate() {
+                    result = self.eval_child(Step::Index("exprs", i), expr, env)?;
+                }
+                Ok(result)
+            }
+            Assign { name, value } => {
+                let value = self.eval_child(Step::Field("value"), value, env)?;
+                env.assign(name, value)?;
+                Ok(Value::Object(Object::empty()))
+            }
+            AssignAt { name, key, value } => {
+                let target = env.lookup(name)?;
+                let key = self.eval_child(Step::Field("key"), key, env)?;
+                let value = self.eval_child(Step::Field("value"), value, env)?;
+                let target = match target {
+                    Value::Array(arr) => match array_index(&key, &arr)? {
+                        i if i < arr.len() => Value::Array(arr.set(i, value)),
+                        i if i == arr.len() => Value::Array(arr.push_back(value)),
+                        i => {
+                            return Err(Unwind::Error(EvalError::InvalidIndex {
+                                index: (i as f64).into(),
+                                len: arr.len(),
+                            }))
+                        }
+                    },
+                    Value::Object(obj) => Value::Object(obj.set(key, value)),
+                    val => {
+                        return Err(Unwind::Error(EvalError::TypeMismatch {
+                            expected: "array or object",
+                            found: val.into(),
+                        }))
+                    }
+                };
+                env.assign(name, target)?;
+                Ok(Value::Object(Object::empty()))
+            }
+            Get { target, key } => {
+                let target_val = self.eval_child(Step::Field("target"), target, env)?;
+                let key = self.eval_child(Step::Field("key"), key, env)?;
+                match target_val {
+                    Value::Array(arr) => Ok((*arr.get(array_index(&key, &arr)?)).clone()),
+                    Value::Object(obj) => Ok((*obj.get(&key)).clone()),
+                    val => Err(Unwind::at(
+                        EvalError::TypeMismatch {
+                            expected: "array or object",
+                            found: val.into(),
+                        },
+                        Step::Field("target"),
+                    )),
+                }
+            }
+            While {
+                acc,
+                init,
+                cond,
+                body,
+                limit,
+            } => {
+                let mut val = self.eval_loop_part(Step::Field("init"), init, env)?;
+                let mut iterations = 0.0;
+                loop {
+                    let mut scope = env.bind(acc.clone(), val.clone());
+                    if !self
+                        .eval_loop_part(Step::Field("cond"), cond, &mut scope)?
+                        .is_truthy()
+                    {
+                        return Ok(val);
+                    }
+                    if iterations >= limit.into_inner() {
+                        return Err(Unwind::Error(EvalError::IterationLimit(*limit)));
+                    }
+                    iterations += 1.0;
+
+                    match self.eval_child(Step::Field("body"), body, &mut scope) {
+                        Ok(next) | Err(Unwind::Continue(next)) => val = next,
+                        Err(Unwind::Break(result)) => return Ok(result),
+                        Err(err) => return Err(err),
+                    }
+                }
+            }
+            ForEach {
+                item,
+                key,
+                over,
+                acc,
+                init,
+                body,
+            } => {
+                let over_val = self.eval_loop_part(Step::Field("over"), over, env)?;
+                let entries: Vec<(Value, Value)> = match over_val {
+                    Value::Array(arr) => arr
+                        .iter()
+                        .enumerate()
+                        .map(|(i, v)| (Value::Number((i as f64).into()), (*v).clone()))
+                        .collect(),
+                    Value::Object(obj) => {
+                        let mut entries: Vec<(Value, Value)> = obj
+                            .iter()
+                            .map(|(k, v)| ((*k).clone(), (*v).clone()))
+                            .collect();
+                        entries.sort();
+                        entries
+                    }
+                    val => {
+                        return Err(Unwind::at(
+                            EvalError::TypeMismatch {
+                                expected: "array or object",
+                                found: val.into(),
+                            },
+                            Step::Field("over"),
+                        ))
+                    }
+                };
+
+                let mut val = self.eval_loop_part(Step::Field("init"), init, env)?;
+                for (k, v) in entries {
+                    let mut scope = env
+                        .bind(acc.clone(), val)
+                        .bind(key.clone(), k)
+                        .bind(item.clone(), v);
+                    match self.eval_child(Step::Field("body"), body, &mut scope) {
+                        Ok(next) | Err(Unwind::Continue(next)) => val = next,
+                        Err(Unwind::Break(result)) => return Ok(result),
+                        Err(err) => return Err(err),
+                    }
+                }
+                Ok(val)
+            }
+            Break(e) => Err(Unwind::Break(self.eval_child(
+                Step::Field("operand"),
+                e,
+                env,
+            )?)),
+            Continue(e) => Err(Unwind::Continue(self.eval_child(
+                Step::Field("operand"),
+                e,
+                env,
+            )?)),
+            Match { scrutinee, arms } => {
+                let val = self.eval_child(Step::Field("scrutinee"), scrutinee, env)?;
+                for (i, (pattern, body)) in arms.iter().enumerate() {
+                    let mut bindings = vec![];
+                    if !self.match_pattern(pattern, &val, &mut bindings) {
+                        continue;
+                    }
+
+                    let shadowed: Vec<(OwnedSymbol, Option<Value>)> = bindings
+                        .iter()
+                        .map(|(name, _)| (name.clone(), env.lookup(name).ok()))
+                        .collect();
+                    for (name, val) in bindings {
+                        env.bind_mut(name, val);
+                    }
+                    let result = self.eval_child(Step::Index("arms", i), body, env);
+                    for (name, shadowed) in shadowed.into_iter().rev() {
+                        env.restore_mut(&name, shadowed);
+                    }
+                    return result;
+                }
+                Err(Unwind::Error(EvalError::NoMatch(val.into())))
+            }
+        }
+    }
+
+    /// Checks if `val` matches `pattern`, adding the variables it binds to `bindings`.
+    fn match_pattern(
+        &mut self,
+        pattern: &Pattern,
+        val: &Value,
+        bindings: &mut Vec<(OwnedSymbol, Value)>,
+    ) -> bool {
+        match (pattern, val) {
+            (Pattern::Wildcard, _) => true,
+            (Pattern::Bind(name), _) => {
+                bindings.push((name.clone(), val.clone()));
+                true
+            }
+            (Pattern::Literal(lit), _) => Value::from_owned(lit, self) == *val,
+            (Pattern::EmptyArray, Value::Array(arr)) => arr.is_empty(),
+            (Pattern::Cons { head, tail }, Value::Array(arr)) => match (arr.head(), arr.tail()) {
+                (Some(h), Some(t)) => {
+                    self.match_pattern(head, &h, bindings)
+                        && self.match_pattern(tail, &Value::Array(t), bindings)
+                }
+                _ => false,
+            },
+            (Pattern::Object(fields), Value::Object(obj)) => fields.iter().all(|(k, p)| {
+                let k = Value::from_owned(k, self);
+                match obj.get_opt(&k) {
+                    Some(v) => self.match_pattern(p, &v, bindings),
+                    None => false,
+                }
+            }),
+            _ => false,
+        }
+    }
+
+    /// Evaluates a part of a loop other than its body, such as its condition.
+    ///
+    /// A `Break` or `Continue` there would otherwise leave an enclosing loop, so it is an error.
+    fn eval_loop_part(
+        &mut self,
+        step: Step,
+        expr: &Expression,
+        env: &mut Env,
+    ) -> Result<Value, Unwind> {
+        self.eval_child(step, expr, env)
+            .map_err(|unwind| match unwind {
+                Unwind::Break(_) | Unwind::Continue(_) => {
+                    Unwind::at(EvalError::BreakOutsideLoop, step)
+                }
+                unwind => unwind,
+            })
+    }
+
+    fn eval_string(
+        &mut self,
+        step: Step,
+        expr: &Expression,
+        env: &mut Env,
+    ) -> Result<String, Unwind> {
+        match self.eval_child(step, expr, env)? {
+            Value::String(s) => Ok(s),
+            val => Err(Unwind::at(
+                EvalError::TypeMismatch {
+                    expected: "string",
+                    found: val.into(),
+                },
+                step,
+            )),
+        }
+    }
+
+    /// Evaluates both sides as numbers and applies `f` to them.
+    fn arithmetic<F>(
+        &mut self,
+        operation: &'static str,
+        lhs: &Expression,
+        rhs: &Expression,
+        env: &mut Env,
+        f: F,
+    ) -> Result<Value, Unwind>
+    where
+        F: FnOnce(f64, f64) -> f64,
+    {
+        let lhs = self.eval_number(Step::Field("lhs"), lhs, env)?;
+        let rhs = self.eval_number(Step::Field("rhs"), rhs, env)?;
+        Ok(checked_number(
+            operation,
+            vec![lhs, rhs],
+            f(lhs.into_inner(), rhs.into_inner()),
+        )?)
+    }
+
+    fn eval_number(
+        &mut self,
+        step: Step,
+        expr: &Expression,
+        env: &mut Env,
+    ) -> Result<Number, Unwind> {
+        match self.eval_child(step, expr, env)? {
+            Value::Number(n) => Ok(n),
+            val => Err(Unwind::at(
+                EvalError::TypeMismatch {
+                    expected: "number",
+                    found: val.into(),
+                },
+                step,
+            )),
+        }
+    }
+
+    /// Evaluates both sides and checks their ordering with `f`, using the `Ord` implementation on
+    /// [`Value`](::value::Value).
+    fn compare<F>(
+        &mut self,
+        lhs: &Expression,
+        rhs: &Expression,
+        env: &mut Env,
+        f: F,
+    ) -> Result<Value, Unwind>
+    where
+        F: FnOnce(Ordering) -> bool,
+    {
+        let lhs = self.eval_child(Step::Field("lhs"), lhs, env)?;
+        let rhs = self.eval_child(Step::Field("rhs"), rhs, env)?;
+        Ok(Value::from_bool(f(lhs.cmp(&rhs)), self))
+    }
+}
+
+/// Converts `key` into an index of `arr`, which may be past the end of `arr`.
+fn array_index(key: &Value, arr: &Array) -> Result<usize, EvalError> {
+    let index = match key {
+        Value::Number(n) => *n,
+        val => {
+            return Err(EvalError::TypeMismatch {
+                expected: "number",
+                found: val.clone().into(),
+            })
+        }
+    };
+    let i = index.into_inner();
+    if i < 0.0 || i.fract() != 0.0 || i.is_infinite() {
+        Err(EvalError::InvalidIndex {
+            index,
+            len: arr.len(),
+        })
+    } else {
+        Ok(i as usize)
+    }
+}
+
+/// Converts the result of an arithmetic operation to a value, failing if it is NaN.
+///
+/// [`Number`](::value::Number) can not hold NaN and would panic instead.
+fn checked_number(
+    operation: &'static str,
+    operands: Vec<Number>,
+    result: f64,
+) -> Result<Value, EvalError> {
+    if result.is_nan() {
+        Err(EvalError::NotANumber {
+            operation,
+            operands,
+        })
+    } else {
+        Ok(Value::Number(result.into()))
+    }
+}
+
+/// Tolerant comparison of two numbers, see
+/// [`Expression::FloatingEqual`](::ast::expression::Expression::FloatingEqual).
+fn tolerant_eq(lhs: Number, rhs: Number, rounding_factor: Number) -> bool {
+    let (lhs, rhs) = (lhs.into_inner(), rhs.into_inner());
+    let factor = if rounding_factor.into_inner() < 0.0 {
+        2f64.powi(-44)
+    } else {
+        rounding_factor.into_inner()
+    };
+
+    // Any tolerance scaled by an infinity is infinite, so infinities are only equal to themselves.
+    if lhs.is_infinite() || rhs.is_infinite() {
+        return lhs == rhs;
+    }
+
+    lhs == rhs || (lhs - rhs).abs() <= lhs.abs().max(rhs.abs()) * factor
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ast::expression::CondDisplay;
+    use ast::module::Module;
+    use serde_json;
+    use value::{OwnedArray, OwnedObject, SymbolError};
+
+    fn lit(val: f64) -> Box<Expression> {
+        Box::new(Expression::Literal(val.into()))
+    }
+
+    #[test]
+    fn eval_nop() {
+        let mut i = Interpreter::new();
+
+        assert!(i.eval(&Expression::Nop).unwrap().is_null());
+    }
+
+    #[test]
+    fn eval_literal() {
+        let mut i = Interpreter::new();
+
+        let val = i.eval(&Expression::Literal(symbol_o!(foo;bar).into())).unwrap();
+
+        assert_eq!(val, symbol!(foo;bar, &mut i).into());
+    }
+
+    #[test]
+    fn eval_ide_option() {
+        let mut i = Interpreter::new();
+        let expr = Expression::IdeOption {
+            id: lit(12.0),
+            options: OwnedObject::singleton("collapsed".into(), 1.0.into()),
+        };
+
+        assert_eq!(i.eval(&expr).unwrap(), Value::from(12.0));
+    }
+
+    #[test]
+    fn eval_cond_true() {
+        let mut i = Interpreter::new();
+        let expr = Expression::Cond {
+            cond: lit(0.0),
+            if_true: lit(1.0),
+            if_false: lit(2.0),
+            display: CondDisplay::Ternary,
+        };
+
+        assert_eq!(i.eval(&expr).unwrap(), Value::from(1.0));
+    }
+
+    fn boolean(val: bool) -> Box<Expression> {
+        Box::new(Expression::Literal(val.into()))
+    }
+
+    fn eval_bool(expr: Expression) -> bool {
+        let mut i = Interpreter::new();
+        let val = i.eval(&expr).unwrap();
+
+        assert!(
+            val == Value::from_bool(true, &mut i) || val == Value::from_bool(false, &mut i),
+            "{} is not a boolean",
+            val
+        );
+        val.is_truthy()
+    }
+
+    #[test]
+    fn eval_cond_false_symbol() {
+        let mut i = Interpreter::new();
+        let expr = Expression::Cond {
+            cond: boolean(false),
+            if_true: lit(1.0),
+            if_false: lit(2.0),
+            display: CondDisplay::If,
+        };
+
+        assert_eq!(i.eval(&expr).unwrap(), Value::from(2.0));
+    }
+
+    #[test]
+    fn eval_land() {
+        assert!(eval_bool(Expression::LAnd(lit(1.0), boolean(true))));
+        assert!(!eval_bool(Expression::LAnd(lit(1.0), Box::new(Expression::Nop))));
+        assert!(!eval_bool(Expression::LAnd(boolean(false), boolean(true))));
+    }
+
+    #[test]
+    fn eval_lor() {
+        assert!(eval_bool(Expression::LOr(boolean(false), lit(0.0))));
+        assert!(eval_bool(Expression::LOr(boolean(true), boolean(false))));
+        assert!(!eval_bool(Expression::LOr(Box::new(Expression::Nop), boolean(false))));
+    }
+
+    /// A literal that interns the symbol `'unevaluated` when it is evaluated.
+    fn unevaluated() -> Box<Expression> {
+        let sym = OwnedSymbol::new_global("unevaluated".to_string());
+        Box::new(Expression::Literal(OwnedValue::Symbol(sym)))
+    }
+
+    fn was_evaluated(i: &Interpreter) -> bool {
+        let table = i.symbol_table.read().unwrap();
+        table.global_symbols.iter().any(|n| **n == "unevaluated")
+    }
+
+    #[test]
+    fn eval_land_short_circuit() {
+        let mut i = Interpreter::new();
+        let val = i.eval(&Expression::LAnd(boolean(false), unevaluated())).unwrap();
+
+        assert!(!val.is_truthy());
+        assert!(!was_evaluated(&i));
+    }
+
+    #[test]
+    fn eval_lor_short_circuit() {
+        let mut i = Interpreter::new();
+        let val = i.eval(&Expression::LOr(boolean(true), unevaluated())).unwrap();
+
+        assert!(val.is_truthy());
+        assert!(!was_evaluated(&i));
+    }
+
+    #[test]
+    fn eval_lxor() {
+        assert!(eval_bool(Expression::LXor(boolean(true), boolean(false))));
+        assert!(!eval_bool(Expression::LXor(boolean(true), lit(1.0))));
+        assert!(!eval_bool(Expression::LXor(boolean(false), Box::new(Expression::Nop))));
+    }
+
+    #[test]
+    fn eval_lnot() {
+        assert!(eval_bool(Expression::LNot(Box::new(Expression::Nop))));
+        assert!(!eval_bool(Expression::LNot(lit(0.0))));
+    }
+
+    #[test]
+    fn eval_null_checks() {
+        assert!(eval_bool(Expression::IsNull(Box::new(Expression::Nop))));
+        assert!(!eval_bool(Expression::IsNull(boolean(false))));
+        assert!(eval_bool(Expression::NotNull(boolean(false))));
+        assert!(!eval_bool(Expression::NotNull(Box::new(Expression::Nop))));
+    }
+
+    fn compare(
+        op: fn(Box<Expression>, Box<Expression>) -> Expression,
+        lhs: OwnedValue,
+        rhs: OwnedValue,
+    ) -> bool {
+        let lhs = Box::new(Expression::Literal(lhs));
+        let rhs = Box::new(Expression::Literal(rhs));
+        eval_bool(op(lhs, rhs))
+    }
+
+    fn floating_equal(lhs: f64, rhs: f64, rounding_factor: f64) -> bool {
+        eval_bool(Expression::FloatingEqual {
+            lhs: lit(lhs),
+            rhs: lit(rhs),
+            rounding_factor: rounding_factor.into(),
+        })
+    }
+
+    #[test]
+    fn eval_equal() {
+        assert!(compare(Expression::Equal, 1.0.into(), 1.0.into()));
+        assert!(compare(
+            Expression::Equal,
+            symbol_o!(a;b).into(),
+            symbol_o!(a;b).into()
+        ));
+        assert!(!compare(Expression::Equal, "1".into(), 1.0.into()));
+        assert!(compare(
+            Expression::NotEqual,
+            symbol_o!(a;b).into(),
+            symbol_o!(a;c).into()
+        ));
+        assert!(!compare(Expression::NotEqual, "a".into(), "a".into()));
+    }
+
+    #[test]
+    fn eval_ordering() {
+        assert!(compare(Expression::LessThan, 1.0.into(), 2.0.into()));
+        assert!(!compare(Expression::LessThan, 2.0.into(), 2.0.into()));
+        assert!(compare(Expression::GreaterThan, "b".into(), "a".into()));
+        assert!(compare(Expression::LesserOrEqual, 2.0.into(), 2.0.into()));
+        assert!(!compare(Expression::LesserOrEqual, 3.0.into(), 2.0.into()));
+        assert!(compare(Expression::GreaterOrEqual, 2.0.into(), 2.0.into()));
+        assert!(!compare(Expression::GreaterOrEqual, 1.0.into(), 2.0.into()));
+    }
+
+    #[test]
+    fn eval_ordering_mixed_types() {
+        assert!(compare(Expression::LessThan, 100.0.into(), "a".into()));
+        assert!(compare(
+            Expression::LessThan,
+            "a".into(),
+            symbol_o!(a).into()
+        ));
+        assert!(compare(
+            Expression::LessThan,
+            symbol_o!(a).into(),
+            OwnedArray::empty().into()
+        ));
+        assert!(compare(
+            Expression::LessThan,
+            OwnedArray::empty().into(),
+            OwnedObject::empty().into()
+        ));
+    }
+
+    #[test]
+    fn eval_floating_equal() {
+        assert!(floating_equal(0.1 + 0.2, 0.3, -1.0));
+        assert!(!floating_equal(1.0, 1.001, -1.0));
+        assert!(floating_equal(1.0, 1.001, 0.01));
+        assert!(floating_equal(-100.0, -100.5, 0.01));
+        assert!(floating_equal(0.0, 0.0, 0.0));
+        assert!(floating_equal(f64::INFINITY, f64::INFINITY, -1.0));
+        assert!(!floating_equal(f64::INFINITY, 1.0, -1.0));
+        assert!(!floating_equal(f64::MAX, f64::INFINITY, 0.5));
+        assert!(!floating_equal(f64::INFINITY, f64::NEG_INFINITY, -1.0));
+    }
+
+    #[test]
+    fn eval_floating_ne() {
+        let expr = Expression::FloatingNE {
+            lhs: lit(0.1 + 0.2),
+            rhs: lit(0.3),
+            rounding_factor: (-1.0).into(),
+        };
+
+        assert!(!eval_bool(expr));
+    }
+
+    #[test]
+    fn eval_floating_equal_type_mismatch() {
+        let mut i = Interpreter::new();
+        let expr = Expression::FloatingEqual {
+            lhs: lit(1.0),
+            rhs: Box::new(Expression::Literal("1".into())),
+            rounding_factor: (-1.0).into(),
+        };
+
+        assert_eq!(
+            i.eval(&expr).map_err(without_path),
+            Err(EvalError::TypeMismatch {
+                expected: "number",
+                found: "1".into(),
+            })
+        );
+    }
+
+    /// The error, without the path to where it occured.
+    fn without_path(err: ::Error) -> EvalError {
+        match err {
+            ::Error::Eval { error, .. } => error,
+            ::Error::Export { error, .. } => without_path(*error),
+            err => panic!("expected an evaluation error, found {:?}", err),
+        }
+    }
+
+    fn eval_lit(expr: Expression) -> Result<Value, EvalError> {
+        Interpreter::new().eval(&expr).map_err(without_path)
+    }
+
+    fn not_a_number(operation: &'static str, operands: &[f64]) -> Result<Value, EvalError> {
+        Err(EvalError::NotANumber {
+            operation,
+            operands: operands.iter().map(|&n| n.into()).collect(),
+        })
+    }
+
+    #[test]
+    fn eval_arithmetic() {
+        assert_eq!(
+            eval_lit(Expression::Add(lit(1.0), lit(2.0))),
+            Ok(3.0.into())
+        );
+        assert_eq!(
+            eval_lit(Expression::Subtract(lit(1.0), lit(2.0))),
+            Ok((-1.0).into())
+        );
+        assert_eq!(
+            eval_lit(Expression::Multiply(lit(3.0), lit(2.0))),
+            Ok(6.0.into())
+        );
+        assert_eq!(
+            eval_lit(Expression::Divide(lit(3.0), lit(2.0))),
+            Ok(1.5.into())
+        );
+        assert_eq!(
+            eval_lit(Expression::IntDivide(lit(3.0), lit(2.0))),
+            Ok(1.0.into())
+        );
+        assert_eq!(
+            eval_lit(Expression::IntDivide(lit(-3.0), lit(2.0))),
+            Ok((-2.0).into())
+        );
+        assert_eq!(
+            eval_lit(Expression::Exponent(lit(2.0), lit(10.0))),
+            Ok(1024.0.into())
+        );
+        assert_eq!(eval_lit(Expression::Sqrt(lit(16.0))), Ok(4.0.into()));
+        assert_eq!(eval_lit(Expression::Log(lit(1.0))), Ok(0.0.into()));
+    }
+
+    #[test]
+    fn eval_divide_by_zero_is_infinite() {
+        assert_eq!(
+            eval_lit(Expression::Divide(lit(1.0), lit(0.0))),
+            Ok(f64::INFINITY.into())
+        );
+    }
+
+    #[test]
+    fn eval_not_a_number() {
+        assert_eq!(
+            eval_lit(Expression::Divide(lit(0.0), lit(0.0))),
+            not_a_number("division", &[0.0, 0.0])
+        );
+        assert_eq!(
+            eval_lit(Expression::Sqrt(lit(-1.0))),
+            not_a_number("square root", &[-1.0])
+        );
+        assert_eq!(
+            eval_lit(Expression::Log(lit(-1.0))),
+            not_a_number("logarithm", &[-1.0])
+        );
+        assert_eq!(
+            eval_lit(Expression::Subtract(lit(f64::INFINITY), lit(f64::INFINITY))),
+            not_a_number("subtraction", &[f64::INFINITY, f64::INFINITY])
+        );
+    }
+
+    #[test]
+    fn not_a_number_display() {
+        let err = not_a_number("division", &[0.0, 0.0]).unwrap_err();
+
+        assert_eq!(err.to_string(), "division of 0 and 0 is not a number");
+    }
+
+    #[test]
+    fn eval_arithmetic_type_mismatch() {
+        assert_eq!(
+            eval_lit(Expression::Add(lit(1.0), Box::new(Expression::Nop))),
+            Err(EvalError::TypeMismatch {
+                expected: "number",
+                found: OwnedObject::empty().into(),
+            })
+        );
+    }
+
+    #[test]
+    fn eval_str_concat() {
+        let expr = Expression::StrConcat(
+            Box::new(Expression::Literal("foo".into())),
+            Box::new(Expression::Literal("bar".into())),
+        );
+
+        assert_eq!(eval_lit(expr), Ok("foobar".into()));
+    }
+
+    fn var(name: OwnedSymbol) -> Box<Expression> {
+        Box::new(Expression::Var(name))
+    }
+
+    #[test]
+    fn eval_let() {
+        let expr = Expression::Let {
+            name: symbol_o!(a),
+            value: lit(2.0),
+            body: Box::new(Expression::Multiply(var(symbol_o!(a)), lit(3.0))),
+        };
+
+        assert_eq!(eval_lit(expr), Ok(6.0.into()));
+    }
+
+    #[test]
+    fn eval_let_shadowing() {
+        let expr = Expression::Let {
+            name: symbol_o!(a),
+            value: lit(1.0),
+            body: Box::new(Expression::Add(
+                Box::new(Expression::Let {
+                    name: symbol_o!(a),
+                    value: lit(10.0),
+                    body: var(symbol_o!(a)),
+                }),
+                var(symbol_o!(a)),
+            )),
+        };
+
+        assert_eq!(eval_lit(expr), Ok(11.0.into()));
+    }
+
+    #[test]
+    fn eval_let_namespaces() {
+        let expr = Expression::Let {
+            name: symbol_o!(foo;a),
+            value: lit(1.0),
+            body: Box::new(Expression::Let {
+                name: symbol_o!(bar;a),
+                value: lit(2.0),
+                body: Box::new(Expression::Subtract(
+                    var(symbol_o!(foo;a)),
+                    var(symbol_o!(bar;a)),
+                )),
+            }),
+        };
+
+        assert_eq!(eval_lit(expr), Ok((-1.0).into()));
+    }
+
+    #[test]
+    fn eval_let_value_not_in_scope() {
+        let expr = Expression::Let {
+            name: symbol_o!(a),
+            value: var(symbol_o!(a)),
+            body: var(symbol_o!(a)),
+        };
+
+        assert_eq!(
+            eval_lit(expr),
+            Err(EvalError::UnboundVariable(symbol_o!(a)))
+        );
+    }
+
+    #[test]
+    fn eval_unbound_variable() {
+        let err = eval_lit(Expression::Var(symbol_o!(foo;bar;baz))).unwrap_err();
+
+        assert_eq!(err, EvalError::UnboundVariable(symbol_o!(foo;bar;baz)));
+        assert_eq!(err.to_string(), "unbound variable 'foo::bar::baz");
+    }
+
+    fn lambda(params: Vec<OwnedSymbol>, body: Expression) -> Box<Expression> {
+        Box::new(Expression::Lambda {
+            params,
+            body: Box::new(body),
+        })
+    }
+
+    #[test]
+    fn eval_call() {
+        let expr = Expression::Call {
+            function: lambda(
+                vec![symbol_o!(a), symbol_o!(b)],
+                Expression::Subtract(var(symbol_o!(a)), var(symbol_o!(b))),
+            ),
+            args: vec![*lit(5.0), *lit(3.0)],
+        };
+
+        assert_eq!(eval_lit(expr), Ok(2.0.into()));
+    }
+
+    /// `let add = (fn(a) => fn(b) => a + b) in add(1)(2)`
+    #[test]
+    fn eval_closure_captures() {
+        let expr = Expression::Let {
+            name: symbol_o!(add),
+            value: lambda(
+                vec![symbol_o!(a)],
+                *lambda(
+                    vec![symbol_o!(b)],
+                    Expression::Add(var(symbol_o!(a)), var(symbol_o!(b))),
+                ),
+            ),
+            body: Box::new(Expression::Call {
+                function: Box::new(Expression::Call {
+                    function: var(symbol_o!(add)),
+                    args: vec![*lit(1.0)],
+                }),
+                args: vec![*lit(2.0)],
+            }),
+        };
+
+        assert_eq!(eval_lit(expr), Ok(3.0.into()));
+    }
+
+    #[test]
+    fn eval_closure_lexical_scope() {
+        // The function sees the `a` in scope where it was created, not where it was called.
+        let expr = Expression::Let {
+            name: symbol_o!(a),
+            value: lit(1.0),
+            body: Box::new(Expression::Let {
+                name: symbol_o!(f),
+                value: lambda(vec![], Expression::Var(symbol_o!(a))),
+                body: Box::new(Expression::Let {
+                    name: symbol_o!(a),
+                    value: lit(2.0),
+                    body: Box::new(Expression::Call {
+                        function: var(symbol_o!(f)),
+                        args: vec![],
+                    }),
+                }),
+            }),
+        };
+
+        assert_eq!(eval_lit(expr), Ok(1.0.into()));
+    }
+
+    #[test]
+    fn eval_call_arity_mismatch() {
+        let expr = Expression::Call {
+            function: lambda(vec![symbol_o!(a)], Expression::Nop),
+            args: vec![],
+        };
+
+        assert_eq!(
+            eval_lit(expr),
+            Err(EvalError::ArityMismatch {
+                expected: 1,
+                found: 0,
+            })
+        );
+    }
+
+    #[test]
+    fn eval_call_not_a_function() {
+        let expr = Expression::Call {
+            function: lit(1.0),
+            args: vec![],
+        };
+
+        assert_eq!(
+            eval_lit(expr),
+            Err(EvalError::TypeMismatch {
+                expected: "function",
+                found: 1.0.into(),
+            })
+        );
+    }
+
+    #[test]
+    fn function_owned_round_trip() {
+        // A closure stored in an array survives being serialized and loaded into a different
+        // interpreter.
+        let mut i = Interpreter::new();
+        let closure = i
+            .eval(&Expression::Let {
+                name: symbol_o!(n),
+                value: lit(10.0),
+                body: lambda(
+                    vec![symbol_o!(x)],
+                    Expression::Multiply(var(symbol_o!(x)), var(symbol_o!(n))),
+                ),
+            })
+            .unwrap();
+        let arr: OwnedValue = Array::from(vec![closure]).into();
+
+        let json = serde_json::to_string(&arr).unwrap();
+        let arr: OwnedValue = serde_json::from_str(&json).unwrap();
+
+        let expr = Expression::Call {
+            function: Box::new(Expression::Literal(match arr {
+                OwnedValue::Array(a) => (*a.get_unwrapped(0)).clone(),
+                _ => unreachable!(),
+            })),
+            args: vec![*lit(4.0)],
+        };
+
+        assert_eq!(Interpreter::new().eval(&expr), Ok(40.0.into()));
+    }
+
+    fn assign(name: OwnedSymbol, value: Expression) -> Expression {
+        Expression::Assign {
+            name,
+            value: Box::new(value),
+        }
+    }
+
+    fn array(vals: Vec<f64>) -> Box<Expression> {
+        let vals: Vec<OwnedValue> = vals.into_iter().map(|v| v.into()).collect();
+        Box::new(Expression::Literal(OwnedArray::from(vals).into()))
+    }
+
+    #[test]
+    fn eval_block() {
+        assert_eq!(
+            eval_lit(Expression::Block(vec![*lit(1.0), *lit(2.0)])),
+            Ok(2.0.into())
+        );
+        assert_eq!(
+            eval_lit(Expression::Block(vec![])),
+            Ok(Value::Object(Object::empty()))
+        );
+    }
+
+    /// `let a = 1 in { a = a + 1; a = a * 10; a }`
+    #[test]
+    fn eval_assign() {
+        let expr = Expression::Let {
+            name: symbol_o!(a),
+            value: lit(1.0),
+            body: Box::new(Expression::Block(vec![
+                assign(symbol_o!(a), Expression::Add(var(symbol_o!(a)), lit(1.0))),
+                assign(
+                    symbol_o!(a),
+                    Expression::Multiply(var(symbol_o!(a)), lit(10.0)),
+                ),
+                Expression::Var(symbol_o!(a)),
+            ])),
+        };
+
+        assert_eq!(eval_lit(expr), Ok(20.0.into()));
+    }
+
+    #[test]
+    fn eval_assign_unbound() {
+        assert_eq!(
+            eval_lit(assign(symbol_o!(a), *lit(1.0))),
+            Err(EvalError::UnboundVariable(symbol_o!(a)))
+        );
+    }
+
+    #[test]
+    fn eval_assign_outlives_inner_let() {
+        // Assigning to `a` inside a `Let` of `b` changes the outer `a`.
+        let expr = Expression::Let {
+            name: symbol_o!(a),
+            value: lit(1.0),
+            body: Box::new(Expression::Block(vec![
+                Expression::Let {
+                    name: symbol_o!(b),
+                    value: lit(2.0),
+                    body: Box::new(assign(symbol_o!(a), Expression::Var(symbol_o!(b)))),
+                },
+                Expression::Var(symbol_o!(a)),
+            ])),
+        };
+
+        assert_eq!(eval_lit(expr), Ok(2.0.into()));
+    }
+
+    #[test]
+    fn eval_assign_shadowed() {
+        // Assigning to a shadowing `a` does not change the shadowed `a`.
+        let expr = Expression::Let {
+            name: symbol_o!(a),
+            value: lit(1.0),
+            body: Box::new(Expression::Block(vec![
+                Expression::Let {
+                    name: symbol_o!(a),
+                    value: lit(2.0),
+                    body: Box::new(assign(symbol_o!(a), *lit(3.0))),
+                },
+                Expression::Var(symbol_o!(a)),
+            ])),
+        };
+
+        assert_eq!(eval_lit(expr), Ok(1.0.into()));
+    }
+
+    #[test]
+    fn eval_assign_does_not_leak_from_call() {
+        // `let a = 1 in { (fn() => a = 2)(); a }`
+        let expr = Expression::Let {
+            name: symbol_o!(a),
+            value: lit(1.0),
+            body: Box::new(Expression::Block(vec![
+                Expression::Call {
+                    function: lambda(vec![], assign(symbol_o!(a), *lit(2.0))),
+                    args: vec![],
+                },
+                Expression::Var(symbol_o!(a)),
+            ])),
+        };
+
+        assert_eq!(eval_lit(expr), Ok(1.0.into()));
+    }
+
+    #[test]
+    fn eval_assign_at() {
+        // `let a = [1, 2] in let b = a in { a[0] = 3; a[2] = 4; a == [3, 2, 4] && b == [1, 2] }`
+        let expr = Expression::Let {
+            name: symbol_o!(a),
+            value: array(vec![1.0, 2.0]),
+            body: Box::new(Expression::Let {
+                name: symbol_o!(b),
+                value: var(symbol_o!(a)),
+                body: Box::new(Expression::Block(vec![
+                    Expression::AssignAt {
+                        name: symbol_o!(a),
+                        key: lit(0.0),
+                        value: lit(3.0),
+                    },
+                    Expression::AssignAt {
+                        name: symbol_o!(a),
+                        key: lit(2.0),
+                        value: lit(4.0),
+                    },
+                    Expression::LAnd(
+                        Box::new(Expression::Equal(
+                            var(symbol_o!(a)),
+                            array(vec![3.0, 2.0, 4.0]),
+                        )),
+                        Box::new(Expression::Equal(var(symbol_o!(b)), array(vec![1.0, 2.0]))),
+                    ),
+                ])),
+            }),
+        };
+
+        assert!(eval_bool(expr));
+    }
+
+    #[test]
+    fn eval_assign_at_object() {
+        let expr = Expression::Let {
+            name: symbol_o!(o),
+            value: Box::new(Expression::Literal(OwnedObject::empty().into())),
+            body: Box::new(Expression::Block(vec![
+                Expression::AssignAt {
+                    name: symbol_o!(o),
+                    key: Box::new(Expression::Literal("foo".into())),
+                    value: lit(1.0),
+                },
+                Expression::Get {
+                    target: var(symbol_o!(o)),
+                    key: Box::new(Expression::Literal("foo".into())),
+                },
+            ])),
+        };
+
+        assert_eq!(eval_lit(expr), Ok(1.0.into()));
+    }
+
+    #[test]
+    fn eval_assign_at_out_of_bounds() {
+        let expr = Expression::Let {
+            name: symbol_o!(a),
+            value: array(vec![1.0]),
+            body: Box::new(Expression::AssignAt {
+                name: symbol_o!(a),
+                key: lit(2.0),
+                value: lit(3.0),
+            }),
+        };
+
+        assert_eq!(
+            eval_lit(expr),
+            Err(EvalError::InvalidIndex {
+                index: 2.0.into(),
+                len: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn eval_get() {
+        let get = |key: f64| {
+            eval_lit(Expression::Get {
+                target: array(vec![1.0, 2.0]),
+                key: lit(key),
+            })
+        };
+
+        assert_eq!(get(1.0), Ok(2.0.into()));
+        assert_eq!(get(2.0), Ok(Value::Object(Object::empty())));
+        assert_eq!(
+            get(0.5),
+            Err(EvalError::InvalidIndex {
+                index: 0.5.into(),
+                len: 2,
+            })
+        );
+        assert_eq!(
+            get(-1.0).unwrap_err().to_string(),
+            "invalid index -1 into an array of length 2"
+        );
+    }
+
+    /// `while i < to (acc i = 0) { i + 1 }`
+    fn count_to(to: f64, limit: f64) -> Expression {
+        Expression::While {
+            acc: symbol_o!(i),
+            init: lit(0.0),
+            cond: Box::new(Expression::LessThan(var(symbol_o!(i)), lit(to))),
+            body: Box::new(Expression::Add(var(symbol_o!(i)), lit(1.0))),
+            limit: limit.into(),
+        }
+    }
+
+    #[test]
+    fn eval_while() {
+        assert_eq!(eval_lit(count_to(5.0, 100.0)), Ok(5.0.into()));
+        assert_eq!(eval_lit(count_to(5.0, 5.0)), Ok(5.0.into()));
+        assert_eq!(eval_lit(count_to(0.0, 0.0)), Ok(0.0.into()));
+    }
+
+    #[test]
+    fn eval_while_limit() {
+        let err = eval_lit(count_to(5.0, 4.0)).unwrap_err();
+
+        assert_eq!(err, EvalError::IterationLimit(4.0.into()));
+        assert_eq!(err.to_string(), "loop did not finish within 4 iterations");
+    }
+
+    /// Sums the values of `over`, breaking or continuing according to `body`.
+    fn sum(over: OwnedValue, body: Expression) -> Expression {
+        Expression::ForEach {
+            item: symbol_o!(x),
+            key: symbol_o!(k),
+            over: Box::new(Expression::Literal(over)),
+            acc: symbol_o!(sum),
+            init: lit(0.0),
+            body: Box::new(body),
+        }
+    }
+
+    fn add_x() -> Expression {
+        Expression::Add(var(symbol_o!(sum)), var(symbol_o!(x)))
+    }
+
+    #[test]
+    fn eval_for_each_array() {
+        let arr: OwnedValue = OwnedArray::from(vec![1.0.into(), 2.0.into(), 3.0.into()]).into();
+
+        assert_eq!(eval_lit(sum(arr.clone(), add_x())), Ok(6.0.into()));
+        // Keys are the indices.
+        assert_eq!(
+            eval_lit(sum(
+                arr,
+                Expression::Add(var(symbol_o!(sum)), var(symbol_o!(k)))
+            )),
+            Ok(3.0.into())
+        );
+    }
+
+    #[test]
+    fn eval_for_each_object() {
+        // Entries are visited in order of their keys.
+        let mut o = OwnedObject::empty();
+        o.set_mut("b".into(), "2".into());
+        o.set_mut("a".into(), "1".into());
+        o.set_mut("c".into(), "3".into());
+        let expr = Expression::ForEach {
+            item: symbol_o!(v),
+            key: symbol_o!(k),
+            over: Box::new(Expression::Literal(o.into())),
+            acc: symbol_o!(s),
+            init: Box::new(Expression::Literal("".into())),
+            body: Box::new(Expression::StrConcat(
+                var(symbol_o!(s)),
+                Box::new(Expression::StrConcat(var(symbol_o!(k)), var(symbol_o!(v)))),
+            )),
+        };
+
+        assert_eq!(eval_lit(expr), Ok("a1b2c3".into()));
+    }
+
+    #[test]
+    fn eval_for_each_not_a_collection() {
+        assert_eq!(
+            eval_lit(sum(1.0.into(), add_x())),
+            Err(EvalError::TypeMismatch {
+                expected: "array or object",
+                found: 1.0.into(),
+            })
+        );
+    }
+
+    #[test]
+    fn eval_break_continue() {
+        let arr: OwnedValue =
+            OwnedArray::from(vec![1.0.into(), 2.0.into(), 3.0.into(), 4.0.into()]).into();
+
+        // Skips 2.
+        let skip = Expression::Cond {
+            cond: Box::new(Expression::Equal(var(symbol_o!(x)), lit(2.0))),
+            if_true: Box::new(Expression::Continue(var(symbol_o!(sum)))),
+            if_false: Box::new(add_x()),
+            display: CondDisplay::If,
+        };
+        assert_eq!(eval_lit(sum(arr.clone(), skip)), Ok(8.0.into()));
+
+        // Stops at 3.
+        let stop = Expression::Cond {
+            cond: Box::new(Expression::Equal(var(symbol_o!(x)), lit(3.0))),
+            if_true: Box::new(Expression::Break(var(symbol_o!(sum)))),
+            if_false: Box::new(add_x()),
+            display: CondDisplay::If,
+        };
+        assert_eq!(eval_lit(sum(arr, stop)), Ok(3.0.into()));
+    }
+
+    #[test]
+    fn eval_break_outside_loop() {
+        assert_eq!(
+            eval_lit(Expression::Break(lit(1.0))),
+            Err(EvalError::BreakOutsideLoop)
+        );
+
+        // A function called from a loop can not break out of it.
+        let call = Expression::Call {
+            function: lambda(vec![], Expression::Break(lit(1.0))),
+            args: vec![],
+        };
+        assert_eq!(
+            eval_lit(sum(OwnedArray::from(vec![1.0.into()]).into(), call)),
+            Err(EvalError::BreakOutsideLoop)
+        );
+    }
+
+    #[test]
+    fn eval_break_in_loop_condition() {
+        // `for x in [1] (acc sum = 0) { while (acc i = 0) break(5) { i } }`
+        let inner = Expression::While {
+            acc: symbol_o!(i),
+            init: lit(0.0),
+            cond: Box::new(Expression::Break(lit(5.0))),
+            body: var(symbol_o!(i)),
+            limit: 10.0.into(),
+        };
+        let mut i = Interpreter::new();
+        let err = i
+            .eval(&sum(OwnedArray::from(vec![1.0.into()]).into(), inner))
+            .unwrap_err();
+
+        let mut path = NodePath::root();
+        path.push(Step::Field("body"));
+        path.push(Step::Field("cond"));
+        assert_eq!(
+            err,
+            ::Error::Eval {
+                error: EvalError::BreakOutsideLoop,
+                path: Some(path),
+            }
+        );
+
+        // The same goes for the initial value and the collection of a `ForEach`.
+        let inner = Expression::ForEach {
+            item: symbol_o!(y),
+            key: symbol_o!(j),
+            over: Box::new(Expression::Continue(lit(0.0))),
+            acc: symbol_o!(n),
+            init: Box::new(Expression::Break(lit(0.0))),
+            body: var(symbol_o!(n)),
+        };
+        assert_eq!(
+            eval_lit(sum(OwnedArray::from(vec![1.0.into()]).into(), inner)),
+            Err(EvalError::BreakOutsideLoop)
+        );
+    }
+
+    #[test]
+    fn eval_loop_does_not_mutate_outside() {
+        // `let a = 1 in { for x in [5] (acc sum = 0) { a = x; sum }; a }`
+        let expr = Expression::Let {
+            name: symbol_o!(a),
+            value: lit(1.0),
+            body: Box::new(Expression::Block(vec![
+                sum(
+                    OwnedArray::from(vec![5.0.into()]).into(),
+                    Expression::Block(vec![
+                        assign(symbol_o!(a), Expression::Var(symbol_o!(x))),
+                        Expression::Var(symbol_o!(sum)),
+                    ]),
+                ),
+                Expression::Var(symbol_o!(a)),
+            ])),
+        };
+
+        assert_eq!(eval_lit(expr), Ok(1.0.into()));
+    }
+
+    fn eval_match(
+        scrutinee: OwnedValue,
+        arms: Vec<(Pattern, Expression)>,
+    ) -> Result<Value, EvalError> {
+        eval_lit(Expression::Match {
+            scrutinee: Box::new(Expression::Literal(scrutinee)),
+            arms,
+        })
+    }
+
+    #[test]
+    fn eval_match_literals() {
+        let arms = || {
+            vec![
+                (Pattern::Literal(1.0.into()), *lit(10.0)),
+                (Pattern::Literal("a".into()), *lit(20.0)),
+                (Pattern::Literal(symbol_o!(foo;bar).into()), *lit(30.0)),
+                (Pattern::Wildcard, *lit(40.0)),
+            ]
+        };
+
+        assert_eq!(eval_match(1.0.into(), arms()), Ok(10.0.into()));
+        assert_eq!(eval_match("a".into(), arms()), Ok(20.0.into()));
+        assert_eq!(
+            eval_match(symbol_o!(foo;bar).into(), arms()),
+            Ok(30.0.into())
+        );
+        assert_eq!(eval_match(symbol_o!(foo).into(), arms()), Ok(40.0.into()));
+    }
+
+    #[test]
+    fn eval_match_cons() {
+        let arms = || {
+            vec![
+                (Pattern::EmptyArray, *lit(0.0)),
+                (
+                    Pattern::Cons {
+                        head: Box::new(Pattern::Bind(symbol_o!(h))),
+                        tail: Box::new(Pattern::EmptyArray),
+                    },
+                    Expression::Var(symbol_o!(h)),
+                ),
+                (
+                    Pattern::Cons {
+                        head: Box::new(Pattern::Wildcard),
+                        tail: Box::new(Pattern::Bind(symbol_o!(t))),
+                    },
+                    Expression::Var(symbol_o!(t)),
+                ),
+            ]
+        };
+
+        assert_eq!(
+            eval_match(OwnedArray::from(vec![]).into(), arms()),
+            Ok(0.0.into())
+        );
+        assert_eq!(
+            eval_match(OwnedArray::from(vec![5.0.into()]).into(), arms()),
+            Ok(5.0.into())
+        );
+        assert_eq!(
+            eval_match(
+                OwnedArray::from(vec![1.0.into(), 2.0.into(), 3.0.into()]).into(),
+                arms()
+            )
+            .map(OwnedValue::from),
+            Ok(OwnedArray::from(vec![2.0.into(), 3.0.into()]).into())
+        );
+    }
+
+    #[test]
+    fn eval_match_object() {
+        let pattern = Pattern::Object(
+            vec![
+                ("kind".into(), Pattern::Literal(symbol_o!(point).into())),
+                ("x".into(), Pattern::Bind(symbol_o!(x))),
+            ]
+            .into_iter()
+            .collect(),
+        );
+        let arms = || {
+            vec![
+                (pattern.clone(), Expression::Var(symbol_o!(x))),
+                (Pattern::Wildcard, Expression::Nop),
+            ]
+        };
+
+        let mut point = OwnedObject::singleton("kind".into(), symbol_o!(point).into());
+        point.set_mut("x".into(), 3.0.into());
+        point.set_mut("y".into(), 4.0.into());
+        assert_eq!(eval_match(point.into(), arms()), Ok(3.0.into()));
+
+        let other = OwnedObject::singleton("kind".into(), symbol_o!(line).into());
+        assert_eq!(
+            eval_match(other.into(), arms()),
+            Ok(Value::Object(Object::empty()))
+        );
+    }
+
+    #[test]
+    fn eval_match_no_arm() {
+        let err = eval_match(
+            "b".into(),
+            vec![(Pattern::Literal("a".into()), Expression::Nop)],
+        )
+        .unwrap_err();
+
+        assert_eq!(err, EvalError::NoMatch("b".into()));
+        assert_eq!(err.to_string(), "no arm of the match covers \"b\"");
+    }
+
+    #[test]
+    fn eval_match_bindings_scoped() {
+        // `let x = 1 in { match 2 { x => x }; x }`
+        let expr = Expression::Let {
+            name: symbol_o!(x),
+            value: lit(1.0),
+            body: Box::new(Expression::Block(vec![
+                Expression::Match {
+                    scrutinee: lit(2.0),
+                    arms: vec![(Pattern::Bind(symbol_o!(x)), Expression::Var(symbol_o!(x)))],
+                },
+                Expression::Var(symbol_o!(x)),
+            ])),
+        };
+
+        assert_eq!(eval_lit(expr), Ok(1.0.into()));
+    }
+
+    fn module(
+        name: OwnedSymbol,
+        imports: Vec<OwnedSymbol>,
+        exports: Vec<(&str, Expression)>,
+    ) -> Module {
+        Module {
+            name,
+            imports,
+            exports: exports
+                .into_iter()
+                .map(|(n, e)| (n.to_string(), e))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn eval_program_exports_in_order() {
+        let program = Program {
+            modules: vec![module(
+                symbol_o!(app),
+                vec![],
+                vec![
+                    ("a", *lit(1.0)),
+                    ("b", Expression::Add(var(symbol_o!(app;a)), lit(1.0))),
+                ],
+            )],
+        };
+
+        assert_eq!(
+            Interpreter::new().eval_program(&program, &symbol_o!(app;b)),
+            Ok(2.0.into())
+        );
+        assert_eq!(
+            Interpreter::new()
+                .eval_program(&program, &symbol_o!(app;c))
+                .map_err(without_path),
+            Err(EvalError::UnboundVariable(symbol_o!(app;c)))
+        );
+    }
+
+    #[test]
+    fn eval_program_imports_not_transitive() {
+        // app imports lib, which imports base, but base is not in scope in app.
+        let program = Program {
+            modules: vec![
+                module(symbol_o!(base), vec![], vec![("x", *lit(1.0))]),
+                module(
+                    symbol_o!(lib),
+                    vec![symbol_o!(base)],
+                    vec![("y", Expression::Var(symbol_o!(base;x)))],
+                ),
+                module(
+                    symbol_o!(app),
+                    vec![symbol_o!(lib)],
+                    vec![
+                        ("y", Expression::Var(symbol_o!(lib;y))),
+                        ("x", Expression::Var(symbol_o!(base;x))),
+                    ],
+                ),
+            ],
+        };
+
+        assert_eq!(
+            Interpreter::new()
+                .eval_program(&program, &symbol_o!(app;y))
+                .map_err(without_path),
+            Err(EvalError::UnboundVariable(symbol_o!(base;x)))
+        );
+        assert_eq!(
+            Interpreter::new().eval_program(&program, &symbol_o!(lib;y)),
+            Ok(1.0.into())
+        );
+    }
+
+    #[test]
+    fn eval_program_invalid_export_name() {
+        let program = Program {
+            modules: vec![module(
+                symbol_o!(app),
+                vec![],
+                vec![("a b", Expression::Nop)],
+            )],
+        };
+
+        assert_eq!(
+            Interpreter::new().eval_program(&program, &symbol_o!(app;main)),
+            Err(::Error::InvalidSymbol {
+                name: "a b".to_string(),
+                error: SymbolError::InvalidChar(' '),
+            })
+        );
+    }
+
+    #[test]
+    fn eval_program_error_in_export() {
+        let program = Program {
+            modules: vec![module(
+                symbol_o!(app),
+                vec![],
+                vec![(
+                    "main",
+                    Expression::Sqrt(Box::new(Expression::Literal("one".into()))),
+                )],
+            )],
+        };
+        let err = Interpreter::new()
+            .eval_program(&program, &symbol_o!(app;main))
+            .unwrap_err();
+
+        let mut path = NodePath::root();
+        path.push(Step::Field("operand"));
+        assert_eq!(
+            err,
+            ::Error::Export {
+                export: symbol_o!(app;main),
+                error: Box::new(::Error::Eval {
+                    error: EvalError::TypeMismatch {
+                        expected: "number",
+                        found: "one".into(),
+                    },
+                    path: Some(path),
+                }),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "in 'app::main, root.operand: expected a number but found \"one\""
+        );
+    }
+
+    #[test]
+    fn eval_program_import_cycle() {
+        let program = Program {
+            modules: vec![
+                module(
+                    symbol_o!(a),
+                    vec![symbol_o!(b)],
+                    vec![("main", Expression::Nop)],
+                ),
+                module(symbol_o!(b), vec![symbol_o!(a)], vec![]),
+            ],
+        };
+        let err = Interpreter::new()
+            .eval_program(&program, &symbol_o!(a;main))
+            .unwrap_err();
+
+        assert_eq!(
+            err,
+            EvalError::Module(ModuleError::ImportCycle(vec![
+                symbol_o!(a),
+                symbol_o!(b),
+                symbol_o!(a),
+            ]))
+            .into()
+        );
+        assert_eq!(err.to_string(), "import cycle: 'a -> 'b -> 'a");
+    }
+
+    #[test]
+    fn function_owned_keeps_local_captures() {
+        let mut i = Interpreter::new();
+        let closure = i
+            .eval(&Expression::Let {
+                name: symbol_o!(s),
+                value: Box::new(Expression::Literal(symbol_o!(a;b).into())),
+                body: lambda(vec![], Expression::Var(symbol_o!(s))),
+            })
+            .unwrap();
+
+        let owned = match OwnedValue::from(closure) {
+            OwnedValue::Function(f) => f,
+            _ => unreachable!(),
+        };
+
+        assert_eq!(
+            owned.captures(),
+            &[(symbol_o!(s), symbol_o!(a;b).into())][..]
+        );
+    }
+
+    #[test]
+    fn eval_cond_null_is_false() {
+        let mut i = Interpreter::new();
+        let expr = Expression::Cond {
+            cond: Box::new(Expression::Nop),
+            if_true: lit(1.0),
+            if_false: lit(2.0),
+            display: CondDisplay::If,
+        };
+
+        assert_eq!(i.eval(&expr).unwrap(), Value::from(2.0));
+    }
+
+    fn path(steps: &[Step]) -> Option<NodePath> {
+        let mut path = NodePath::root();
+        for step in steps {
+            path.push(*step);
+        }
+        Some(path)
+    }
+
+    #[test]
+    fn error_path() {
+        let expr = Expression::Block(vec![
+            Expression::Nop,
+            Expression::Sqrt(Box::new(Expression::Subtract(lit(0.0), lit(1.0)))),
+        ]);
+
+        let err = Interpreter::new().eval(&expr).unwrap_err();
+
+        assert_eq!(
+            err,
+            ::Error::Eval {
+                error: EvalError::NotANumber {
+                    operation: "square root",
+                    operands: vec![(-1.0).into()],
+                },
+                path: path(&[Step::Index("exprs", 1)]),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "root.exprs[1]: square root of -1 is not a number"
+        );
+    }
+
+    #[test]
+    fn error_path_type_mismatch_at_operand() {
+        let expr = Expression::LNot(Box::new(Expression::Add(
+            lit(1.0),
+            Box::new(Expression::Literal("a".into())),
+        )));
+
+        assert_eq!(
+            Interpreter::new().eval(&expr),
+            Err(::Error::Eval {
+                error: EvalError::TypeMismatch {
+                    expected: "number",
+                    found: "a".into(),
+                },
+                path: path(&[Step::Field("operand"), Step::Field("rhs")]),
+            })
+        );
+    }
+
+    #[test]
+    fn error_path_in_function() {
+        let function = Expression::Lambda {
+            params: vec![symbol_o!(x)],
+            body: Box::new(Expression::Log(Box::new(Expression::Var(symbol_o!(x))))),
+        };
+        let expr = Expression::Add(
+            lit(1.0),
+            Box::new(Expression::Call {
+                function: Box::new(function),
+                args: vec![*lit(-1.0)],
+            }),
+        );
+
+        let err = Interpreter::new().eval(&expr).unwrap_err();
+
+        assert_eq!(
+            err,
+            ::Error::Eval {
+                error: EvalError::InFunction {
+                    error: Box::new(EvalError::NotANumber {
+                        operation: "logarithm",
+                        operands: vec![(-1.0).into()],
+                    }),
+                    path: path(&[]).unwrap(),
+                },
+                path: path(&[Step::Field("rhs")]),
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "root.rhs: in a function, root: logarithm of -1 is not a number"
+        );
+    }
+
+    #[test]
+    fn error_path_in_nested_function() {
+        let inner = Expression::Lambda {
+            params: vec![],
+            body: Box::new(Expression::Sqrt(Box::new(Expression::Literal("a".into())))),
+        };
+        let outer = Expression::Lambda {
+            params: vec![],
+            body: Box::new(Expression::Block(vec![Expression::Call {
+                function: Box::new(inner),
+                args: vec![],
+            }])),
+        };
+        let expr = Expression::Call {
+            function: Box::new(outer),
+            args: vec![],
+        };
+
+        assert_eq!(
+            Interpreter::new().eval(&expr),
+            Err(::Error::Eval {
+                error: EvalError::InFunction {
+                    error: Box::new(EvalError::InFunction {
+                        error: Box::new(EvalError::TypeMismatch {
+                            expected: "number",
+                            found: "a".into(),
+                        }),
+                        path: path(&[Step::Field("operand")]).unwrap(),
+                    }),
+                    path: path(&[Step::Index("exprs", 0)]).unwrap(),
+                },
+                path: path(&[]),
+            })
+        );
+    }
+}
@@ -32,6 +32,9 @@ mod macros;
 
 pub mod value;
 pub mod ast;
+pub mod eval;
+pub mod typeck;
+pub mod error;
 
 #[cfg(test)]
 mod test;
@@ -39,6 +42,8 @@ mod test;
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 
+pub use error::Error;
+
 use value::Symbol;
 
 /// The central value for Dermis interpreter.
@@ -47,6 +52,8 @@ use value::Symbol;
 #[derive(Debug)]
 pub struct Interpreter {
     symbol_table: Arc<RwLock<SymbolTable>>,
+    /// The id of the next anonymous symbol, see [`Interpreter::gensym`](Interpreter::gensym).
+    next_anonymous: u64,
 }
 
 impl Interpreter {
@@ -62,6 +69,7 @@ impl Interpreter {
     pub fn new() -> Interpreter {
         Interpreter {
             symbol_table: Arc::new(RwLock::new(Default::default())),
+            next_anonymous: 0,
         }
     }
 }
@@ -0,0 +1,852 @@
+/*
+ * Dermis is an interpreter for a pure, statically typed, imperitive language designed to be edited with a custom IDE.
+ * Copyright (C) 2018 Amelorate
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! A static type checker for the language AST.
+//!
+//! The checker infers the type of every node in an expression, and reports each node that is
+//! given an operand of the wrong type, so that mistakes are found before the expression is
+//! evaluated. Values whose type can not be known until they are evaluated, such as the
+//! parameters of a function, have the type [`Type::Any`](::ast::types::Type::Any) and are
+//! checked at runtime instead.
+
+use im::HashMap;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::mem;
+
+use ast::expression::Expression;
+use ast::path::{NodePath, Step};
+use ast::pattern::Pattern;
+use ast::types::Type;
+use value::{OwnedSymbol, OwnedValue};
+
+/// A problem found by the type checker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeError {
+    /// A node was given an operand of the wrong type.
+    Mismatch { expected: Type, found: Type },
+    /// An array or object was expected, to be indexed or iterated over.
+    NotCollection(Type),
+    /// A variable was used that has not been bound by an enclosing `Let`.
+    UnboundVariable(OwnedSymbol),
+    /// A `Break` or `Continue` was used outside of a loop.
+    BreakOutsideLoop,
+}
+
+impl Display for TypeError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            TypeError::Mismatch { expected, found } => {
+                write!(f, "expected {} but found {}", expected, found)
+            }
+            TypeError::NotCollection(found) => {
+                write!(f, "expected an array or object but found {}", found)
+            }
+            TypeError::UnboundVariable(name) => write!(f, "unbound variable {}", name),
+            TypeError::BreakOutsideLoop => write!(f, "break or continue outside of a loop"),
+        }
+    }
+}
+
+impl Error for TypeError {}
+
+/// A [`TypeError`](TypeError), along with the node it was found at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub path: NodePath,
+    pub error: TypeError,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.error)
+    }
+}
+
+impl Error for Diagnostic {}
+
+/// Checks the types of an expression, returning the type of the value it results in.
+///
+/// # Errors
+/// Every problem found in the expression, in the order the nodes would be evaluated.
+///
+/// # Example
+/// ```
+/// use dermis::ast::expression::{CondDisplay, Expression};
+/// use dermis::ast::path::{NodePath, Step};
+/// use dermis::ast::types::Type;
+/// use dermis::typeck::{self, TypeError};
+///
+/// let expr = Expression::Cond {
+///     cond: Box::new(Expression::Literal(1.0.into())),
+///     if_true: Box::new(Expression::Literal("yes".into())),
+///     if_false: Box::new(Expression::Literal("no".into())),
+///     display: CondDisplay::If,
+/// };
+///
+/// let errors = typeck::check(&expr).unwrap_err();
+///
+/// let mut path = NodePath::root();
+/// path.push(Step::Field("cond"));
+/// assert_eq!(errors[0].path, path);
+/// assert_eq!(
+///     errors[0].error,
+///     TypeError::Mismatch {
+///         expected: Type::Bool,
+///         found: Type::Number,
+///     }
+/// );
+/// assert_eq!(errors[0].to_string(), "root.cond: expected bool but found number");
+/// ```
+pub fn check(expr: &Expression) -> Result<Type, Vec<Diagnostic>> {
+    let mut checker = Checker {
+        path: NodePath::root(),
+        loops: vec![],
+        diagnostics: vec![],
+    };
+    let ty = checker.infer(expr, &Scope::new());
+    if checker.diagnostics.is_empty() {
+        Ok(ty)
+    } else {
+        Err(checker.diagnostics)
+    }
+}
+
+/// The type of the field `key` of an object type, if `key` is a constant.
+fn field_type<'a>(fields: &'a BTreeMap<OwnedValue, Type>, key: &Expression) -> Option<&'a Type> {
+    match key {
+        Expression::Literal(key) => fields.get(key),
+        _ => None,
+    }
+}
+
+/// The types of the variables in scope.
+type Scope = HashMap<OwnedSymbol, Type>;
+
+struct Checker {
+    /// The path to the node being checked.
+    path: NodePath,
+    /// The accumulator types of the loops enclosing the node being checked, innermost last.
+    loops: Vec<Type>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Checker {
+    fn report(&mut self, error: TypeError) {
+        self.diagnostics.push(Diagnostic {
+            path: self.path.clone(),
+            error,
+        })
+    }
+
+    /// Reports an error at the child of the current node at `step`.
+    fn report_at(&mut self, step: Step, error: TypeError) {
+        self.path.push(step);
+        self.report(error);
+        self.path.pop();
+    }
+
+    /// Infers the type of the child of the current node at `step`.
+    fn child(&mut self, step: Step, expr: &Expression, scope: &Scope) -> Type {
+        self.path.push(step);
+        let ty = self.infer(expr, scope);
+        self.path.pop();
+        ty
+    }
+
+    /// Infers the type of a child, reporting it if it can not be used as an `expected`.
+    fn expect(&mut self, step: Step, expr: &Expression, scope: &Scope, expected: &Type) -> Type {
+        self.path.push(step);
+        let ty = self.infer(expr, scope);
+        if !ty.is_subtype_of(expected) {
+            self.report(TypeError::Mismatch {
+                expected: expected.clone(),
+                found: ty.clone(),
+            });
+        }
+        self.path.pop();
+        ty
+    }
+
+    /// Checks both operands of a binary operator against `expected`.
+    fn operands(&mut self, lhs: &Expression, rhs: &Expression, scope: &Scope, expected: &Type) {
+        self.expect(Step::Field("lhs"), lhs, scope, expected);
+        self.expect(Step::Field("rhs"), rhs, scope, expected);
+    }
+
+    /// Checks the body of a loop, whose accumulator has the type `acc`.
+    fn loop_body(&mut self, body: &Expression, scope: &Scope, acc: &Type) {
+        self.loops.push(acc.clone());
+        self.expect(Step::Field("body"), body, scope, acc);
+        self.loops.pop();
+    }
+
+    fn infer(&mut self, expr: &Expression, scope: &Scope) -> Type {
+        use ast::expression::Expression::*;
+        match expr {
+            Nop => Type::object(),
+            Literal(val) => Type::of_value(val),
+            IdeOption { id, .. } => self.child(Step::Field("id"), id, scope),
+            Typed { expr, ty } => {
+                self.expect(Step::Field("expr"), expr, scope, ty);
+                ty.clone()
+            }
+            Cond {
+                cond,
+                if_true,
+                if_false,
+                ..
+            } => {
+                self.expect(Step::Field("cond"), cond, scope, &Type::Bool);
+                let if_true = self.child(Step::Field("if_true"), if_true, scope);
+                let if_false = self.child(Step::Field("if_false"), if_false, scope);
+                if_true.join(&if_false)
+            }
+            LAnd(lhs, rhs) | LOr(lhs, rhs) | LXor(lhs, rhs) => {
+                self.operands(lhs, rhs, scope, &Type::Bool);
+                Type::Bool
+            }
+            LNot(e) => {
+                self.expect(Step::Field("operand"), e, scope, &Type::Bool);
+                Type::Bool
+            }
+            NotNull(e) | IsNull(e) => {
+                self.child(Step::Field("operand"), e, scope);
+                Type::Bool
+            }
+            FloatingEqual { lhs, rhs, .. } | FloatingNE { lhs, rhs, .. } => {
+                self.operands(lhs, rhs, scope, &Type::Number);
+                Type::Bool
+            }
+            Equal(lhs, rhs)
+            | NotEqual(lhs, rhs)
+            | LessThan(lhs, rhs)
+            | GreaterThan(lhs, rhs)
+            | LesserOrEqual(lhs, rhs)
+            | GreaterOrEqual(lhs, rhs) => {
+                self.operands(lhs, rhs, scope, &Type::Any);
+                Type::Bool
+            }
+            StrConcat(lhs, rhs) => {
+                self.operands(lhs, rhs, scope, &Type::String);
+                Type::String
+            }
+            Add(lhs, rhs)
+            | Subtract(lhs, rhs)
+            | Multiply(lhs, rhs)
+            | Divide(lhs, rhs)
+            | IntDivide(lhs, rhs)
+            | Exponent(lhs, rhs) => {
+                self.operands(lhs, rhs, scope, &Type::Number);
+                Type::Number
+            }
+            Sqrt(e) | Log(e) => {
+                self.expect(Step::Field("operand"), e, scope, &Type::Number);
+                Type::Number
+            }
+            Let { name, value, body } => {
+                let ty = self.child(Step::Field("value"), value, scope);
+                self.child(Step::Field("body"), body, &scope.insert(name.clone(), ty))
+            }
+            Var(name) => match scope.get(name) {
+                Some(ty) => (*ty).clone(),
+                None => {
+                    self.report(TypeError::UnboundVariable(name.clone()));
+                    Type::Any
+                }
+            },
+            Lambda { params, body } => {
+                let inner = params
+                    .iter()
+                    .fold(scope.clone(), |s, p| s.insert(p.clone(), Type::Any));
+                // The body of a function is not part of the loops around it.
+                let loops = mem::take(&mut self.loops);
+                let ret = self.child(Step::Field("body"), body, &inner);
+                self.loops = loops;
+                Type::Function {
+                    params: vec![Type::Any; params.len()],
+                    ret: Box::new(ret),
+                }
+            }
+            Call { function, args } => {
+                let expected = Type::Function {
+                    params: vec![Type::Any; args.len()],
+                    ret: Box::new(Type::Any),
+                };
+                match self.expect(Step::Field("function"), function, scope, &expected) {
+                    Type::Function {
+                        ref params,
+                        ref ret,
+                    } if params.len() == args.len() => {
+                        for (i, (arg, param)) in args.iter().zip(params).enumerate() {
+                            self.expect(Step::Index("args", i), arg, scope, param);
+                        }
+                        (**ret).clone()
+                    }
+                    _ => {
+                        for (i, arg) in args.iter().enumerate() {
+                            self.child(Step::Index("args", i), arg, scope);
+                        }
+                        Type::Any
+                    }
+                }
+            }
+            Block(exprs) => {
+                let mut ty = Type::object();
+                for (i, e) in exprs.iter().enumerate() {
+                    ty = self.child(Step::Index("exprs", i), e, scope);
+                }
+                ty
+            }
+            Assign { name, value } => {
+                match scope.get(name) {
+                    Some(ty) => {
+                        self.expect(Step::Field("value"), value, scope, &ty);
+                    }
+                    None => {
+                        self.report(TypeError::UnboundVariable(name.clone()));
+                        self.child(Step::Field("value"), value, scope);
+                    }
+                }
+                Type::object()
+            }
+            AssignAt { name, key, value } => {
+                match scope.get(name).map(|ty| (*ty).clone()) {
+                    Some(Type::Array(elem)) => {
+                        self.expect(Step::Field("key"), key, scope, &Type::Number);
+                        self.expect(Step::Field("value"), value, scope, &elem);
+                    }
+                    Some(Type::Object(ref fields)) if field_type(fields, key).is_some() => {
+                        self.child(Step::Field("key"), key, scope);
+                        let field = field_type(fields, key).unwrap();
+                        self.expect(Step::Field("value"), value, scope, field);
+                    }
+                    ty => {
+                        match ty {
+                            Some(Type::Object(_)) | Some(Type::Any) => {}
+                            Some(ty) => self.report(TypeError::NotCollection(ty)),
+                            None => self.report(TypeError::UnboundVariable(name.clone())),
+                        }
+                        self.child(Step::Field("key"), key, scope);
+                        self.child(Step::Field("value"), value, scope);
+                    }
+                }
+                Type::object()
+            }
+            Get { target, key } => match self.child(Step::Field("target"), target, scope) {
+                // An index past the end of the array results in null, which is not tracked.
+                Type::Array(elem) => {
+                    self.expect(Step::Field("key"), key, scope, &Type::Number);
+                    *elem
+                }
+                Type::Object(fields) => {
+                    self.child(Step::Field("key"), key, scope);
+                    field_type(&fields, key).cloned().unwrap_or(Type::Any)
+                }
+                ty => {
+                    if ty != Type::Any {
+                        self.report_at(Step::Field("target"), TypeError::NotCollection(ty));
+                    }
+                    self.child(Step::Field("key"), key, scope);
+                    Type::Any
+                }
+            },
+            While {
+                acc,
+                init,
+                cond,
+                body,
+                ..
+            } => {
+                let ty = self.child(Step::Field("init"), init, scope);
+                let inner = scope.insert(acc.clone(), ty.clone());
+                self.expect(Step::Field("cond"), cond, &inner, &Type::Bool);
+                self.loop_body(body, &inner, &ty);
+                ty
+            }
+            ForEach {
+                item,
+                key,
+                over,
+                acc,
+                init,
+                body,
+            } => {
+                let (key_ty, item_ty) = match self.child(Step::Field("over"), over, scope) {
+                    Type::Array(elem) => (Type::Number, *elem),
+                    Type::Object(_) | Type::Any => (Type::Any, Type::Any),
+                    ty => {
+                        self.report_at(Step::Field("over"), TypeError::NotCollection(ty));
+                        (Type::Any, Type::Any)
+                    }
+                };
+                let ty = self.child(Step::Field("init"), init, scope);
+                let inner = scope
+                    .insert(acc.clone(), ty.clone())
+                    .insert(key.clone(), key_ty)
+                    .insert(item.clone(), item_ty);
+                self.loop_body(body, &inner, &ty);
+                ty
+            }
+            Break(e) | Continue(e) => {
+                match self.loops.last().cloned() {
+                    Some(acc) => {
+                        self.expect(Step::Field("operand"), e, scope, &acc);
+                    }
+                    None => {
+                        self.report(TypeError::BreakOutsideLoop);
+                        self.child(Step::Field("operand"), e, scope);
+                    }
+                }
+                Type::Any
+            }
+            Match { scrutinee, arms } => {
+                let ty = self.child(Step::Field("scrutinee"), scrutinee, scope);
+                let mut result: Option<Type> = None;
+                for (i, (pattern, body)) in arms.iter().enumerate() {
+                    let mut inner = scope.clone();
+                    self.path.push(Step::Index("arms", i));
+                    self.pattern(pattern, &ty, &mut inner);
+                    let body = self.infer(body, &inner);
+                    self.path.pop();
+                    result = Some(match result {
+                        Some(result) => result.join(&body),
+                        None => body,
+                    });
+                }
+                result.unwrap_or(Type::Any)
+            }
+        }
+    }
+
+    /// Checks that `pattern` can match a value of type `ty`, adding the variables it binds to
+    /// `scope`.
+    ///
+    /// Problems with the pattern are reported at the body of it's arm.
+    fn pattern(&mut self, pattern: &Pattern, ty: &Type, scope: &mut Scope) {
+        match pattern {
+            Pattern::Wildcard => {}
+            Pattern::Bind(name) => scope.insert_mut(name.clone(), ty.clone()),
+            Pattern::Literal(val) => {
+                let found = Type::of_value(val);
+                if !found.is_subtype_of(ty) && !ty.is_subtype_of(&found) {
+                    self.report(TypeError::Mismatch {
+                        expected: ty.clone(),
+                        found,
+                    });
+                }
+            }
+            Pattern::EmptyArray | Pattern::Cons { .. } => {
+                let elem = match ty {
+                    Type::Array(elem) => (**elem).clone(),
+                    Type::Any => Type::Any,
+                    found => {
+                        self.report(TypeError::Mismatch {
+                            expected: Type::Array(Box::new(Type::Any)),
+                            found: found.clone(),
+                        });
+                        Type::Any
+                    }
+                };
+                if let Pattern::Cons { head, tail } = pattern {
+                    self.pattern(head, &elem, scope);
+                    self.pattern(tail, &Type::Array(Box::new(elem)), scope);
+                }
+            }
+            Pattern::Object(fields) => {
+                let types = match ty {
+                    Type::Object(types) => types.clone(),
+                    Type::Any => BTreeMap::new(),
+                    found => {
+                        self.report(TypeError::Mismatch {
+                            expected: Type::object(),
+                            found: found.clone(),
+                        });
+                        BTreeMap::new()
+                    }
+                };
+                for (k, p) in fields {
+                    self.pattern(p, types.get(k).unwrap_or(&Type::Any), scope);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ast::expression::CondDisplay;
+    use value::{OwnedArray, OwnedObject};
+
+    fn lit<V: Into<OwnedValue>>(val: V) -> Box<Expression> {
+        Box::new(Expression::Literal(val.into()))
+    }
+
+    fn var(name: OwnedSymbol) -> Box<Expression> {
+        Box::new(Expression::Var(name))
+    }
+
+    fn path(steps: &[Step]) -> NodePath {
+        let mut path = NodePath::root();
+        for step in steps {
+            path.push(*step);
+        }
+        path
+    }
+
+    fn mismatch(steps: &[Step], expected: Type, found: Type) -> Diagnostic {
+        Diagnostic {
+            path: path(steps),
+            error: TypeError::Mismatch { expected, found },
+        }
+    }
+
+    #[test]
+    fn check_operators() {
+        assert_eq!(
+            check(&Expression::Add(lit(1.0), lit(2.0))),
+            Ok(Type::Number)
+        );
+        assert_eq!(
+            check(&Expression::Equal(lit(1.0), lit("a"))),
+            Ok(Type::Bool)
+        );
+        assert_eq!(
+            check(&Expression::LAnd(lit(true), lit(false))),
+            Ok(Type::Bool)
+        );
+        assert_eq!(
+            check(&Expression::StrConcat(lit("a"), lit("b"))),
+            Ok(Type::String)
+        );
+        assert_eq!(check(&Expression::Nop), Ok(Type::object()));
+    }
+
+    #[test]
+    fn check_operand_mismatch() {
+        let expr = Expression::Multiply(Box::new(Expression::Add(lit(1.0), lit("a"))), lit(true));
+
+        assert_eq!(
+            check(&expr),
+            Err(vec![
+                mismatch(
+                    &[Step::Field("lhs"), Step::Field("rhs")],
+                    Type::Number,
+                    Type::String
+                ),
+                mismatch(&[Step::Field("rhs")], Type::Number, Type::Bool),
+            ])
+        );
+    }
+
+    #[test]
+    fn check_cond() {
+        let cond = |cond, if_false| Expression::Cond {
+            cond,
+            if_true: lit(true),
+            if_false,
+            display: CondDisplay::Ternary,
+        };
+
+        assert_eq!(check(&cond(lit(true), lit(symbol_o!(a)))), Ok(Type::Symbol));
+        assert_eq!(check(&cond(lit(true), lit(1.0))), Ok(Type::Any));
+        assert_eq!(
+            check(&cond(lit(symbol_o!(a)), lit(false))),
+            Err(vec![mismatch(
+                &[Step::Field("cond")],
+                Type::Bool,
+                Type::Symbol
+            )])
+        );
+    }
+
+    #[test]
+    fn check_variables() {
+        let expr = Expression::Let {
+            name: symbol_o!(a),
+            value: lit("a"),
+            body: Box::new(Expression::Block(vec![
+                Expression::Assign {
+                    name: symbol_o!(a),
+                    value: lit(1.0),
+                },
+                Expression::Var(symbol_o!(b)),
+            ])),
+        };
+
+        assert_eq!(
+            check(&expr),
+            Err(vec![
+                mismatch(
+                    &[
+                        Step::Field("body"),
+                        Step::Index("exprs", 0),
+                        Step::Field("value"),
+                    ],
+                    Type::String,
+                    Type::Number
+                ),
+                Diagnostic {
+                    path: path(&[Step::Field("body"), Step::Index("exprs", 1)]),
+                    error: TypeError::UnboundVariable(symbol_o!(b)),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn check_functions() {
+        let add_one = Expression::Lambda {
+            params: vec![symbol_o!(x)],
+            body: Box::new(Expression::Add(var(symbol_o!(x)), lit(1.0))),
+        };
+        let call = |args| Expression::Call {
+            function: Box::new(add_one.clone()),
+            args,
+        };
+
+        assert_eq!(check(&call(vec![*lit(1.0)])), Ok(Type::Number));
+        assert_eq!(
+            check(&call(vec![])),
+            Err(vec![mismatch(
+                &[Step::Field("function")],
+                Type::Function {
+                    params: vec![],
+                    ret: Box::new(Type::Any),
+                },
+                Type::Function {
+                    params: vec![Type::Any],
+                    ret: Box::new(Type::Number),
+                }
+            )])
+        );
+        assert_eq!(
+            check(&Expression::Call {
+                function: lit(1.0),
+                args: vec![],
+            })
+            .unwrap_err()[0]
+                .to_string(),
+            "root.function: expected fn() -> any but found number"
+        );
+    }
+
+    #[test]
+    fn check_collections() {
+        let arr = OwnedArray::from(vec![1.0.into(), 2.0.into()]);
+        let get = Expression::Get {
+            target: lit(arr.clone()),
+            key: lit(0.0),
+        };
+        assert_eq!(check(&get), Ok(Type::Number));
+
+        let sum = Expression::ForEach {
+            item: symbol_o!(x),
+            key: symbol_o!(k),
+            over: lit(arr),
+            acc: symbol_o!(sum),
+            init: lit(0.0),
+            body: Box::new(Expression::Add(var(symbol_o!(sum)), var(symbol_o!(x)))),
+        };
+        assert_eq!(check(&sum), Ok(Type::Number));
+
+        let get = Expression::Get {
+            target: lit("abc"),
+            key: lit(0.0),
+        };
+        assert_eq!(
+            check(&get),
+            Err(vec![Diagnostic {
+                path: path(&[Step::Field("target")]),
+                error: TypeError::NotCollection(Type::String),
+            }])
+        );
+    }
+
+    #[test]
+    fn check_loops() {
+        let count = |body| Expression::While {
+            acc: symbol_o!(i),
+            init: lit(0.0),
+            cond: Box::new(Expression::LessThan(var(symbol_o!(i)), lit(10.0))),
+            body,
+            limit: 100.0.into(),
+        };
+
+        assert_eq!(
+            check(&count(Box::new(Expression::Add(
+                var(symbol_o!(i)),
+                lit(1.0)
+            )))),
+            Ok(Type::Number)
+        );
+        assert_eq!(
+            check(&count(Box::new(Expression::Break(lit("done"))))),
+            Err(vec![mismatch(
+                &[Step::Field("body"), Step::Field("operand")],
+                Type::Number,
+                Type::String
+            )])
+        );
+        assert_eq!(
+            check(&count(Box::new(Expression::Call {
+                function: Box::new(Expression::Lambda {
+                    params: vec![],
+                    body: Box::new(Expression::Continue(lit(1.0))),
+                }),
+                args: vec![],
+            }))),
+            Err(vec![Diagnostic {
+                path: path(&[
+                    Step::Field("body"),
+                    Step::Field("function"),
+                    Step::Field("body"),
+                ]),
+                error: TypeError::BreakOutsideLoop,
+            }])
+        );
+    }
+
+    #[test]
+    fn check_typed() {
+        // `let inc: fn(number) -> number = fn(x) => x + 1 in inc("a")`
+        let expr = Expression::Let {
+            name: symbol_o!(inc),
+            value: Box::new(Expression::Typed {
+                expr: Box::new(Expression::Lambda {
+                    params: vec![symbol_o!(x)],
+                    body: Box::new(Expression::Add(var(symbol_o!(x)), lit(1.0))),
+                }),
+                ty: Type::Function {
+                    params: vec![Type::Number],
+                    ret: Box::new(Type::Number),
+                },
+            }),
+            body: Box::new(Expression::Call {
+                function: var(symbol_o!(inc)),
+                args: vec![*lit("a")],
+            }),
+        };
+
+        assert_eq!(
+            check(&expr),
+            Err(vec![mismatch(
+                &[Step::Field("body"), Step::Index("args", 0)],
+                Type::Number,
+                Type::String
+            )])
+        );
+        assert_eq!(
+            check(&Expression::Typed {
+                expr: lit(1.0),
+                ty: Type::Bool,
+            }),
+            Err(vec![mismatch(
+                &[Step::Field("expr")],
+                Type::Bool,
+                Type::Number
+            )])
+        );
+    }
+
+    #[test]
+    fn check_object_fields() {
+        let point = Expression::Typed {
+            expr: lit(OwnedObject::singleton("x".into(), 1.0.into())),
+            ty: Type::Object(vec![("x".into(), Type::Number)].into_iter().collect()),
+        };
+        let get = Expression::Get {
+            target: Box::new(point.clone()),
+            key: lit("x"),
+        };
+        assert_eq!(check(&get), Ok(Type::Number));
+
+        let set = Expression::Let {
+            name: symbol_o!(p),
+            value: Box::new(point),
+            body: Box::new(Expression::AssignAt {
+                name: symbol_o!(p),
+                key: lit("x"),
+                value: lit("a"),
+            }),
+        };
+        assert_eq!(
+            check(&set),
+            Err(vec![mismatch(
+                &[Step::Field("body"), Step::Field("value")],
+                Type::Number,
+                Type::String
+            )])
+        );
+    }
+
+    #[test]
+    fn check_match() {
+        let arr = OwnedArray::from(vec![1.0.into(), 2.0.into()]);
+        let head = Expression::Match {
+            scrutinee: lit(arr.clone()),
+            arms: vec![
+                (
+                    Pattern::Cons {
+                        head: Box::new(Pattern::Bind(symbol_o!(h))),
+                        tail: Box::new(Pattern::Wildcard),
+                    },
+                    Expression::Var(symbol_o!(h)),
+                ),
+                (Pattern::EmptyArray, *lit(0.0)),
+            ],
+        };
+        assert_eq!(check(&head), Ok(Type::Number));
+
+        let wrong = Expression::Match {
+            scrutinee: lit(arr),
+            arms: vec![
+                (Pattern::Literal("a".into()), Expression::Nop),
+                (Pattern::Wildcard, Expression::Nop),
+            ],
+        };
+        assert_eq!(
+            check(&wrong),
+            Err(vec![mismatch(
+                &[Step::Index("arms", 0)],
+                Type::Array(Box::new(Type::Number)),
+                Type::String
+            )])
+        );
+    }
+
+    #[test]
+    fn diagnostic_path_resolves() {
+        let expr = Expression::Sqrt(Box::new(Expression::Block(vec![
+            Expression::Nop,
+            Expression::Literal("a".into()),
+        ])));
+        let errors = check(&expr).unwrap_err();
+
+        assert_eq!(
+            errors[0].path.resolve(&expr),
+            Some(&Expression::Block(vec![
+                Expression::Nop,
+                Expression::Literal("a".into()),
+            ]))
+        );
+    }
+}
@@ -0,0 +1,95 @@
+/*
+ * Dermis is an interpreter for a pure, statically typed, imperitive language designed to be edited with a custom IDE.
+ * Copyright (C) 2018 Amelorate
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+
+use ast::expression::Expression;
+use eval::Env;
+use value::{OwnedFunction, OwnedSymbol, Value};
+use Interpreter;
+
+/// A function, created by evaluating an
+/// [`Expression::Lambda`](::ast::expression::Expression::Lambda).
+///
+/// A function is a closure: it captures the variables in scope where it was created, and they
+/// stay available to its body after that scope has ended.
+///
+/// For a serializable version of this struct, see [`OwnedFunction`](::value::OwnedFunction).
+#[derive(Ord, PartialOrd, PartialEq, Eq, Hash, Debug, Clone)]
+pub struct Function {
+    pub(crate) params: Vec<OwnedSymbol>,
+    pub(crate) body: Arc<Expression>,
+    pub(crate) env: Env,
+}
+
+impl Function {
+    /// Creates a function that, when called, evaluates `body` in `env` with `params` bound to
+    /// the arguments.
+    pub fn new(params: Vec<OwnedSymbol>, body: Arc<Expression>, env: Env) -> Function {
+        Function { params, body, env }
+    }
+
+    pub fn params(&self) -> &[OwnedSymbol] {
+        &self.params
+    }
+
+    pub fn body(&self) -> &Expression {
+        &self.body
+    }
+
+    /// The variables captured when this function was created.
+    pub fn env(&self) -> &Env {
+        &self.env
+    }
+
+    pub fn from_owned(val: &OwnedFunction, interpreter: &mut Interpreter) -> Function {
+        (val, interpreter).into()
+    }
+}
+
+impl<'a, 'b> From<(&'a OwnedFunction, &'b mut Interpreter)> for Function {
+    fn from((val, i): (&'a OwnedFunction, &'b mut Interpreter)) -> Function {
+        Function {
+            params: val.params.clone(),
+            body: Arc::new((*val.body).clone()),
+            env: val
+                .captures
+                .iter()
+                .map(|(name, val)| (name.clone(), Value::from_owned(val, &mut *i)))
+                .collect(),
+        }
+    }
+}
+
+impl Display for Function {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "fn(")?;
+        let mut first = true;
+        for p in &self.params {
+            if !first {
+                write!(f, ", ")?;
+            } else {
+                first = false;
+            }
+            write!(f, "{}", p)?;
+        }
+        write!(f, ")")
+    }
+}
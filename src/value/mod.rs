@@ -25,6 +25,7 @@
 //! not.
 
 pub mod array;
+pub mod function;
 pub mod object;
 pub mod symbol;
 pub mod value;
@@ -32,11 +33,13 @@ pub mod value;
 pub mod owned;
 
 pub use self::array::Array;
+pub use self::function::Function;
 pub use self::object::{get_null, Object};
-pub use self::symbol::Symbol;
+pub use self::symbol::{ParseSymbolError, Symbol, SymbolError};
 pub use self::value::Value;
 
 pub use self::owned::array::OwnedArray;
+pub use self::owned::function::OwnedFunction;
 pub use self::owned::object::OwnedObject;
 pub use self::owned::symbol::OwnedSymbol;
 pub use self::owned::value::OwnedValue;
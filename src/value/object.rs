@@ -176,6 +176,11 @@ impl Object {
         self.0.len()
     }
 
+    /// Iterates over the entries of this object, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (Arc<Value>, Arc<Value>)> {
+        self.0.iter()
+    }
+
     pub fn keys(&self) -> Keys<Value, Value> {
         self.0.keys()
     }
@@ -0,0 +1,87 @@
+/*
+ * Dermis is an interpreter for a pure, statically typed, imperitive language designed to be edited with a custom IDE.
+ * Copyright (C) 2018 Amelorate
+ *
+ * This program is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * This program is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Owned version of [`dermis::value::Function`](::value::Function)
+
+use std::convert::From;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+
+use ast::expression::Expression;
+use value::owned::symbol::OwnedSymbol;
+use value::owned::value::OwnedValue;
+use value::Function;
+
+/// Owned version of [`dermis::value::Function`](::value::Function)
+///
+/// The captured variables are stored sorted by name, so equal functions always serialize the
+/// same way.
+#[derive(Ord, PartialOrd, PartialEq, Eq, Hash, Debug, Clone, Serialize, Deserialize)]
+pub struct OwnedFunction {
+    pub(crate) params: Vec<OwnedSymbol>,
+    pub(crate) body: Box<Expression>,
+    pub(crate) captures: Vec<(OwnedSymbol, OwnedValue)>,
+}
+
+impl OwnedFunction {
+    pub fn params(&self) -> &[OwnedSymbol] {
+        &self.params
+    }
+
+    pub fn body(&self) -> &Expression {
+        &self.body
+    }
+
+    /// The variables captured when this function was created, sorted by name.
+    pub fn captures(&self) -> &[(OwnedSymbol, OwnedValue)] {
+        &self.captures
+    }
+}
+
+impl From<Function> for OwnedFunction {
+    fn from(fun: Function) -> OwnedFunction {
+        let mut captures: Vec<(OwnedSymbol, OwnedValue)> = fun
+            .env
+            .iter()
+            .map(|(name, val)| ((*name).clone(), OwnedValue::from((*val).clone())))
+            .collect();
+        captures.sort();
+
+        OwnedFunction {
+            params: fun.params,
+            body: Box::new((*fun.body).clone()),
+            captures,
+        }
+    }
+}
+
+impl Display for OwnedFunction {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "fn(")?;
+        let mut first = true;
+        for p in &self.params {
+            if !first {
+                write!(f, ", ")?;
+            } else {
+                first = false;
+            }
+            write!(f, "{}", p)?;
+        }
+        write!(f, ")")
+    }
+}
@@ -20,6 +20,7 @@
 //! interpreter that created the values.
 
 pub mod array;
+pub mod function;
 pub mod object;
 pub mod symbol;
 pub mod value;
@@ -17,9 +17,13 @@
  */
 
 //! Owned version of [`dermis::value::Object`](::value::Object)
+//!
+//! An [`OwnedObject`](OwnedObject) is serialized as a sequence of pairs rather than as a map, so
+//! a serialized object is not a JSON object.
 
 use im::hashmap::{Keys, Values};
 use im::HashMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::BTreeMap;
 use std::convert::From;
 use std::sync::Arc;
@@ -33,10 +37,27 @@ pub fn get_null_owned() -> Arc<OwnedValue> {
 }
 
 /// Owned version of [`dermis::value::Object`](::value::Object)
-#[derive(Ord, PartialOrd, PartialEq, Eq, Hash, Debug, Clone, Default, From, Into, Serialize,
-         Deserialize)]
+///
+/// Serializes as a sequence of key and value pairs sorted by key, since keys are not always
+/// strings and formats such as JSON only allow string keys.
+#[derive(Ord, PartialOrd, PartialEq, Eq, Hash, Debug, Clone, Default, From, Into)]
 pub struct OwnedObject(pub HashMap<OwnedValue, OwnedValue>);
 
+impl Serialize for OwnedObject {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        let mut pairs: Vec<(Arc<OwnedValue>, Arc<OwnedValue>)> = self.0.iter().collect();
+        pairs.sort();
+        s.collect_seq(pairs.iter().map(|(k, v)| (&**k, &**v)))
+    }
+}
+
+impl<'de> Deserialize<'de> for OwnedObject {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<OwnedObject, D::Error> {
+        let pairs: Vec<(OwnedValue, OwnedValue)> = Vec::deserialize(d)?;
+        Ok(OwnedObject(pairs.into_iter().collect()))
+    }
+}
+
 impl From<Object> for OwnedObject {
     fn from(obj: Object) -> OwnedObject {
         OwnedObject(
@@ -102,6 +123,11 @@ impl OwnedObject {
         self.0.len()
     }
 
+    /// Iterates over the entries of this object, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (Arc<OwnedValue>, Arc<OwnedValue>)> {
+        self.0.iter()
+    }
+
     pub fn keys(&self) -> Keys<OwnedValue, OwnedValue> {
         self.0.keys()
     }
@@ -299,6 +325,7 @@ impl OwnedObject {
 #[cfg(test)]
 mod test {
     use super::*;
+    use serde_json;
 
     #[test]
     fn owned_object_from_btree() {
@@ -325,4 +352,24 @@ mod test {
         assert_eq!(*tree.get(&"a".into()).unwrap(), 12.0.into());
         assert_eq!(*tree.get(&"b".into()).unwrap(), 2.0.into());
     }
+
+    #[test]
+    fn owned_object_serde_json() {
+        let mut obj = OwnedObject::empty();
+        obj.set_mut(symbol_o!(b).into(), 2.0.into());
+        obj.set_mut(
+            "a".into(),
+            OwnedObject::singleton(1.0.into(), 1.0.into()).into(),
+        );
+
+        // A sequence of pairs, sorted by key.
+        let json = concat!(
+            r#"[[{"String":"a"},{"Object":[[{"Number":{"value":1.0,"phantom":null}},"#,
+            r#"{"Number":{"value":1.0,"phantom":null}}]]}],"#,
+            r#"[{"Symbol":{"Global":{"name":"b"}}},{"Number":{"value":2.0,"phantom":null}}]]"#
+        );
+
+        assert_eq!(serde_json::to_string(&obj).unwrap(), json);
+        assert_eq!(serde_json::from_str::<OwnedObject>(json).unwrap(), obj);
+    }
 }
@@ -21,8 +21,15 @@
 use std::convert::From;
 use std::fmt;
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
 
 use value::symbol::format::SymbolFormat;
+use value::symbol::{
+    self, AnonymousSymbol, GlobalSymbol, LocalSymbol, ParseSymbolError, SymbolError, NO_NAME,
+};
 use value::Symbol;
 
 /// Provides an owned version of [`dermis::value::symbol::Symbol`](::value::Symbol).
@@ -48,64 +55,147 @@ use value::Symbol;
 ///
 /// assert_eq!(another_symbol.get_name(), "bar");
 /// ```
+///
+/// Deserializing a symbol fails if any of its names are not valid, see
+/// [`check_name`](::value::symbol::check_name).
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum OwnedSymbol {
     Local(LocalOwnedSymbol),
     Global(GlobalOwnedSymbol),
+    Anonymous(AnonymousOwnedSymbol),
 }
 
 /// A symbol scoped to a path.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
 pub struct LocalOwnedSymbol {
     pub(crate) name: String,
     pub(crate) namespace: Box<OwnedSymbol>,
 }
 
 /// A symbol that is located in the global namespace.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
 pub struct GlobalOwnedSymbol {
     pub(crate) name: String,
 }
 
+/// A symbol without a name, see [`Interpreter::gensym`](::Interpreter::gensym).
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct AnonymousOwnedSymbol {
+    pub(crate) id: u64,
+}
+
+/// The fields of a [`LocalOwnedSymbol`](LocalOwnedSymbol), before its name is checked.
+#[derive(Deserialize)]
+#[serde(rename = "LocalOwnedSymbol")]
+struct LocalFields {
+    name: String,
+    namespace: Box<OwnedSymbol>,
+}
+
+/// The fields of a [`GlobalOwnedSymbol`](GlobalOwnedSymbol), before its name is checked.
+#[derive(Deserialize)]
+#[serde(rename = "GlobalOwnedSymbol")]
+struct GlobalFields {
+    name: String,
+}
+
+impl<'de> Deserialize<'de> for LocalOwnedSymbol {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<LocalOwnedSymbol, D::Error> {
+        let LocalFields { name, namespace } = LocalFields::deserialize(d)?;
+        match OwnedSymbol::try_new_local(name.clone(), *namespace) {
+            Ok(OwnedSymbol::Local(sym)) => Ok(sym),
+            Ok(_) => unreachable!("try_new_local always creates a local symbol"),
+            Err(err) => Err(invalid_name(&name, err)),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for GlobalOwnedSymbol {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<GlobalOwnedSymbol, D::Error> {
+        let GlobalFields { name } = GlobalFields::deserialize(d)?;
+        match OwnedSymbol::try_new_global(name.clone()) {
+            Ok(OwnedSymbol::Global(sym)) => Ok(sym),
+            Ok(_) => unreachable!("try_new_global always creates a global symbol"),
+            Err(err) => Err(invalid_name(&name, err)),
+        }
+    }
+}
+
+fn invalid_name<E: Error>(name: &str, err: SymbolError) -> E {
+    E::custom(format_args!(
+        "{:?} is not a valid symbol name: {}",
+        name, err
+    ))
+}
+
 impl OwnedSymbol {
     /// Creates a new symbol.
     ///
     /// # Panics
-    /// `name` contained a space. This limitation is in place to ease the creation of an input
-    /// method for an IDE.
+    /// `name` is not a valid name, see [`check_name`](::value::symbol::check_name).
     pub fn new_global(name: String) -> OwnedSymbol {
-        if name.contains(" ") {
-            panic!(
-                "Symbols can not contain spaces but symbol {} contained a space.",
-                name
-            );
-        }
-
-        OwnedSymbol::Global(GlobalOwnedSymbol { name })
+        OwnedSymbol::try_new_global(name).unwrap_or_else(|err| panic!("{}", err))
     }
 
     /// Creates a new symbol in the given namespace. See [`Symbol::new_local`](Symbol::new_local) for more info.
     ///
     /// # Panics
-    /// `name` contained a space.
+    /// `name` is not a valid name.
     pub fn new_local(name: String, namespace: OwnedSymbol) -> OwnedSymbol {
-        if name.contains(" ") {
-            panic!(
-                "Symbols can not contain spaces but symbol {} contained a space.",
-                name
-            );
-        }
+        OwnedSymbol::try_new_local(name, namespace).unwrap_or_else(|err| panic!("{}", err))
+    }
 
-        OwnedSymbol::Local(LocalOwnedSymbol {
+    /// Creates a new symbol, or returns an error if `name` is not a valid name.
+    ///
+    /// # Example
+    /// ```
+    /// use dermis::value::{OwnedSymbol, SymbolError};
+    ///
+    /// assert!(OwnedSymbol::try_new_global("foo".to_string()).is_ok());
+    /// assert_eq!(
+    ///     OwnedSymbol::try_new_global("".to_string()),
+    ///     Err(SymbolError::Empty)
+    /// );
+    /// ```
+    pub fn try_new_global(name: String) -> Result<OwnedSymbol, SymbolError> {
+        symbol::check_name(&name)?;
+        Ok(OwnedSymbol::Global(GlobalOwnedSymbol { name }))
+    }
+
+    /// Creates a new symbol in the given namespace, or returns an error if `name` is not a valid
+    /// name.
+    pub fn try_new_local(name: String, namespace: OwnedSymbol) -> Result<OwnedSymbol, SymbolError> {
+        symbol::check_name(&name)?;
+        Ok(OwnedSymbol::Local(LocalOwnedSymbol {
             name,
             namespace: Box::new(namespace),
-        })
+        }))
     }
 
     pub fn get_name(&self) -> &String {
         match self {
             OwnedSymbol::Local(LocalOwnedSymbol { name, namespace: _ }) => &name,
             OwnedSymbol::Global(GlobalOwnedSymbol { name }) => &name,
+            OwnedSymbol::Anonymous(_) => &NO_NAME,
+        }
+    }
+
+    /// Returns the namespace this symbol is in, or `None` if it is a global symbol.
+    ///
+    /// # Example
+    /// ```
+    /// #[macro_use]
+    /// extern crate dermis;
+    ///
+    /// # fn main() {
+    /// assert_eq!(symbol_o!(foo;bar).get_namespace(), Some(&symbol_o!(foo)));
+    /// assert_eq!(symbol_o!(foo).get_namespace(), None);
+    /// # }
+    /// ```
+    pub fn get_namespace(&self) -> Option<&OwnedSymbol> {
+        match self {
+            OwnedSymbol::Local(local) => Some(local.get_parent_namespace()),
+            OwnedSymbol::Global(_) | OwnedSymbol::Anonymous(_) => None,
         }
     }
 }
@@ -123,7 +213,53 @@ impl<'a> From<&'a OwnedSymbol> for SymbolFormat<'a> {
                 SymbolFormat::Local(&name, Box::new((&**namespace).into()))
             }
             OwnedSymbol::Global(GlobalOwnedSymbol { name }) => SymbolFormat::Global(&name),
+            OwnedSymbol::Anonymous(_) => SymbolFormat::Anonymous,
+        }
+    }
+}
+
+/// Reads a symbol from the text it is displayed as, such as `'foo::bar`.
+///
+/// The text is a `'` followed by the names of the symbol's namespaces and then it's own name,
+/// separated by `::`, and each name must be valid, see
+/// [`check_name`](::value::symbol::check_name). This is exactly the text a symbol is displayed
+/// as, except for anonymous symbols, which are all displayed as `'_`.
+///
+/// # Example
+/// ```
+/// #[macro_use]
+/// extern crate dermis;
+/// use dermis::value::{OwnedSymbol, ParseSymbolError, SymbolError};
+///
+/// # fn main() {
+/// assert_eq!("'foo::bar".parse(), Ok(symbol_o!(foo;bar)));
+/// assert_eq!(symbol_o!(foo).to_string().parse(), Ok(symbol_o!(foo)));
+/// assert_eq!(
+///     "'foo::".parse::<OwnedSymbol>(),
+///     Err(ParseSymbolError::Invalid(SymbolError::Empty))
+/// );
+/// assert_eq!(
+///     "foo".parse::<OwnedSymbol>(),
+///     Err(ParseSymbolError::MissingQuote)
+/// );
+/// # }
+/// ```
+impl FromStr for OwnedSymbol {
+    type Err = ParseSymbolError;
+
+    fn from_str(s: &str) -> Result<OwnedSymbol, ParseSymbolError> {
+        if !s.starts_with('\'') {
+            return Err(ParseSymbolError::MissingQuote);
         }
+        let mut names = s[1..].split("::");
+        let first = names
+            .next()
+            .expect("split always returns at least one string");
+        let symbol = names.try_fold(
+            OwnedSymbol::try_new_global(first.to_string())?,
+            |ns, name| OwnedSymbol::try_new_local(name.to_string(), ns),
+        )?;
+        Ok(symbol)
     }
 }
 
@@ -135,6 +271,51 @@ impl Display for OwnedSymbol {
 
 impl From<Symbol> for OwnedSymbol {
     fn from(val: Symbol) -> OwnedSymbol {
-        OwnedSymbol::new_global(val.get_name().to_string()) // TODO: Fix for locals
+        match val {
+            Symbol::Global(GlobalSymbol { name, .. }) => OwnedSymbol::Global(GlobalOwnedSymbol {
+                name: (*name).clone(),
+            }),
+            Symbol::Local(LocalSymbol {
+                name, namespace, ..
+            }) => OwnedSymbol::Local(LocalOwnedSymbol {
+                name: (*name).clone(),
+                namespace: Box::new((*namespace).into()),
+            }),
+            Symbol::Anonymous(AnonymousSymbol { id, .. }) => {
+                OwnedSymbol::Anonymous(AnonymousOwnedSymbol { id })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json;
+
+    #[test]
+    fn deserialize_symbol() {
+        let sym: OwnedSymbol = serde_json::from_str(
+            r#"{"Local":{"name":"bar","namespace":{"Global":{"name":"foo"}}}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(sym, symbol_o!(foo;bar));
+    }
+
+    #[test]
+    fn deserialize_invalid_global() {
+        let err = serde_json::from_str::<OwnedSymbol>(r#"{"Global":{"name":""}}"#).unwrap_err();
+
+        assert!(err.to_string().contains("symbol names can not be empty"));
+    }
+
+    #[test]
+    fn deserialize_invalid_local() {
+        let name = r#"{"Local":{"name":"a b","namespace":{"Global":{"name":"foo"}}}}"#;
+        let namespace = r#"{"Local":{"name":"bar","namespace":{"Global":{"name":"_"}}}}"#;
+
+        assert!(serde_json::from_str::<OwnedSymbol>(name).is_err());
+        assert!(serde_json::from_str::<OwnedSymbol>(namespace).is_err());
     }
 }
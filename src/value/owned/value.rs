@@ -25,9 +25,10 @@ use std::fmt::{Display, Formatter};
 use decorum::N64;
 
 use value::owned::array::OwnedArray;
+use value::owned::function::OwnedFunction;
 use value::owned::object::OwnedObject;
 use value::owned::symbol::OwnedSymbol;
-use value::{Array, Object, Symbol, Value};
+use value::{Array, Function, Object, Symbol, Value};
 
 /// Owned version of [`dermis::value::Value`](::value::Value)
 ///
@@ -41,6 +42,7 @@ pub enum OwnedValue {
     Symbol(OwnedSymbol),
     Array(OwnedArray),
     Object(OwnedObject),
+    Function(OwnedFunction),
 }
 
 impl Display for OwnedValue {
@@ -77,6 +79,8 @@ impl Display for OwnedValue {
                 }
                 write!(f, "}}")
             }
+
+            OwnedValue::Function(ref fun) => write!(f, "{}", fun),
         }
     }
 }
@@ -89,6 +93,7 @@ impl From<Value> for OwnedValue {
             Value::Symbol(val) => OwnedValue::Symbol(val.into()),
             Value::Array(val) => OwnedValue::Array(val.into()),
             Value::Object(val) => OwnedValue::Object(val.into()),
+            Value::Function(val) => OwnedValue::Function(val.into()),
         }
     }
 }
@@ -99,6 +104,16 @@ impl From<f64> for OwnedValue {
     }
 }
 
+/// Converts to the global symbol `'true` or `'false`.
+///
+/// See [`Value::is_truthy`](::value::Value::is_truthy).
+impl From<bool> for OwnedValue {
+    fn from(val: bool) -> OwnedValue {
+        let name = if val { "true" } else { "false" };
+        OwnedValue::Symbol(OwnedSymbol::new_global(name.to_string()))
+    }
+}
+
 impl<'a> From<&'a str> for OwnedValue {
     fn from(val: &'a str) -> OwnedValue {
         OwnedValue::String(val.to_string())
@@ -117,6 +132,12 @@ impl From<Array> for OwnedValue {
     }
 }
 
+impl From<Function> for OwnedValue {
+    fn from(val: Function) -> OwnedValue {
+        OwnedValue::Function(val.into())
+    }
+}
+
 impl From<Symbol> for OwnedValue {
     fn from(val: Symbol) -> OwnedValue {
         OwnedValue::Symbol(val.into())
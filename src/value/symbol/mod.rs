@@ -18,15 +18,16 @@
 
 use std::cmp::Ordering;
 use std::convert::From;
+use std::error;
 use std::fmt;
 use std::fmt::{Display, Formatter};
 use std::hash::{Hash, Hasher};
 use std::sync::{Arc, RwLock, Weak};
 
 use self::format::SymbolFormat;
-use value::owned::symbol::LocalOwnedSymbol;
+use value::owned::symbol::{AnonymousOwnedSymbol, LocalOwnedSymbol};
 use value::OwnedSymbol;
-use {Interpreter, SymbolTable};
+use {Error, Interpreter, SymbolTable};
 
 pub(crate) mod format;
 
@@ -54,6 +55,7 @@ pub(crate) mod format;
 pub enum Symbol {
     Local(LocalSymbol),
     Global(GlobalSymbol),
+    Anonymous(AnonymousSymbol),
 }
 
 #[derive(Debug, Clone)]
@@ -69,6 +71,16 @@ pub struct GlobalSymbol {
     pub(crate) symbol_table: Weak<RwLock<SymbolTable>>,
 }
 
+/// A symbol without a name, see [`Interpreter::gensym`](::Interpreter::gensym).
+#[derive(Debug, Clone)]
+pub struct AnonymousSymbol {
+    pub(crate) id: u64,
+    pub(crate) symbol_table: Weak<RwLock<SymbolTable>>,
+}
+
+/// The name of an anonymous symbol.
+pub(crate) static NO_NAME: String = String::new();
+
 impl Symbol {
     /// Returns a new symbol in the global namespace.
     ///
@@ -94,39 +106,57 @@ impl Symbol {
     /// ```
     ///
     /// # Panics
-    /// `name` contained a space. This limitation is in place to ease the creation of an input
-    /// method for an IDE.
+    /// [`Symbol::try_new_global`](Symbol::try_new_global) would have returned an error.
     pub fn new_global(name: String, interpreter: &mut Interpreter) -> Symbol {
-        if name.contains(" ") {
-            panic!(
-                "Symbols can not contain spaces but symbol {} contained a space",
-                name
-            );
-        }
+        Symbol::try_new_global(name, interpreter).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Returns a new symbol in the global namespace, or an error instead of panicking.
+    ///
+    /// # Errors
+    /// [`Error::InvalidSymbol`](::Error::InvalidSymbol) if `name` is not valid, see
+    /// [`check_name`](check_name), or [`Error::PoisonedInterpreter`](::Error::PoisonedInterpreter).
+    ///
+    /// # Example
+    /// ```
+    /// use dermis::value::symbol::{Symbol, SymbolError};
+    /// use dermis::{Error, Interpreter};
+    ///
+    /// let mut interpreter = Interpreter::new();
+    ///
+    /// assert!(Symbol::try_new_global("a".to_string(), &mut interpreter).is_ok());
+    /// assert_eq!(
+    ///     Symbol::try_new_global("a::b".to_string(), &mut interpreter),
+    ///     Err(Error::InvalidSymbol {
+    ///         name: "a::b".to_string(),
+    ///         error: SymbolError::InvalidChar(':'),
+    ///     })
+    /// );
+    /// ```
+    pub fn try_new_global(name: String, interpreter: &mut Interpreter) -> Result<Symbol, Error> {
+        validate(&name)?;
 
         let mut name_a: Option<Arc<String>> = interpreter
             .symbol_table
-            .read()
-            .expect(&format!("lock poisoned while creating symbol {}", &name))
+            .read()?
             .global_symbols
             .iter()
             .find(|n| ***n == name)
-            .map(|n| n.clone());
+            .cloned();
 
         if name_a.is_none() {
-            name_a = Some(Arc::new(name.clone()));
+            name_a = Some(Arc::new(name));
             interpreter
                 .symbol_table
-                .write()
-                .expect(&format!("lock poisoned while creating symbol {}", &name))
+                .write()?
                 .global_symbols
                 .push(name_a.clone().unwrap());
         }
 
-        Symbol::Global(GlobalSymbol {
+        Ok(Symbol::Global(GlobalSymbol {
             name: name_a.unwrap(),
             symbol_table: Arc::downgrade(&interpreter.symbol_table),
-        })
+        }))
     }
 
     /// Creates a symbol local to it's namespace. Two symbols that share a name but not a namespace
@@ -150,42 +180,48 @@ impl Symbol {
     /// assert_eq!(local_a.get_name(), local_b.get_name());
     /// assert_ne!(local_a, local_b);
     /// ```
+    ///
+    /// # Panics
+    /// [`Symbol::try_new_local`](Symbol::try_new_local) would have returned an error.
     pub fn new_local(name: String, namespace: Symbol, interpreter: &mut Interpreter) -> Symbol {
-        if name.contains(" ") {
-            panic!(
-                "Symbols can not contain spaces but symbol {} contained a space",
-                name
-            );
+        Symbol::try_new_local(name, namespace, interpreter).unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Creates a symbol local to it's namespace, or returns an error instead of panicking.
+    ///
+    /// # Errors
+    /// The errors of [`Symbol::try_new_global`](Symbol::try_new_global), or
+    /// [`Error::DroppedInterpreter`](::Error::DroppedInterpreter) if the interpreter of
+    /// `namespace` was dropped.
+    pub fn try_new_local(
+        name: String,
+        namespace: Symbol,
+        interpreter: &mut Interpreter,
+    ) -> Result<Symbol, Error> {
+        validate(&name)?;
+        if namespace.symbol_table().upgrade().is_none() {
+            return Err(Error::DroppedInterpreter);
         }
 
-        let mut name_a: Option<Arc<String>> = interpreter
-            .symbol_table
-            .write()
-            .expect(&format!("lock poisoned while creating symbol {}", &name))
+        let mut table = interpreter.symbol_table.write()?;
+        let names = table
             .symbols
             .entry(namespace.clone())
-            .or_insert_with(|| vec![])
-            .iter()
-            .find(|n| ***n == name)
-            .map(|n| n.clone());
-
-        if name_a.is_none() {
-            name_a = Some(Arc::new(name.clone()));
-            interpreter
-                    .symbol_table
-                    .write()
-                    .expect(&format!("lock poisoned while creating symbol {}", &name))
-                    .symbols
-                    .get_mut(&namespace)
-                    .expect("symbol table namespace lookup should have been some") // Above will always set it to an empty vec if None.
-                    .push(name_a.clone().unwrap());
-        }
-
-        Symbol::Local(LocalSymbol {
-            name: name_a.unwrap(),
+            .or_insert_with(Vec::new);
+        let name_a = match names.iter().find(|n| ***n == name) {
+            Some(n) => n.clone(),
+            None => {
+                let n = Arc::new(name);
+                names.push(n.clone());
+                n
+            }
+        };
+
+        Ok(Symbol::Local(LocalSymbol {
+            name: name_a,
             namespace: Box::new(namespace),
             symbol_table: Arc::downgrade(&interpreter.symbol_table),
-        })
+        }))
     }
 
     /// Converts from an owned symbol.
@@ -193,7 +229,65 @@ impl Symbol {
         (owned, interpreter).into()
     }
 
-    /// Returns the name of the symbol.
+    /// Converts from an owned symbol, or returns an error instead of panicking.
+    ///
+    /// # Errors
+    /// The errors of [`Symbol::try_new_local`](Symbol::try_new_local), or
+    /// [`Error::AnonymousSymbolsExhausted`](::Error::AnonymousSymbolsExhausted) if `owned` is,
+    /// or is in the namespace of, an anonymous symbol with the id `u64::MAX`, which is never
+    /// generated.
+    pub fn try_from_owned(
+        owned: &OwnedSymbol,
+        interpreter: &mut Interpreter,
+    ) -> Result<Symbol, Error> {
+        match owned {
+            OwnedSymbol::Global(_) => Symbol::try_new_global(owned.get_name().clone(), interpreter),
+            OwnedSymbol::Local(LocalOwnedSymbol { name, namespace }) => {
+                let namespace = Symbol::try_from_owned(namespace, interpreter)?;
+                Symbol::try_new_local(name.clone(), namespace, interpreter)
+            }
+            OwnedSymbol::Anonymous(AnonymousOwnedSymbol { id }) => {
+                // Symbols generated later must not be equal to this one.
+                let next = id.checked_add(1).ok_or(Error::AnonymousSymbolsExhausted)?;
+                interpreter.next_anonymous = interpreter.next_anonymous.max(next);
+                Ok(Symbol::Anonymous(AnonymousSymbol {
+                    id: *id,
+                    symbol_table: Arc::downgrade(&interpreter.symbol_table),
+                }))
+            }
+        }
+    }
+
+    /// Reads a symbol from the text it is displayed as, such as `'foo::bar`.
+    ///
+    /// See [`OwnedSymbol::from_str`](::value::OwnedSymbol::from_str).
+    ///
+    /// # Errors
+    /// [`Error::ParseSymbol`](::Error::ParseSymbol) if `s` is not the text of a symbol, or the
+    /// errors of [`Symbol::try_new_local`](Symbol::try_new_local).
+    ///
+    /// # Example
+    /// ```
+    /// use dermis::value::Symbol;
+    /// use dermis::Interpreter;
+    ///
+    /// let mut interpreter = Interpreter::new();
+    ///
+    /// let symbol = Symbol::parse("'foo::bar", &mut interpreter).unwrap();
+    ///
+    /// assert_eq!(symbol.get_name(), "bar");
+    /// assert_eq!(symbol.to_string(), "'foo::bar");
+    /// assert!(Symbol::parse("foo::bar", &mut interpreter).is_err());
+    /// ```
+    pub fn parse(s: &str, interpreter: &mut Interpreter) -> Result<Symbol, Error> {
+        let owned: OwnedSymbol = s.parse().map_err(|error| Error::ParseSymbol {
+            text: s.to_string(),
+            error,
+        })?;
+        Symbol::try_from_owned(&owned, interpreter)
+    }
+
+    /// Returns the name of the symbol, which is empty for an anonymous symbol.
     ///
     /// # Example
     /// ```
@@ -219,10 +313,118 @@ impl Symbol {
                 namespace: _,
                 symbol_table: _,
             }) => &name,
+            Symbol::Anonymous(_) => &NO_NAME,
+        }
+    }
+
+    /// The symbol table of the interpreter the symbol was created by.
+    fn symbol_table(&self) -> &Weak<RwLock<SymbolTable>> {
+        match self {
+            Symbol::Global(GlobalSymbol { symbol_table, .. })
+            | Symbol::Local(LocalSymbol { symbol_table, .. })
+            | Symbol::Anonymous(AnonymousSymbol { symbol_table, .. }) => symbol_table,
+        }
+    }
+}
+
+/// Why a name can not be the name of a symbol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolError {
+    /// The name was empty.
+    Empty,
+    /// The name was `_`, which is how an anonymous symbol is written.
+    Reserved,
+    /// The name contained a character that is not allowed, see [`check_name`](check_name).
+    InvalidChar(char),
+}
+
+impl Display for SymbolError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            SymbolError::Empty => write!(f, "symbol names can not be empty"),
+            SymbolError::Reserved => write!(f, "`_` is reserved for anonymous symbols"),
+            SymbolError::InvalidChar(c) => write!(f, "symbol names can not contain {:?}", c),
+        }
+    }
+}
+
+impl error::Error for SymbolError {}
+
+/// Why text is not the text of a symbol, see [`OwnedSymbol::from_str`](OwnedSymbol::from_str).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseSymbolError {
+    /// The text did not start with `'`.
+    MissingQuote,
+    /// One of the names in the text was not valid.
+    Invalid(SymbolError),
+}
+
+impl Display for ParseSymbolError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ParseSymbolError::MissingQuote => write!(f, "symbols start with `'`"),
+            ParseSymbolError::Invalid(err) => write!(f, "{}", err),
         }
     }
 }
 
+impl error::Error for ParseSymbolError {}
+
+impl From<SymbolError> for ParseSymbolError {
+    fn from(err: SymbolError) -> ParseSymbolError {
+        ParseSymbolError::Invalid(err)
+    }
+}
+
+/// Checks that `name` can be the name of a symbol.
+///
+/// A name is valid if it:
+///
+/// * is not empty,
+/// * is not `_`, which is how an anonymous symbol is written,
+/// * does not contain whitespace or control characters, to ease the creation of an input method
+///   for an IDE,
+/// * and does not contain any of `()[]{},;:'"`.
+///
+/// Since a name can not contain `:` or `'`, the text a symbol is displayed as, such as
+/// `'foo::bar`, can be split back into it's names at each `::`. The other characters end a symbol
+/// in the [text syntax](::ast::parse), so a symbol is always read back whole.
+///
+/// # Example
+/// ```
+/// use dermis::value::symbol::{self, SymbolError};
+///
+/// assert_eq!(symbol::check_name("a-b?"), Ok(()));
+/// assert_eq!(symbol::check_name(""), Err(SymbolError::Empty));
+/// assert_eq!(symbol::check_name("_"), Err(SymbolError::Reserved));
+/// assert_eq!(symbol::check_name("a b"), Err(SymbolError::InvalidChar(' ')));
+/// ```
+pub fn check_name(name: &str) -> Result<(), SymbolError> {
+    if name.is_empty() {
+        Err(SymbolError::Empty)
+    } else if name == "_" {
+        Err(SymbolError::Reserved)
+    } else {
+        match name.chars().find(|c| !is_symbol_char(*c)) {
+            Some(c) => Err(SymbolError::InvalidChar(c)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Whether `c` can be part of the name of a symbol.
+pub(crate) fn is_symbol_char(c: char) -> bool {
+    !c.is_whitespace() && !c.is_control() && !"()[]{},;:'\"".contains(c)
+}
+
+/// Checks `name`, as the name of a new symbol.
+fn validate(name: &str) -> Result<(), Error> {
+    check_name(name).map_err(|error| Error::InvalidSymbol {
+        name: name.to_string(),
+        error,
+    })
+}
+
 impl PartialEq for GlobalSymbol {
     fn eq(&self, other: &GlobalSymbol) -> bool {
         self.name == other.name
@@ -276,6 +478,32 @@ impl Ord for LocalSymbol {
     }
 }
 
+impl PartialEq for AnonymousSymbol {
+    fn eq(&self, other: &AnonymousSymbol) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for AnonymousSymbol {}
+
+impl Hash for AnonymousSymbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl PartialOrd for AnonymousSymbol {
+    fn partial_cmp(&self, other: &AnonymousSymbol) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AnonymousSymbol {
+    fn cmp(&self, other: &AnonymousSymbol) -> Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
 impl<'a> From<&'a Symbol> for SymbolFormat<'a> {
     fn from(val: &'a Symbol) -> SymbolFormat<'a> {
         use Symbol::*;
@@ -289,20 +517,63 @@ impl<'a> From<&'a Symbol> for SymbolFormat<'a> {
                 namespace,
                 symbol_table: _,
             }) => SymbolFormat::Local(&name, Box::new((&**namespace).into())),
+            Anonymous(_) => SymbolFormat::Anonymous,
         }
     }
 }
 
 impl<'a, 'b> From<(&'a OwnedSymbol, &'b mut Interpreter)> for Symbol {
     fn from((val, i): (&'a OwnedSymbol, &'b mut Interpreter)) -> Symbol {
-        use value::owned::symbol::OwnedSymbol::*;
-        match val {
-            Global(_) => Symbol::new_global(val.get_name().clone(), i),
-            Local(LocalOwnedSymbol {
-                ref name,
-                namespace,
-            }) => Symbol::new_local(name.clone(), (&**namespace, &mut *i).into(), i),
-        }
+        Symbol::try_from_owned(val, i).unwrap_or_else(|err| panic!("{}", err))
+    }
+}
+
+impl Interpreter {
+    /// Creates a new anonymous symbol, which is not equal to any other symbol of this
+    /// interpreter.
+    ///
+    /// Anonymous symbols are used where a name must not clash with any other, such as for the
+    /// variables introduced by a macro, or a binding the IDE has not been given a name for yet.
+    /// An anonymous symbol has an empty name and is displayed as `'_`, so unlike other symbols it
+    /// can not be read back from it's text.
+    ///
+    /// # Example
+    /// ```
+    /// use dermis::value::Symbol;
+    /// use dermis::Interpreter;
+    ///
+    /// let mut interpreter = Interpreter::new();
+    ///
+    /// let a = interpreter.gensym();
+    /// let b = interpreter.gensym();
+    ///
+    /// assert_ne!(a, b);
+    /// assert_eq!(a, a.clone());
+    /// assert_eq!(a.to_string(), "'_");
+    ///
+    /// let local = Symbol::new_local("x".to_string(), a, &mut interpreter);
+    /// assert_eq!(local.to_string(), "'_::x");
+    /// ```
+    ///
+    /// # Panics
+    /// [`Interpreter::try_gensym`](Interpreter::try_gensym) would have returned an error.
+    pub fn gensym(&mut self) -> Symbol {
+        self.try_gensym().unwrap_or_else(|err| panic!("{}", err))
+    }
+
+    /// Creates a new anonymous symbol, or returns an error instead of panicking.
+    ///
+    /// # Errors
+    /// [`Error::AnonymousSymbolsExhausted`](::Error::AnonymousSymbolsExhausted) if every id has
+    /// been used, which can only happen after reading in an anonymous symbol with a very large
+    /// id.
+    pub fn try_gensym(&mut self) -> Result<Symbol, Error> {
+        let id = self.next_anonymous;
+        self.next_anonymous = id.checked_add(1).ok_or(Error::AnonymousSymbolsExhausted)?;
+        Ok(Symbol::Anonymous(AnonymousSymbol {
+            id,
+            symbol_table: Arc::downgrade(&self.symbol_table),
+        }))
     }
 }
 
@@ -315,6 +586,7 @@ impl Display for Symbol {
 #[cfg(test)]
 mod test {
     use super::*;
+    use std::thread;
 
     #[test]
     fn symbol_from_owned_global() {
@@ -345,4 +617,178 @@ mod test {
 
         assert_eq!(s.to_string(), owned.to_string());
     }
+
+    #[test]
+    fn symbol_with_space() {
+        let mut i = Interpreter::new();
+
+        assert_eq!(
+            Symbol::try_new_global("a b".to_string(), &mut i),
+            Err(Error::InvalidSymbol {
+                name: "a b".to_string(),
+                error: SymbolError::InvalidChar(' '),
+            })
+        );
+    }
+
+    #[test]
+    fn parse_display() {
+        let mut i = Interpreter::new();
+        for owned in &[symbol_o!(foo), symbol_o!(foo;bar;baz)] {
+            let text = owned.to_string();
+
+            assert_eq!(text.parse::<OwnedSymbol>().as_ref(), Ok(owned));
+            assert_eq!(
+                Symbol::parse(&text, &mut i),
+                Ok(Symbol::from_owned(owned, &mut i))
+            );
+        }
+
+        let sym = OwnedSymbol::new_local("c.d".to_string(), symbol_o!(a));
+        assert_eq!(sym.to_string().parse(), Ok(sym));
+    }
+
+    #[test]
+    fn parse_invalid() {
+        let parse = |s: &str| s.parse::<OwnedSymbol>();
+
+        let invalid = |err| Err(ParseSymbolError::Invalid(err));
+
+        assert_eq!(parse("foo"), Err(ParseSymbolError::MissingQuote));
+        assert_eq!(parse(""), Err(ParseSymbolError::MissingQuote));
+        assert_eq!(parse("'"), invalid(SymbolError::Empty));
+        assert_eq!(parse("'foo::"), invalid(SymbolError::Empty));
+        assert_eq!(parse("'::foo"), invalid(SymbolError::Empty));
+        assert_eq!(parse("'foo:bar"), invalid(SymbolError::InvalidChar(':')));
+        assert_eq!(parse("'foo:::bar"), invalid(SymbolError::InvalidChar(':')));
+        assert_eq!(parse("''foo"), invalid(SymbolError::InvalidChar('\'')));
+        assert_eq!(parse("'foo "), invalid(SymbolError::InvalidChar(' ')));
+        assert_eq!(parse("'_"), invalid(SymbolError::Reserved));
+
+        let mut i = Interpreter::new();
+        assert_eq!(
+            Symbol::parse("'a b", &mut i),
+            Err(Error::ParseSymbol {
+                text: "'a b".to_string(),
+                error: ParseSymbolError::Invalid(SymbolError::InvalidChar(' ')),
+            })
+        );
+        assert_eq!(
+            Error::ParseSymbol {
+                text: "a".to_string(),
+                error: ParseSymbolError::MissingQuote,
+            }
+            .to_string(),
+            "\"a\" is not the text of a symbol: symbols start with `'`"
+        );
+    }
+
+    #[test]
+    fn gensym_unique() {
+        let mut i = Interpreter::new();
+        let a = i.gensym();
+        let b = i.gensym();
+
+        assert_ne!(a, b);
+        assert_eq!(a.get_name(), "");
+        assert_eq!(b.to_string(), "'_");
+        assert_ne!(
+            Symbol::new_local("x".to_string(), a, &mut i),
+            Symbol::new_local("x".to_string(), b, &mut i)
+        );
+    }
+
+    #[test]
+    fn anonymous_to_owned() {
+        let mut i = Interpreter::new();
+        let sym = i.gensym();
+        let local = Symbol::new_local("x".to_string(), sym.clone(), &mut i);
+
+        let owned = OwnedSymbol::from(local.clone());
+        assert_eq!(owned.to_string(), "'_::x");
+        assert_eq!(owned.get_namespace(), Some(&OwnedSymbol::from(sym)));
+        assert_eq!(Symbol::from_owned(&owned, &mut i), local);
+    }
+
+    #[test]
+    fn gensym_after_owned() {
+        let owned = OwnedSymbol::from(Interpreter::new().gensym());
+
+        // Symbols generated by a new interpreter are not equal to the ones read into it.
+        let mut i = Interpreter::new();
+        let read = Symbol::from_owned(&owned, &mut i);
+        assert_ne!(i.gensym(), read);
+    }
+
+    #[test]
+    fn anonymous_symbols_exhausted() {
+        let last = OwnedSymbol::Anonymous(AnonymousOwnedSymbol { id: u64::MAX - 1 });
+        let mut i = Interpreter::new();
+        Symbol::from_owned(&last, &mut i);
+
+        assert_eq!(i.try_gensym(), Err(Error::AnonymousSymbolsExhausted));
+
+        let max = OwnedSymbol::Anonymous(AnonymousOwnedSymbol { id: u64::MAX });
+        let local = OwnedSymbol::new_local("x".to_string(), max.clone());
+        assert_eq!(
+            Symbol::try_from_owned(&max, &mut Interpreter::new()),
+            Err(Error::AnonymousSymbolsExhausted)
+        );
+        assert_eq!(
+            Symbol::try_from_owned(&local, &mut Interpreter::new()),
+            Err(Error::AnonymousSymbolsExhausted)
+        );
+    }
+
+    #[test]
+    fn invalid_names() {
+        assert_eq!(check_name("foo"), Ok(()));
+        assert_eq!(check_name("a-b.c+1!"), Ok(()));
+        assert_eq!(check_name("_a"), Ok(()));
+        assert_eq!(check_name("ñ"), Ok(()));
+        assert_eq!(check_name(""), Err(SymbolError::Empty));
+        assert_eq!(check_name("_"), Err(SymbolError::Reserved));
+        assert_eq!(check_name("a::b"), Err(SymbolError::InvalidChar(':')));
+        assert_eq!(check_name("it's"), Err(SymbolError::InvalidChar('\'')));
+        assert_eq!(check_name("a\tb"), Err(SymbolError::InvalidChar('\t')));
+        assert_eq!(check_name("a\u{7}"), Err(SymbolError::InvalidChar('\u{7}')));
+        assert_eq!(check_name("f(x)"), Err(SymbolError::InvalidChar('(')));
+    }
+
+    #[test]
+    fn valid_names_display_unambiguously() {
+        let mut i = Interpreter::new();
+        let ns = Symbol::try_new_global("a-b".to_string(), &mut i).unwrap();
+        let sym = Symbol::try_new_local("c.d".to_string(), ns, &mut i).unwrap();
+
+        assert_eq!(sym.to_string(), "'a-b::c.d");
+        assert!(Symbol::try_new_local("".to_string(), sym, &mut i).is_err());
+    }
+
+    #[test]
+    fn symbol_poisoned_interpreter() {
+        let mut i = Interpreter::new();
+        let table = i.symbol_table.clone();
+        let _ = thread::spawn(move || {
+            let _lock = table.write().unwrap();
+            panic!("poisoning the symbol table");
+        })
+        .join();
+
+        assert_eq!(
+            Symbol::try_new_global("a".to_string(), &mut i),
+            Err(Error::PoisonedInterpreter)
+        );
+    }
+
+    #[test]
+    fn symbol_dropped_interpreter() {
+        let namespace = Symbol::new_global("foo".to_string(), &mut Interpreter::new());
+        let mut i = Interpreter::new();
+
+        assert_eq!(
+            Symbol::try_new_local("a".to_string(), namespace, &mut i),
+            Err(Error::DroppedInterpreter)
+        );
+    }
 }
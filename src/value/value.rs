@@ -21,7 +21,7 @@ use std::fmt::{Display, Formatter};
 
 use decorum::N64;
 
-use value::{Array, Object, OwnedValue, Symbol};
+use value::{Array, Function, Object, OwnedValue, Symbol};
 use Interpreter;
 
 /// Denotes any basic value possible in Dermis.
@@ -114,6 +114,12 @@ pub enum Value {
     /// let another_obj: Value = obj.clone().into();
     /// ```
     Object(Object),
+
+    /// A function, which can be called with
+    /// [`Expression::Call`](::ast::expression::Expression::Call).
+    ///
+    /// See [`Function`](Function) for more info.
+    Function(Function),
 }
 
 impl Display for Value {
@@ -124,6 +130,7 @@ impl Display for Value {
             Value::Symbol(ref s) => s.fmt(f),
             Value::Array(ref a) => a.fmt(f),
             Value::Object(ref m) => m.fmt(f),
+            Value::Function(ref fun) => fun.fmt(f),
         }
     }
 }
@@ -144,6 +151,53 @@ impl Value {
     pub fn from_owned(val: &OwnedValue, interpreter: &mut Interpreter) -> Value {
         (val, interpreter).into()
     }
+
+    /// Returns true if this value is null, that is an empty object.
+    ///
+    /// See [`get_null`](::value::get_null).
+    ///
+    /// # Example
+    /// ```
+    /// use dermis::value::{Value, get_null};
+    ///
+    /// assert!(get_null().is_null());
+    /// assert!(!Value::from(0.0).is_null());
+    /// ```
+    pub fn is_null(&self) -> bool {
+        match self {
+            Value::Object(ref o) => o.is_empty(),
+            _ => false,
+        }
+    }
+
+    /// Returns true if this value is considered true when used as a condition.
+    ///
+    /// Null and the global symbol `'false` are the only values that are not truthy. Every other
+    /// value, including `0` and `""`, is truthy.
+    ///
+    /// # Example
+    /// ```
+    /// use dermis::value::{Value, get_null};
+    /// use dermis::Interpreter;
+    ///
+    /// let mut interpreter = Interpreter::new();
+    ///
+    /// assert!(Value::from(0.0).is_truthy());
+    /// assert!(Value::from_bool(true, &mut interpreter).is_truthy());
+    /// assert!(!Value::from_bool(false, &mut interpreter).is_truthy());
+    /// assert!(!get_null().is_truthy());
+    /// ```
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Symbol(Symbol::Global(ref s)) => *s.name != "false",
+            _ => !self.is_null(),
+        }
+    }
+
+    /// Returns the canonical boolean value, either the global symbol `'true` or `'false`.
+    pub fn from_bool(val: bool, interpreter: &mut Interpreter) -> Value {
+        Value::from_owned(&val.into(), interpreter)
+    }
 }
 
 impl<'a, 'b> From<(&'a OwnedValue, &'b mut Interpreter)> for Value {
@@ -154,6 +208,7 @@ impl<'a, 'b> From<(&'a OwnedValue, &'b mut Interpreter)> for Value {
             OwnedValue::Symbol(ref sym) => Value::Symbol(Symbol::from_owned(sym, i)),
             OwnedValue::Object(ref obj) => Value::Object(Object::from_owned(obj, i)),
             OwnedValue::Array(ref arra) => Value::Array(Array::from_owned(arra, i)),
+            OwnedValue::Function(ref fun) => Value::Function(Function::from_owned(fun, i)),
         }
     }
 }